@@ -184,6 +184,7 @@ fn run_compile(args: CompileOptions) -> Result<CompileResult, wesl::Error> {
             lazy: args.lazy,
             keep: args.keep,
             features: args.features,
+            ..Default::default()
         })
         .use_sourcemap(args.sourcemap)
         .set_mangler(args.mangler.into())
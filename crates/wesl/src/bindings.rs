@@ -0,0 +1,199 @@
+use std::collections::{BTreeSet, HashMap};
+
+use wgsl_parse::syntax::{
+    AddressSpace, Attribute, Declaration, DeclarationKind, Expression, GlobalDeclaration,
+    LiteralExpression, TranslationUnit,
+};
+
+use crate::CompileResult;
+
+/// Assigns `@group` and `@binding` attributes to resource variables declared without
+/// them. See [`crate::CompileOptions::auto_bindings`].
+///
+/// Each resource gets the next free binding of its group, in declaration order, so the
+/// assignment is deterministic. Bindings taken by explicitly bound resources are skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BindingAllocator {
+    /// The group of resources declared without `@group`. Default: 0.
+    pub group: u32,
+}
+
+impl BindingAllocator {
+    /// An allocator that places unbound resources in `group`.
+    pub fn new(group: u32) -> Self {
+        Self { group }
+    }
+}
+
+/// The `@group` and `@binding` of a resource variable. See [`CompileResult::bindings`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceBinding {
+    pub name: String,
+    pub group: u32,
+    pub binding: u32,
+}
+
+pub(crate) fn literal_u32(expr: &Expression) -> Option<u32> {
+    match expr {
+        Expression::Literal(LiteralExpression::AbstractInt(n)) => u32::try_from(*n).ok(),
+        Expression::Literal(LiteralExpression::I32(n)) => u32::try_from(*n).ok(),
+        Expression::Literal(LiteralExpression::U32(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Module-scope variables in the uniform, storage or handle address spaces.
+fn is_resource(decl: &Declaration) -> bool {
+    matches!(
+        decl.kind,
+        DeclarationKind::Var(
+            None | Some(AddressSpace::Uniform | AddressSpace::Storage(_) | AddressSpace::Handle)
+        )
+    )
+}
+
+fn resources(wgsl: &TranslationUnit) -> impl Iterator<Item = &Declaration> {
+    wgsl.global_declarations
+        .iter()
+        .filter_map(|decl| match decl {
+            GlobalDeclaration::Declaration(decl) if is_resource(decl) => Some(decl),
+            _ => None,
+        })
+}
+
+/// The group and binding attributes of a declaration. `Some(None)` if the attribute is
+/// present but not a literal.
+fn group_binding(decl: &Declaration) -> (Option<Option<u32>>, Option<Option<u32>>) {
+    let group = decl.attributes.iter().find_map(|attr| match attr {
+        Attribute::Group(e) => Some(literal_u32(e)),
+        _ => None,
+    });
+    let binding = decl.attributes.iter().find_map(|attr| match attr {
+        Attribute::Binding(e) => Some(literal_u32(e)),
+        _ => None,
+    });
+    (group, binding)
+}
+
+/// The group and binding of a declaration, if both attributes are present and literals.
+pub(crate) fn literal_group_binding(decl: &Declaration) -> Option<(u32, u32)> {
+    match group_binding(decl) {
+        (Some(Some(group)), Some(Some(binding))) => Some((group, binding)),
+        _ => None,
+    }
+}
+
+pub(crate) fn assign_bindings(wgsl: &mut TranslationUnit, allocator: &BindingAllocator) {
+    let mut taken = HashMap::<u32, BTreeSet<u32>>::new();
+    for decl in resources(wgsl) {
+        if let (Some(Some(group)), Some(Some(binding))) = group_binding(decl) {
+            taken.entry(group).or_default().insert(binding);
+        }
+    }
+
+    for decl in &mut wgsl.global_declarations {
+        let GlobalDeclaration::Declaration(decl) = decl else {
+            continue;
+        };
+        if !is_resource(decl) {
+            continue;
+        }
+        let (group, binding) = match group_binding(decl) {
+            (Some(_), Some(_)) => continue,
+            // bindings that are not literals cannot be allocated around.
+            (Some(None), None) => continue,
+            (Some(Some(group)), None) => (group, None),
+            (None, binding) => (allocator.group, binding),
+        };
+        let taken = taken.entry(group).or_default();
+        let binding = binding.flatten().unwrap_or_else(|| {
+            (0..).find(|b| !taken.contains(b)).unwrap() // safety: the range is infinite
+        });
+        taken.insert(binding);
+
+        let literal = |n: u32| Expression::Literal(LiteralExpression::AbstractInt(n.into())).into();
+        // attributes are displayed in order, `@group` comes before `@binding`.
+        if !decl
+            .attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Group(_)))
+        {
+            let pos = decl
+                .attributes
+                .iter()
+                .position(|attr| matches!(attr, Attribute::Binding(_)))
+                .unwrap_or(decl.attributes.len());
+            decl.attributes
+                .insert(pos, Attribute::Group(literal(group)));
+        }
+        if !decl
+            .attributes
+            .iter()
+            .any(|attr| matches!(attr, Attribute::Binding(_)))
+        {
+            decl.attributes.push(Attribute::Binding(literal(binding)));
+        }
+    }
+}
+
+impl CompileResult {
+    /// The final `@group` and `@binding` of each resource variable of the output, in
+    /// declaration order. It includes bindings assigned by
+    /// [`crate::CompileOptions::auto_bindings`].
+    ///
+    /// Bindings with attributes that are not literals (e.g. referring to a `const`) are
+    /// omitted.
+    pub fn bindings(&self) -> Vec<ResourceBinding> {
+        resources(&self.syntax)
+            .filter_map(|decl| match group_binding(decl) {
+                (Some(Some(group)), Some(Some(binding))) => Some(ResourceBinding {
+                    name: decl.ident.to_string(),
+                    group,
+                    binding,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_auto_bindings() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::buffers::{input, output};
+        @group(0) @binding(0) var<uniform> params: vec4f;
+        @compute @workgroup_size(1) fn main() { output[0] = input[0] * params.x; }"
+            .into(),
+    );
+    resolver.add_module(
+        "buffers",
+        "var<storage> input: array<f32>;
+        var<storage, read_write> output: array<f32>;"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.auto_bindings(BindingAllocator::new(0));
+    let res = compiler.compile("main").unwrap();
+
+    let wgsl = res.to_string();
+    assert!(wgsl.contains("@group(0) @binding(1)\nvar<storage> package_buffers_input:"));
+    assert!(
+        wgsl.contains("@group(0) @binding(2)\nvar<storage, read_write> package_buffers_output:")
+    );
+    let binding = |name: &str, group, binding| ResourceBinding {
+        name: name.to_string(),
+        group,
+        binding,
+    };
+    assert_eq!(
+        res.bindings(),
+        [
+            binding("params", 0, 0),
+            binding("package_buffers_input", 0, 1),
+            binding("package_buffers_output", 0, 2),
+        ]
+    );
+}
@@ -0,0 +1,316 @@
+//! Programmatic construction of [`TranslationUnit`]s.
+//!
+//! Use-case: code generators that would otherwise concatenate WGSL strings. Statements
+//! and expressions can be parsed from strings with [`str::parse`], or built by hand.
+//!
+//! ```rust
+//! # use wesl::builder::{ty, FunctionBuilder, StructBuilder, UnitBuilder};
+//! let wgsl = UnitBuilder::new()
+//!     .struct_(StructBuilder::new("Particle").member("pos", ty("vec3f")))
+//!     .function(
+//!         FunctionBuilder::new("speed")
+//!             .param("p", ty("Particle"))
+//!             .returns(ty("f32"))
+//!             .statement("return length(p.pos);".parse().unwrap()),
+//!     )
+//!     .build();
+//! ```
+
+use wgsl_parse::syntax::{
+    AddressSpace, Attribute, Attributes, CompoundStatement, Declaration, DeclarationKind,
+    Expression, FormalParameter, Function, GlobalDeclaration, Ident, LiteralExpression, Statement,
+    Struct, StructMember, TemplateArg, TranslationUnit, TypeExpression, WorkgroupSizeAttribute,
+};
+
+/// A type expression without template arguments, e.g. `f32` or `MyStruct`.
+pub fn ty(name: &str) -> TypeExpression {
+    TypeExpression::new(Ident::new(name.to_string()))
+}
+
+/// A type expression with template arguments, e.g. `array<f32, 4>`.
+///
+/// Template arguments are expressions. Use [`ty_arg`] for type arguments.
+pub fn ty_with(name: &str, args: impl IntoIterator<Item = Expression>) -> TypeExpression {
+    let mut ty = ty(name);
+    ty.template_args = Some(
+        args.into_iter()
+            .map(|arg| TemplateArg {
+                expression: arg.into(),
+            })
+            .collect(),
+    );
+    ty
+}
+
+/// A type used as a template argument, e.g. the `f32` in `array<f32, 4>`.
+pub fn ty_arg(ty: TypeExpression) -> Expression {
+    Expression::TypeOrIdentifier(ty)
+}
+
+/// Builder for a [`Function`] declaration.
+#[derive(Clone, Debug)]
+pub struct FunctionBuilder {
+    function: Function,
+}
+
+impl FunctionBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            function: Function {
+                attributes: Default::default(),
+                ident: Ident::new(name.to_string()),
+                parameters: Vec::new(),
+                return_attributes: Default::default(),
+                return_type: None,
+                body: CompoundStatement::new(Default::default(), Vec::new()),
+            },
+        }
+    }
+
+    /// Add a formal parameter.
+    pub fn param(self, name: &str, ty: TypeExpression) -> Self {
+        self.param_with(name, ty, Vec::new())
+    }
+
+    /// Add a formal parameter with attributes, e.g. `@builtin(global_invocation_id)`.
+    pub fn param_with(mut self, name: &str, ty: TypeExpression, attributes: Attributes) -> Self {
+        self.function.parameters.push(FormalParameter {
+            attributes,
+            ident: Ident::new(name.to_string()),
+            ty,
+        });
+        self
+    }
+
+    /// Set the return type.
+    pub fn returns(mut self, ty: TypeExpression) -> Self {
+        self.function.return_type = Some(ty);
+        self
+    }
+
+    /// Add a function attribute.
+    pub fn attribute(mut self, attribute: Attribute) -> Self {
+        self.function.attributes.push(attribute);
+        self
+    }
+
+    /// Make this function a compute entrypoint: `@compute @workgroup_size(x, y, z)`.
+    pub fn compute(self, x: u32, y: u32, z: u32) -> Self {
+        let lit = |n: u32| Expression::Literal(LiteralExpression::AbstractInt(n as i64)).into();
+        self.attribute(Attribute::Compute)
+            .attribute(Attribute::WorkgroupSize(WorkgroupSizeAttribute {
+                x: lit(x),
+                y: Some(lit(y)),
+                z: Some(lit(z)),
+            }))
+    }
+
+    /// Append a statement to the function body.
+    pub fn statement(mut self, statement: Statement) -> Self {
+        self.function.body.statements.push(statement.into());
+        self
+    }
+
+    pub fn build(self) -> Function {
+        self.function
+    }
+}
+
+/// Builder for a [`Struct`] declaration.
+#[derive(Clone, Debug)]
+pub struct StructBuilder {
+    struct_: Struct,
+}
+
+impl StructBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            struct_: Struct {
+                attributes: Default::default(),
+                ident: Ident::new(name.to_string()),
+                members: Vec::new(),
+            },
+        }
+    }
+
+    /// Add a member.
+    pub fn member(self, name: &str, ty: TypeExpression) -> Self {
+        self.member_with(name, ty, Vec::new())
+    }
+
+    /// Add a member with attributes, e.g. `@location(0)`.
+    pub fn member_with(mut self, name: &str, ty: TypeExpression, attributes: Attributes) -> Self {
+        self.struct_.members.push(StructMember {
+            attributes,
+            ident: Ident::new(name.to_string()),
+            ty,
+        });
+        self
+    }
+
+    pub fn build(self) -> Struct {
+        self.struct_
+    }
+}
+
+/// Builder for a [`TranslationUnit`].
+///
+/// The resulting translation unit can be compiled like any parsed module, e.g. served by
+/// a [`crate::Resolver`]. Identifiers are created fresh, so call
+/// [`crate::SyntaxUtil::retarget_idents`] before running passes on it directly.
+#[derive(Clone, Debug, Default)]
+pub struct UnitBuilder {
+    unit: TranslationUnit,
+}
+
+impl UnitBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add any module-scope declaration.
+    pub fn declaration(mut self, decl: impl Into<GlobalDeclaration>) -> Self {
+        self.unit.global_declarations.push(decl.into());
+        self
+    }
+
+    /// Add a function declaration.
+    pub fn function(self, function: FunctionBuilder) -> Self {
+        self.declaration(function.build())
+    }
+
+    /// Add a struct declaration.
+    pub fn struct_(self, struct_: StructBuilder) -> Self {
+        self.declaration(struct_.build())
+    }
+
+    /// Add a `const` declaration. The type is optional.
+    pub fn const_(self, name: &str, ty: Option<TypeExpression>, initializer: Expression) -> Self {
+        self.declaration(Declaration {
+            attributes: Default::default(),
+            kind: DeclarationKind::Const,
+            ident: Ident::new(name.to_string()),
+            ty,
+            initializer: Some(initializer.into()),
+        })
+    }
+
+    /// Add a module-scope `var` declaration without initializer.
+    pub fn var(self, name: &str, space: Option<AddressSpace>, ty: TypeExpression) -> Self {
+        self.var_with(name, space, ty, Vec::new())
+    }
+
+    /// Add a resource `var` declaration: `@group(g) @binding(b) var<space> name: ty;`
+    pub fn binding(
+        self,
+        group: u32,
+        binding: u32,
+        name: &str,
+        space: Option<AddressSpace>,
+        ty: TypeExpression,
+    ) -> Self {
+        let lit = |n: u32| Expression::Literal(LiteralExpression::AbstractInt(n as i64)).into();
+        let attributes = vec![
+            Attribute::Group(lit(group)),
+            Attribute::Binding(lit(binding)),
+        ];
+        self.var_with(name, space, ty, attributes)
+    }
+
+    fn var_with(
+        self,
+        name: &str,
+        space: Option<AddressSpace>,
+        ty: TypeExpression,
+        attributes: Attributes,
+    ) -> Self {
+        self.declaration(Declaration {
+            attributes,
+            kind: DeclarationKind::Var(space),
+            ident: Ident::new(name.to_string()),
+            ty: Some(ty),
+            initializer: None,
+        })
+    }
+
+    pub fn build(self) -> TranslationUnit {
+        self.unit
+    }
+}
+
+#[test]
+fn test_unit_builder() {
+    use crate::{syntax::ModulePath, CompileOptions, NoMangler, Resolver};
+    use std::borrow::Cow;
+    use wgsl_parse::syntax::AccessMode;
+
+    let unit = UnitBuilder::new()
+        .struct_(
+            StructBuilder::new("Data")
+                .member("a", ty("f32"))
+                .member("b", ty_with("vec4", [ty_arg(ty("u32"))])),
+        )
+        .const_(
+            "N",
+            Some(ty("u32")),
+            Expression::Literal(LiteralExpression::U32(64)),
+        )
+        .binding(
+            0,
+            0,
+            "buf",
+            Some(AddressSpace::Storage(Some(AccessMode::ReadWrite))),
+            ty_with("array", [ty_arg(ty("Data")), "N".parse().unwrap()]),
+        )
+        .function(
+            FunctionBuilder::new("main")
+                .compute(64, 1, 1)
+                .param_with(
+                    "id",
+                    ty("vec3u"),
+                    vec![Attribute::Builtin(
+                        wgsl_parse::syntax::BuiltinValue::GlobalInvocationId,
+                    )],
+                )
+                .statement("buf[id.x].a *= 2.0;".parse().unwrap()),
+        )
+        .build();
+
+    struct UnitResolver(TranslationUnit);
+    impl Resolver for UnitResolver {
+        fn resolve_source<'a>(
+            &'a self,
+            _: &ModulePath,
+        ) -> Result<Cow<'a, str>, crate::ResolveError> {
+            Ok(self.0.to_string().into())
+        }
+        fn resolve_module(&self, _: &ModulePath) -> Result<TranslationUnit, crate::ResolveError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    let wgsl = crate::compile(
+        &ModulePath::from_path("/main"),
+        &UnitResolver(unit),
+        &NoMangler,
+        &CompileOptions::default(),
+    )
+    .unwrap()
+    .to_string();
+    let expected = "struct Data {
+    a: f32,
+    b: vec4<u32>
+}
+
+const N: u32 = 64u;
+
+@group(0) @binding(0)
+var<storage, read_write> buf: array<Data, N>;
+
+@compute @workgroup_size(64, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3u) {
+    buf[id.x].a *= 2.0;
+}
+";
+    assert_eq!(wgsl, expected);
+}
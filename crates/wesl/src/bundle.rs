@@ -0,0 +1,206 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use wgsl_parse::syntax::{AddressSpace, Attribute, DeclarationKind, GlobalDeclaration};
+
+use crate::{bindings::literal_group_binding, CompileResult};
+
+/// A single artifact with the compiled WGSL and its metadata, for web delivery.
+///
+/// Obtained with [`CompileResult::bundle`]. It serializes to JSON (or any other `serde`
+/// format), so an asset build can produce everything a web engine needs in one file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShaderBundle {
+    /// The assembled WGSL.
+    pub wgsl: String,
+    /// Entry point functions of the shader.
+    pub entry_points: Vec<EntryPointInfo>,
+    /// Resource bindings: module-scope variables with `@group` and `@binding`.
+    pub bindings: Vec<BindingInfo>,
+    /// Mangled names of the declarations of imported modules. See [`crate::MangleMap`].
+    pub mangle_map: Vec<MangledItem>,
+    /// Modules that the shader is composed of, excluding the root module.
+    pub dependencies: Vec<String>,
+}
+
+/// An entry point of a [`ShaderBundle`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryPointInfo {
+    pub name: String,
+    /// `vertex`, `fragment` or `compute`.
+    pub stage: String,
+}
+
+/// A resource binding of a [`ShaderBundle`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BindingInfo {
+    pub group: u32,
+    pub binding: u32,
+    pub name: String,
+    /// e.g. `uniform`, `storage`. `None` for textures and samplers.
+    pub address_space: Option<String>,
+    /// e.g. `read_write`. Only for the `storage` address space.
+    pub access_mode: Option<String>,
+    /// The WGSL type of the variable, e.g. `array<f32>` or `texture_2d<f32>`.
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// A mangled name of a [`ShaderBundle`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MangledItem {
+    pub module: String,
+    pub item: String,
+    pub mangled: String,
+}
+
+impl CompileResult {
+    /// Bundle the compiled WGSL with its reflection info, mangle map and dependencies.
+    ///
+    /// Bindings with `@group` or `@binding` attributes that are not literals (e.g.
+    /// referring to a `const`) are omitted.
+    pub fn bundle(&self) -> ShaderBundle {
+        let mut entry_points = Vec::new();
+        let mut bindings = Vec::new();
+
+        for decl in &self.syntax.global_declarations {
+            match decl {
+                GlobalDeclaration::Function(f) => {
+                    let stage = f.attributes.iter().find_map(|attr| match attr {
+                        Attribute::Vertex => Some("vertex"),
+                        Attribute::Fragment => Some("fragment"),
+                        Attribute::Compute => Some("compute"),
+                        _ => None,
+                    });
+                    if let Some(stage) = stage {
+                        entry_points.push(EntryPointInfo {
+                            name: f.ident.to_string(),
+                            stage: stage.to_string(),
+                        });
+                    }
+                }
+                GlobalDeclaration::Declaration(d) => {
+                    let DeclarationKind::Var(space) = &d.kind else {
+                        continue;
+                    };
+                    let Some((group, binding)) = literal_group_binding(d) else {
+                        continue;
+                    };
+                    let (address_space, access_mode) = match space {
+                        Some(AddressSpace::Storage(access)) => (
+                            Some("storage".to_string()),
+                            access.as_ref().map(ToString::to_string),
+                        ),
+                        Some(space) => (Some(space.to_string()), None),
+                        None => (None, None),
+                    };
+                    bindings.push(BindingInfo {
+                        group,
+                        binding,
+                        name: d.ident.to_string(),
+                        address_space,
+                        access_mode,
+                        ty: d.ty.as_ref().map(ToString::to_string).unwrap_or_default(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let mangle_map = self
+            .mangle_map
+            .iter()
+            .map(|(path, item, mangled)| MangledItem {
+                module: path.to_string(),
+                item: item.to_string(),
+                mangled: mangled.to_string(),
+            })
+            .sorted_by(|a, b| (&a.module, &a.item).cmp(&(&b.module, &b.item)))
+            .collect_vec();
+        let dependencies = self
+            .modules
+            .iter()
+            .skip(1)
+            .map(ToString::to_string)
+            .collect();
+
+        ShaderBundle {
+            wgsl: self.syntax.to_string(),
+            entry_points,
+            bindings,
+            mangle_map,
+            dependencies,
+        }
+    }
+}
+
+#[test]
+fn test_shader_bundle() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::scale;
+        import package::config::factor;
+        @group(0) @binding(1) var<storage, read_write> data: array<f32>;
+        @group(1) @binding(0) var tex: texture_2d<f32>;
+        @compute @workgroup_size(64) fn main(@builtin(global_invocation_id) id: vec3u) {
+            data[id.x] = scale(data[id.x]) * factor + f32(textureDimensions(tex).x);
+        }"
+        .into(),
+    );
+    resolver.add_module("util", "fn scale(x: f32) -> f32 { return x * 2.0; }".into());
+    // overrides are not mangled, the module is absent from the mangle map.
+    resolver.add_module("config", "override factor: f32 = 1.0;".into());
+    let result = Wesl::new("")
+        .set_custom_resolver(resolver)
+        .compile("main")
+        .unwrap();
+
+    let bundle = result.bundle();
+    assert_eq!(bundle.wgsl, result.syntax.to_string());
+    assert_eq!(
+        bundle.entry_points,
+        [EntryPointInfo {
+            name: "main".to_string(),
+            stage: "compute".to_string(),
+        }]
+    );
+    assert_eq!(
+        bundle.bindings,
+        [
+            BindingInfo {
+                group: 0,
+                binding: 1,
+                name: "data".to_string(),
+                address_space: Some("storage".to_string()),
+                access_mode: Some("read_write".to_string()),
+                ty: "array<f32>".to_string(),
+            },
+            BindingInfo {
+                group: 1,
+                binding: 0,
+                name: "tex".to_string(),
+                address_space: None,
+                access_mode: None,
+                ty: "texture_2d<f32>".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        bundle.mangle_map,
+        [MangledItem {
+            module: "package::util".to_string(),
+            item: "scale".to_string(),
+            mangled: "package_util_scale".to_string(),
+        }]
+    );
+    assert_eq!(
+        bundle.dependencies.iter().sorted().collect_vec(),
+        ["package::config", "package::util"]
+    );
+
+    let json = serde_json::to_string(&bundle).unwrap();
+    assert!(json.contains(r#""type":"array<f32>""#));
+    let parsed: ShaderBundle = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, bundle);
+}
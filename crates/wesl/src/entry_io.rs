@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+
+use wgsl_parse::syntax::{
+    Attribute, Expression, ExpressionNode, FormalParameter, Function, FunctionCall,
+    GlobalDeclaration, Ident, NamedComponentExpression, Statement, StatementNode, Struct,
+    StructMember, TranslationUnit, TypeExpression,
+};
+
+use crate::visit::Visit;
+
+/// The direction of [`normalize_entry_params`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryParams {
+    /// Gather the `@location` and `@builtin` parameters of an entry point into a single
+    /// parameter of a generated input struct.
+    Gather,
+    /// Split entry point parameters of a struct type into one parameter per member.
+    Split,
+}
+
+fn is_entry_point(f: &Function) -> bool {
+    f.attributes.iter().any(|attr| {
+        matches!(
+            attr,
+            Attribute::Vertex | Attribute::Fragment | Attribute::Compute
+        )
+    })
+}
+
+fn is_io(attributes: &[Attribute]) -> bool {
+    attributes
+        .iter()
+        .any(|attr| matches!(attr, Attribute::Location(_) | Attribute::Builtin(_)))
+}
+
+/// A name starting with `name` that is not in `taken`.
+fn fresh_name(name: &str, taken: &HashSet<String>) -> String {
+    let mut res = name.to_string();
+    let mut i = 1;
+    while taken.contains(&res) {
+        res = format!("{name}_{i}");
+        i += 1;
+    }
+    res
+}
+
+/// Parameter names and local declaration names of a function.
+fn local_names(f: &Function) -> HashSet<String> {
+    fn rec(stmt: &StatementNode, res: &mut HashSet<String>) {
+        if let Statement::Declaration(decl) = stmt.node() {
+            res.insert(decl.ident.to_string());
+        }
+        for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+            rec(stmt, res);
+        }
+    }
+    let mut res = f
+        .parameters
+        .iter()
+        .map(|p| p.ident.to_string())
+        .collect::<HashSet<_>>();
+    for stmt in &f.body.statements {
+        rec(stmt, &mut res);
+    }
+    res
+}
+
+/// Replace the expressions for which `replace` returns a value, outermost first.
+fn rewrite(expr: &mut ExpressionNode, replace: &impl Fn(&Expression) -> Option<Expression>) {
+    if let Some(new) = replace(expr.node()) {
+        *expr.node_mut() = new;
+        return;
+    }
+    let rec = |expr: &mut ExpressionNode| rewrite(expr, replace);
+    match expr.node_mut() {
+        Expression::Literal(_) | Expression::TypeOrIdentifier(_) => (),
+        Expression::Parenthesized(p) => rec(&mut p.expression),
+        Expression::NamedComponent(c) => rec(&mut c.base),
+        Expression::Indexing(i) => {
+            rec(&mut i.base);
+            rec(&mut i.index);
+        }
+        Expression::Unary(u) => rec(&mut u.operand),
+        Expression::Binary(b) => {
+            rec(&mut b.left);
+            rec(&mut b.right);
+        }
+        Expression::FunctionCall(call) => call.arguments.iter_mut().for_each(rec),
+    }
+}
+
+fn rewrite_body(f: &mut Function, replace: impl Fn(&Expression) -> Option<Expression>) {
+    for stmt in &mut f.body.statements {
+        for expr in Visit::<ExpressionNode>::visit_mut(stmt.node_mut()) {
+            rewrite(expr, &replace);
+        }
+    }
+}
+
+fn ident_expr(ident: &Ident) -> Expression {
+    Expression::TypeOrIdentifier(TypeExpression::new(ident.clone()))
+}
+
+fn is_ident(expr: &Expression, ident: &Ident) -> bool {
+    matches!(expr, Expression::TypeOrIdentifier(ty) if ty.ident == *ident && ty.template_args.is_none())
+}
+
+fn gather(f: &mut Function, taken: &mut HashSet<String>) -> Option<Struct> {
+    if f.parameters.is_empty() || !f.parameters.iter().all(|p| is_io(&p.attributes)) {
+        return None;
+    }
+    let struct_name = fresh_name(&format!("{}_Input", f.ident), taken);
+    taken.insert(struct_name.clone());
+    let struct_ident = Ident::new(struct_name);
+    let input = Ident::new(fresh_name("input", &local_names(f)));
+
+    let params = std::mem::take(&mut f.parameters);
+    let members = params
+        .iter()
+        .map(|p| StructMember {
+            attributes: p.attributes.clone(),
+            ident: Ident::new(p.ident.to_string()),
+            ty: p.ty.clone(),
+        })
+        .collect();
+    f.parameters.push(FormalParameter {
+        attributes: Vec::new(),
+        ident: input.clone(),
+        ty: TypeExpression::new(struct_ident.clone()),
+    });
+    rewrite_body(f, |expr| {
+        let p = params.iter().find(|p| is_ident(expr, &p.ident))?;
+        Some(Expression::NamedComponent(NamedComponentExpression {
+            base: ident_expr(&input).into(),
+            component: Ident::new(p.ident.to_string()),
+        }))
+    });
+
+    Some(Struct {
+        attributes: Vec::new(),
+        ident: struct_ident,
+        members,
+    })
+}
+
+fn split(f: &mut Function, structs: &[Struct]) {
+    let mut taken = local_names(f);
+    let params = std::mem::take(&mut f.parameters);
+    // (parameter, its struct, the idents of the new parameters)
+    let mut splits = Vec::new();
+    for param in params {
+        let decl = structs.iter().find(|s| {
+            s.ident == param.ty.ident
+                && !s.members.is_empty()
+                && s.members.iter().all(|m| is_io(&m.attributes))
+        });
+        let Some(decl) = decl.filter(|_| param.attributes.is_empty()) else {
+            f.parameters.push(param);
+            continue;
+        };
+        let mut idents = Vec::new();
+        for m in &decl.members {
+            let name = m.ident.to_string();
+            let name = if taken.contains(&name) {
+                fresh_name(&format!("{}_{name}", param.ident), &taken)
+            } else {
+                name
+            };
+            taken.insert(name.clone());
+            let ident = Ident::new(name);
+            f.parameters.push(FormalParameter {
+                attributes: m.attributes.clone(),
+                ident: ident.clone(),
+                ty: m.ty.clone(),
+            });
+            idents.push((m.ident.to_string(), ident));
+        }
+        splits.push((param, decl, idents));
+    }
+    rewrite_body(f, |expr| {
+        for (param, decl, idents) in &splits {
+            match expr {
+                // `param.member` becomes the member parameter.
+                Expression::NamedComponent(c) if is_ident(c.base.node(), &param.ident) => {
+                    let (_, ident) = idents.iter().find(|(m, _)| *m == *c.component.name())?;
+                    return Some(ident_expr(ident));
+                }
+                // other uses of the whole struct construct it from the member parameters.
+                _ if is_ident(expr, &param.ident) => {
+                    return Some(Expression::FunctionCall(FunctionCall {
+                        ty: TypeExpression::new(decl.ident.clone()),
+                        arguments: idents.iter().map(|(_, id)| ident_expr(id).into()).collect(),
+                    }));
+                }
+                _ => (),
+            }
+        }
+        None
+    });
+}
+
+/// Convert the parameters of entry points between individual `@location`/`@builtin`
+/// parameters and input structs.
+///
+/// * [`EntryParams::Gather`]: an entry point whose parameters all have a `@location` or
+///   `@builtin` attribute gets a single `input` parameter of a new struct named
+///   `<entry>_Input`, with the same attributes on its members. Uses of the parameters in
+///   the body become `input.<param>`.
+/// * [`EntryParams::Split`]: parameters whose type is a struct declared in the module,
+///   with an IO attribute on every member, are replaced with one parameter per member.
+///   Uses of `param.member` become the member parameter, other uses of `param` are
+///   replaced with a constructor call. The struct declaration is kept.
+///
+/// Identifiers must be retargeted, see [`crate::SyntaxUtil::retarget_idents`].
+pub fn normalize_entry_params(wesl: &mut TranslationUnit, direction: EntryParams) {
+    match direction {
+        EntryParams::Gather => {
+            let mut taken = wesl
+                .global_declarations
+                .iter()
+                .filter_map(GlobalDeclaration::ident)
+                .map(|id| id.to_string())
+                .collect::<HashSet<_>>();
+            let mut structs = Vec::new();
+            for decl in &mut wesl.global_declarations {
+                if let GlobalDeclaration::Function(f) = decl {
+                    if is_entry_point(f) {
+                        structs.extend(gather(f, &mut taken));
+                    }
+                }
+            }
+            wesl.global_declarations
+                .extend(structs.into_iter().map(GlobalDeclaration::Struct));
+        }
+        EntryParams::Split => {
+            let structs = wesl
+                .global_declarations
+                .iter()
+                .filter_map(|decl| match decl {
+                    GlobalDeclaration::Struct(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            for decl in &mut wesl.global_declarations {
+                if let GlobalDeclaration::Function(f) = decl {
+                    if is_entry_point(f) {
+                        split(f, &structs);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_normalize_entry_params() {
+    use crate::SyntaxUtil;
+    let source = "@fragment
+        fn main(@builtin(position) pos: vec4f, @location(0) @interpolate(flat) id: u32, @location(1) uv: vec2f) -> @location(0) vec4f {
+            let input = uv * pos.xy;
+            return vec4f(input, f32(id), 1.0);
+        }";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+    normalize_entry_params(&mut wgsl, EntryParams::Gather);
+    let expected = "@fragment
+fn main(input_1: main_Input) -> @location(0) vec4f {
+    let input = input_1.uv * input_1.pos.xy;
+    return vec4f(input, f32(input_1.id), 1.0);
+}
+
+struct main_Input {
+    @builtin(position)
+    pos: vec4f,
+    @location(0) @interpolate(flat)
+    id: u32,
+    @location(1)
+    uv: vec2f
+}
+";
+    assert_eq!(wgsl.to_string(), expected);
+
+    // splitting recovers the parameters, and whole-struct uses are reconstructed.
+    let mut wgsl = wgsl_parse::parse_str(&wgsl.to_string()).unwrap();
+    wgsl.retarget_idents();
+    normalize_entry_params(&mut wgsl, EntryParams::Split);
+    let main = wgsl.global_declarations[0].to_string();
+    assert!(main.starts_with(
+        "@fragment\nfn main(@builtin(position) pos: vec4f, @location(0) @interpolate(flat) id: u32, @location(1) uv: vec2f)"
+    ));
+    assert!(main.contains("let input = uv * pos.xy;"));
+
+    let source = "struct In { @location(0) a: f32, @location(1) b: f32 }
+        fn sum(v: In) -> f32 { return v.a + v.b; }
+        @vertex fn main(v: In) -> @builtin(position) vec4f { return vec4f(sum(v), v.b, 0.0, 1.0); }";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+    normalize_entry_params(&mut wgsl, EntryParams::Split);
+    let main = wgsl.global_declarations[2].to_string();
+    assert!(main.contains("fn main(@location(0) a: f32, @location(1) b: f32)"));
+    assert!(main.contains("return vec4f(sum(In(a, b)), b, 0.0, 1.0);"));
+}
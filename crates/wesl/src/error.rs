@@ -5,7 +5,7 @@ use wgsl_parse::{
     syntax::{Expression, Ident, ModulePath},
 };
 
-use crate::{Mangler, ResolveError, SourceMap, ValidateError};
+use crate::{Mangler, ResolveError, SourceMap, ValidateError, Warning};
 
 #[cfg(feature = "generics")]
 use crate::GenericsError;
@@ -16,6 +16,9 @@ use crate::ImportError;
 #[cfg(feature = "eval")]
 use crate::eval::{Context, EvalError};
 
+#[cfg(feature = "naga")]
+use crate::NagaError;
+
 /// Any WESL error.
 #[derive(Clone, Debug, thiserror::Error)]
 pub enum Error {
@@ -35,6 +38,11 @@ pub enum Error {
     #[cfg(feature = "eval")]
     #[error("{0}")]
     EvalError(#[from] EvalError),
+    #[cfg(feature = "naga")]
+    #[error("{0}")]
+    NagaError(#[from] NagaError),
+    #[error("{0}")]
+    Warning(Warning),
     #[error("{0}")]
     Error(#[from] Diagnostic<Error>),
 }
@@ -72,9 +80,10 @@ impl From<ValidateError> for Diagnostic<Error> {
 impl From<ResolveError> for Diagnostic<Error> {
     fn from(error: ResolveError) -> Self {
         match error {
-            ResolveError::FileNotFound(_, _) | ResolveError::ModuleNotFound(_, _) => {
-                Self::new(error.into())
-            }
+            ResolveError::FileNotFound(_, _)
+            | ResolveError::ModuleNotFound(_, _)
+            | ResolveError::Preprocess(_, _)
+            | ResolveError::InvalidDataUri(_, _) => Self::new(error.into()),
             ResolveError::Error(e) => e,
         }
     }
@@ -121,6 +130,9 @@ impl From<Error> for Diagnostic<Error> {
             Error::GenericsError(e) => e.into(),
             #[cfg(feature = "eval")]
             Error::EvalError(e) => e.into(),
+            #[cfg(feature = "naga")]
+            Error::NagaError(e) => e.into(),
+            Error::Warning(_) => Self::new(error),
             Error::Error(e) => e,
         }
     }
@@ -128,7 +140,7 @@ impl From<Error> for Diagnostic<Error> {
 
 impl<E: std::error::Error> Diagnostic<E> {
     /// Create an empty diagnostic from an error. No metadata is attached.
-    fn new(error: E) -> Diagnostic<E> {
+    pub(crate) fn new(error: E) -> Diagnostic<E> {
         Self {
             error: Box::new(error),
             source: None,
@@ -319,12 +331,24 @@ impl Diagnostic<Error> {
                 ValidateError::UndefinedSymbol(name)
                 | ValidateError::ParamCount(name, _, _)
                 | ValidateError::NotCallable(name)
-                | ValidateError::Duplicate(name) => unmangle_name(name, sourcemap, mangler),
+                | ValidateError::Duplicate(name)
+                | ValidateError::NotConst(name)
+                | ValidateError::OverrideArrayLength(_, name)
+                | ValidateError::WorkgroupSize(name, ..)
+                | ValidateError::WorkgroupInvocations(name, ..) => {
+                    unmangle_name(name, sourcemap, mangler)
+                }
                 ValidateError::Cycle(name1, name2) => {
                     unmangle_name(name1, sourcemap, mangler);
                     unmangle_name(name2, sourcemap, mangler);
                 }
-                ValidateError::ReservedWord(_) => {}
+                ValidateError::RuntimeArrayNotLast(name, _) | ValidateError::NoLayout(name, _) => {
+                    unmangle_name(name, sourcemap, mangler)
+                }
+                ValidateError::ReservedWord(_)
+                | ValidateError::NoEntryPoint
+                | ValidateError::UnknownAttribute(_)
+                | ValidateError::MangleCollision(..) => {}
             },
             Error::ResolveError(_) => {}
             Error::ImportError(_) => {}
@@ -455,6 +479,9 @@ impl Diagnostic<Error> {
                 | EvalError::FlowInFunction(_)
                 | EvalError::FlowInModule(_) => {}
             },
+            #[cfg(feature = "naga")]
+            Error::NagaError(_) => {}
+            Error::Warning(_) => {}
             Error::Error(_) => {}
         };
 
@@ -466,9 +493,20 @@ impl<E: std::error::Error> std::error::Error for Diagnostic<E> {}
 
 impl<E: std::error::Error> Display for Diagnostic<E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(annotate_snippets::Level::Error, f)
+    }
+}
+
+impl<E: std::error::Error> Diagnostic<E> {
+    /// Render the diagnostic with annotated source snippets, at the given level.
+    pub(crate) fn render(
+        &self,
+        level: annotate_snippets::Level,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
         use annotate_snippets::*;
         let title = format!("{}", self.error);
-        let mut msg = Level::Error.title(&title);
+        let mut msg = level.title(&title);
 
         let orig = self.display_origin();
         let short_orig = self.display_short_origin();
@@ -478,7 +516,7 @@ impl<E: std::error::Error> Display for Diagnostic<E> {
 
             if let Some(source) = source {
                 if span.range().end <= source.len() {
-                    let annot = Level::Error.span(span.range()).label(&title);
+                    let annot = level.span(span.range()).label(&title);
                     let mut snip = Snippet::source(source).fold(true).annotation(annot);
 
                     if let Some(orig) = &short_orig {
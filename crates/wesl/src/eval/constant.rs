@@ -1,6 +1,9 @@
 use super::{is_constructor_fn, Scope, SyntaxUtil};
 use itertools::Itertools;
-use wgsl_parse::{span::Spanned, syntax::*};
+use wgsl_parse::{
+    span::{Span, Spanned},
+    syntax::*,
+};
 
 macro_rules! with_scope {
     ($scope:expr, $body:tt) => {{
@@ -38,6 +41,33 @@ pub(crate) fn mark_functions_const(wesl: &mut TranslationUnit) {
     }
 }
 
+/// Check that a function body is const-evaluable, without trusting its `@const` attribute.
+///
+/// Returns the span of the first offending statement, or `Err(None)` if the function
+/// signature itself is not const (e.g. pointer parameters).
+pub(crate) fn check_function_const(
+    decl: &Function,
+    wesl: &TranslationUnit,
+) -> Result<(), Option<Span>> {
+    let mut locals = Locals::new();
+    locals.add(decl.ident.to_string(), true); // recursive calls are const.
+    with_scope!(locals, {
+        if !(decl.attributes.is_const(wesl, &mut locals)
+            && decl.parameters.is_const(wesl, &mut locals)
+            && decl.return_attributes.is_const(wesl, &mut locals)
+            && decl.return_type.is_const(wesl, &mut locals))
+        {
+            return Err(None);
+        }
+        for stmt in &decl.body.statements {
+            if !stmt.is_const(wesl, &mut locals) {
+                return Err(Some(stmt.span().clone()));
+            }
+        }
+        Ok(())
+    })
+}
+
 impl IsConst for Function {
     fn is_const(&self, wesl: &TranslationUnit, locals: &mut Locals) -> bool {
         self.attributes.contains(&Attribute::Const)
@@ -111,6 +141,7 @@ impl IsConst for Attribute {
             Attribute::Else => true,              // if attributes are translate-time (always const)
             #[cfg(feature = "generics")]
             Attribute::Type(_) => todo!(),
+            Attribute::Deprecated(_) => true, // deprecation is translate-time (always const)
             Attribute::Custom(attr) => attr.arguments.is_const(wesl, locals),
         }
     }
@@ -0,0 +1,208 @@
+use std::{collections::HashSet, fmt::Display};
+
+use wgsl_parse::syntax::{
+    Attribute, Attributes, Expression, ExpressionNode, GlobalDeclaration, GlobalDirective,
+    LiteralExpression, TranslationUnit, TypeExpression,
+};
+
+use crate::{syntax_util::all_types, visit::Visit, Diagnostic, Error, Warning};
+
+/// A WGSL feature that must be declared with an `enable` directive (extensions) or a
+/// `requires` directive (language extensions) before it is used.
+///
+/// See [`crate::SyntaxUtil::used_features`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// `enable f16;`: the `f16` type, its vector and matrix aliases and `h` literals.
+    F16,
+    /// `enable dual_source_blending;`: the `@blend_src` attribute.
+    DualSourceBlending,
+    /// `enable subgroups;`: the `subgroup*` and `quad*` builtin functions.
+    Subgroups,
+    /// `requires readonly_and_readwrite_storage_textures;`: storage textures with the
+    /// `read` and `read_write` access modes.
+    ReadonlyAndReadwriteStorageTextures,
+    /// `requires packed_4x8_integer_dot_product;`: the `dot4*Packed`, `pack4x*` and
+    /// `unpack4x*` builtin functions.
+    Packed4x8IntegerDotProduct,
+}
+
+impl Feature {
+    /// The name of the feature in `enable` and `requires` directives.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::F16 => "f16",
+            Feature::DualSourceBlending => "dual_source_blending",
+            Feature::Subgroups => "subgroups",
+            Feature::ReadonlyAndReadwriteStorageTextures => {
+                "readonly_and_readwrite_storage_textures"
+            }
+            Feature::Packed4x8IntegerDotProduct => "packed_4x8_integer_dot_product",
+        }
+    }
+
+    /// The directive that declares the feature: `enable` or `requires`.
+    pub fn directive(&self) -> &'static str {
+        match self {
+            Feature::ReadonlyAndReadwriteStorageTextures | Feature::Packed4x8IntegerDotProduct => {
+                "requires"
+            }
+            _ => "enable",
+        }
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+const PACKED_4X8_FUNCTIONS: &[&str] = &[
+    "dot4U8Packed",
+    "dot4I8Packed",
+    "pack4xI8",
+    "pack4xU8",
+    "pack4xI8Clamp",
+    "pack4xU8Clamp",
+    "unpack4xI8",
+    "unpack4xU8",
+];
+
+/// The feature implied by a type or function name, declared names excluded.
+fn ty_feature(ty: &TypeExpression, declared: &HashSet<String>, res: &mut HashSet<Feature>) {
+    let name = ty.ident.name();
+    if declared.contains(&*name) {
+        return;
+    }
+    let is_f16_alias = |prefix: &str| {
+        name.strip_prefix(prefix).is_some_and(|dims| {
+            dims.starts_with(|c: char| c.is_ascii_digit()) && dims.ends_with('h') && dims.len() <= 4
+        })
+    };
+    if *name == "f16" || is_f16_alias("vec") || is_f16_alias("mat") {
+        res.insert(Feature::F16);
+    } else if name.starts_with("subgroup") || name.starts_with("quad") {
+        res.insert(Feature::Subgroups);
+    } else if PACKED_4X8_FUNCTIONS.contains(&name.as_str()) {
+        res.insert(Feature::Packed4x8IntegerDotProduct);
+    } else if name.starts_with("texture_storage_") {
+        let access = ty.template_args.as_ref().and_then(|args| args.get(1));
+        if let Some(Expression::TypeOrIdentifier(access)) = access.map(|arg| arg.expression.node())
+        {
+            if matches!(access.ident.name().as_str(), "read" | "read_write") {
+                res.insert(Feature::ReadonlyAndReadwriteStorageTextures);
+            }
+        }
+    }
+}
+
+fn expr_feature(expr: &ExpressionNode, res: &mut HashSet<Feature>) {
+    if let Expression::Literal(LiteralExpression::F16(_)) = expr.node() {
+        res.insert(Feature::F16);
+    }
+    for expr in Visit::<ExpressionNode>::visit(expr.node()) {
+        expr_feature(expr, res);
+    }
+}
+
+/// The features used by the translation unit, whether they are declared or not.
+pub(crate) fn used_features(wesl: &TranslationUnit) -> HashSet<Feature> {
+    // user declarations can shadow builtin names, e.g. a function named `quadratic`.
+    let declared = wesl
+        .global_declarations
+        .iter()
+        .filter_map(GlobalDeclaration::ident)
+        .map(|id| id.to_string())
+        .collect::<HashSet<_>>();
+
+    let mut res = HashSet::new();
+    for ty in all_types(wesl) {
+        ty_feature(ty, &declared, &mut res);
+    }
+    for expr in Visit::<ExpressionNode>::visit(wesl) {
+        expr_feature(expr, &mut res);
+    }
+    let has_blend_src = Visit::<Attributes>::visit(wesl)
+        .flatten()
+        .any(|attr| matches!(attr, Attribute::BlendSrc(_)));
+    if has_blend_src {
+        res.insert(Feature::DualSourceBlending);
+    }
+    res
+}
+
+/// The names in the `enable` and `requires` directives of the translation unit.
+fn declared_features(wesl: &TranslationUnit) -> HashSet<&str> {
+    wesl.global_directives
+        .iter()
+        .flat_map(|directive| match directive {
+            GlobalDirective::Enable(enable) => enable.extensions.as_slice(),
+            GlobalDirective::Requires(requires) => requires.extensions.as_slice(),
+            GlobalDirective::Diagnostic(_) => &[],
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+/// Used features that are not declared in a directive, in a stable order.
+pub(crate) fn missing_features(wesl: &TranslationUnit) -> Vec<Feature> {
+    let declared = declared_features(wesl);
+    let mut res = used_features(wesl)
+        .into_iter()
+        .filter(|feature| !declared.contains(feature.name()))
+        .collect::<Vec<_>>();
+    res.sort_by_key(|feature| feature.name());
+    res
+}
+
+/// Emit a [`Warning::MissingFeature`] for each feature used but not declared.
+pub(crate) fn emit_missing_features(
+    wesl: &TranslationUnit,
+    options: &crate::CompileOptions,
+    warnings: &mut Vec<Diagnostic<Warning>>,
+) -> Result<(), Diagnostic<Error>> {
+    for feature in missing_features(wesl) {
+        let warning = Diagnostic::from(Warning::MissingFeature(feature));
+        options.deny_warnings.emit(warning, warnings)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_used_features() {
+    use crate::{SyntaxUtil, VirtualResolver, Wesl};
+    let source = "enable f16;
+        @group(0) @binding(0) var tex: texture_storage_2d<rgba8unorm, read_write>;
+        fn quadratic(x: f32) -> f32 { return x * x; }
+        fn f() -> vec3h { let p = dot4U8Packed(1u, 2u); return vec3(1.0h); }";
+    let wesl = source.parse::<TranslationUnit>().unwrap();
+    assert_eq!(
+        wesl.used_features(),
+        HashSet::from([
+            Feature::F16,
+            Feature::ReadonlyAndReadwriteStorageTextures,
+            Feature::Packed4x8IntegerDotProduct,
+        ])
+    );
+
+    // an f16 type without `enable f16;` is reported.
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "@compute @workgroup_size(1) fn main() { var x: array<f16, 4>; }".into(),
+    );
+    let res = Wesl::new("")
+        .set_custom_resolver(resolver)
+        .compile("main")
+        .unwrap();
+    assert_eq!(res.warnings.len(), 1);
+    assert!(matches!(
+        *res.warnings[0].error,
+        Warning::MissingFeature(Feature::F16)
+    ));
+    assert_eq!(
+        res.warnings[0].error.to_string(),
+        "the `f16` feature is used but not declared, add `enable f16;`"
+    );
+}
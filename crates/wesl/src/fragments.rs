@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use wgsl_parse::syntax::{GlobalDeclaration, ModulePath, TranslationUnit};
+
+use crate::MangleMap;
+
+/// Produces the WGSL text of a declaration. Used by [`FragmentCache`].
+pub trait Emitter {
+    fn emit(&mut self, decl: &GlobalDeclaration) -> String;
+}
+
+/// The default [`Emitter`], using the [`std::fmt::Display`] implementation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DisplayEmitter;
+
+impl Emitter for DisplayEmitter {
+    fn emit(&mut self, decl: &GlobalDeclaration) -> String {
+        decl.to_string()
+    }
+}
+
+/// Caches the emitted text of declarations across compilations, for watch mode.
+///
+/// Each global declaration is a fragment of the output, keyed by its name and the
+/// semantic hash of the module it comes from (see [`crate::ImportGraph::semantic_hashes`]).
+/// [`Self::emit`] only re-emits the declarations of modules that changed since the
+/// previous call, e.g. the module that was edited and its importers, and concatenates
+/// the fragments in order. Declarations that do not come from an imported module, e.g.
+/// those of the root module, are emitted every time.
+///
+/// The output is identical to the [`std::fmt::Display`] of the translation unit, as long
+/// as the compile options do not change between calls and the mangle map of the
+/// previous compilation is reused (see [`crate::CompileOptions::mangle_map`]).
+///
+/// # Example
+/// ```rust
+/// # use wesl::{FragmentCache, ImportGraph, ModulePath, VirtualResolver, Wesl};
+/// let mut resolver = VirtualResolver::new();
+/// resolver.add_module("main", "@compute @workgroup_size(1) fn main() {}".into());
+/// let graph = ImportGraph::new(ModulePath::from_path("/main"), &resolver).unwrap();
+/// let hashes = graph.semantic_hashes(&resolver).unwrap();
+/// let compiler = Wesl::new("").set_custom_resolver(resolver);
+///
+/// let mut cache = FragmentCache::new();
+/// let result = compiler.compile("main").unwrap();
+/// let text = cache.emit(&result.syntax, &result.mangle_map, &hashes);
+/// assert_eq!(text, result.syntax.to_string());
+/// ```
+#[derive(Debug, Default)]
+pub struct FragmentCache<E: Emitter = DisplayEmitter> {
+    emitter: E,
+    fragments: HashMap<(u64, String), String>,
+}
+
+impl FragmentCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<E: Emitter> FragmentCache<E> {
+    /// A cache that produces fragments with a custom emitter.
+    pub fn with_emitter(emitter: E) -> Self {
+        Self {
+            emitter,
+            fragments: HashMap::new(),
+        }
+    }
+
+    /// Stringify the translation unit, reusing the fragments of unchanged modules.
+    ///
+    /// `mangle_map` is the mangle map of the compilation, it gives the module of each
+    /// imported declaration. `hashes` are the semantic hashes of the modules.
+    ///
+    /// Fragments that are not part of the output anymore are evicted.
+    pub fn emit(
+        &mut self,
+        wgsl: &TranslationUnit,
+        mangle_map: &MangleMap,
+        hashes: &HashMap<ModulePath, u64>,
+    ) -> String {
+        // imports, exports and directives are short, they are stringified every time.
+        let header = TranslationUnit {
+            base: wgsl.base.clone(),
+            imports: wgsl.imports.clone(),
+            exports: wgsl.exports.clone(),
+            global_directives: wgsl.global_directives.clone(),
+            global_declarations: Vec::new(),
+        };
+        let mut res = header.to_string();
+        // the display of an empty translation unit ends with a newline, it is re-added at
+        // the end.
+        res.pop();
+
+        let modules = mangle_map
+            .iter()
+            .map(|(path, _, mangled)| (mangled, path))
+            .collect::<HashMap<_, _>>();
+
+        let mut fragments = HashMap::new();
+        let mut decls = Vec::new();
+        for decl in &wgsl.global_declarations {
+            if matches!(decl, GlobalDeclaration::Void) {
+                continue;
+            }
+            let key = decl.ident().and_then(|ident| {
+                let name = ident.name();
+                let hash = hashes.get(*modules.get(name.as_str())?)?;
+                Some((*hash, name.to_string()))
+            });
+            let Some(key) = key else {
+                decls.push(self.emitter.emit(decl));
+                continue;
+            };
+            let text = match self.fragments.remove(&key) {
+                Some(text) => text,
+                None => self.emitter.emit(decl),
+            };
+            decls.push(text.clone());
+            fragments.insert(key, text);
+        }
+        self.fragments = fragments;
+
+        res.push_str(&decls.join("\n\n"));
+        res.push('\n');
+        res
+    }
+
+    /// Number of cached fragments.
+    pub fn len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.fragments.clear();
+    }
+
+    pub fn emitter(&self) -> &E {
+        &self.emitter
+    }
+}
+
+#[test]
+fn test_fragment_cache() {
+    use crate::{CompileResult, ImportGraph, VirtualResolver, Wesl};
+
+    #[derive(Default)]
+    struct CountingEmitter(Vec<String>);
+    impl Emitter for CountingEmitter {
+        fn emit(&mut self, decl: &GlobalDeclaration) -> String {
+            let text = decl.to_string();
+            self.0.push(text.clone());
+            text
+        }
+    }
+
+    let compile = |util: &str, seed: &MangleMap| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module(
+            "main",
+            "import package::util::{scale, offset};
+            enable f16;
+            @compute @workgroup_size(1) fn main() { let x = scale(offset(1.0)); }"
+                .into(),
+        );
+        resolver.add_module("util", util.into());
+        resolver.add_module(
+            "math",
+            "fn double(x: f32) -> f32 { return x * 2.0; }".into(),
+        );
+        let graph = ImportGraph::new(ModulePath::from_path("/main"), &resolver).unwrap();
+        let hashes = graph.semantic_hashes(&resolver).unwrap();
+        let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+        compiler.set_options(crate::CompileOptions {
+            mangle_map: seed.clone(),
+            ..Default::default()
+        });
+        let result: CompileResult = compiler.compile("main").unwrap();
+        (result, hashes)
+    };
+    let util = "import package::math::double;
+        fn scale(x: f32) -> f32 { return double(x); }
+        fn offset(x: f32) -> f32 { return x + 1.0; }";
+
+    let mut cache = FragmentCache::with_emitter(CountingEmitter::default());
+    let mut emit = |(result, hashes): (CompileResult, _)| {
+        let text = cache.emit(&result.syntax, &result.mangle_map, &hashes);
+        assert_eq!(text, result.syntax.to_string());
+        result.mangle_map
+    };
+    let seed = emit(compile(util, &MangleMap::new()));
+    // re-emitting without changes only emits the root module declaration.
+    let seed = emit(compile(util, &seed));
+    // changing a module re-emits its declarations, not those of its dependencies.
+    let changed = util.replace("x + 1.0", "x + 2.0");
+    emit(compile(&changed, &seed));
+
+    let emitted = &cache.emitter().0;
+    assert_eq!(emitted.len(), 4 + 1 + 3);
+    let double = |text: &&String| text.contains("fn package_math_double(");
+    assert_eq!(emitted.iter().filter(double).count(), 1);
+    assert!(emitted[4].starts_with("@compute"));
+    assert!(emitted[5..].iter().any(|text| text.contains("x + 2.0")));
+    assert_eq!(cache.len(), 3);
+}
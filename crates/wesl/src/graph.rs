@@ -0,0 +1,156 @@
+use std::{cell::RefCell, rc::Rc};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::import::{find_decl, Module, Modules};
+use crate::{ImportError, ImportGraph, Resolver, SyntaxUtil};
+
+/// The import graph in a serializable form, for external visualization tools.
+///
+/// Obtained with [`ImportGraph::to_graph_json`]. Nodes and edges are sorted by module
+/// path, so the output is stable.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GraphJson {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A module of a [`GraphJson`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub path: String,
+    /// The resource the module was loaded from. See [`Resolver::display_name`].
+    pub resource: Option<String>,
+    /// Whether the module is the root of the graph.
+    pub entry: bool,
+}
+
+/// An import of a [`GraphJson`], from the importing module to the imported module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    /// The items imported with import statements. Empty when the module is only
+    /// referred to with inline paths, e.g. `package::noise::perlin(x)`.
+    pub items: Vec<GraphItem>,
+}
+
+/// An imported item of a [`GraphEdge`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphItem {
+    pub name: String,
+    /// Whether the imported module declares or re-exports the item.
+    pub resolved: bool,
+}
+
+impl ImportGraph {
+    /// Serializable nodes and edges of the graph, e.g. to emit JSON with `serde_json`.
+    ///
+    /// The modules are re-loaded with `resolver` to list the imported items.
+    pub fn to_graph_json(&self, resolver: &impl Resolver) -> Result<GraphJson, ImportError> {
+        let mut modules = Modules::new();
+        for path in self.modules() {
+            let mut source = resolver.resolve_module(path)?;
+            source.retarget_idents();
+            let module = Module::new(source, path.clone())?;
+            modules.insert(path.clone(), Rc::new(RefCell::new(module)));
+        }
+
+        let nodes = self
+            .modules()
+            .sorted_by_key(|path| path.to_string())
+            .map(|path| GraphNode {
+                path: path.to_string(),
+                resource: resolver.display_name(path),
+                entry: path == self.root(),
+            })
+            .collect_vec();
+
+        let mut edges = Vec::new();
+        for from in self.modules().sorted_by_key(|path| path.to_string()) {
+            let module = modules[from].borrow();
+            for to in self
+                .dependencies(from)
+                .sorted_by_key(|path| path.to_string())
+            {
+                let items = module
+                    .imports
+                    .values()
+                    .filter(|(path, _)| path == to)
+                    .map(|(_, item)| item.to_string())
+                    .sorted()
+                    .map(|name| GraphItem {
+                        resolved: find_decl(&module, &modules, to, &name).is_some(),
+                        name,
+                    })
+                    .collect_vec();
+                edges.push(GraphEdge {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    items,
+                });
+            }
+        }
+
+        Ok(GraphJson { nodes, edges })
+    }
+}
+
+#[test]
+fn test_graph_json() {
+    use crate::{ModulePath, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{scale, missing};
+        @compute @workgroup_size(1) fn main() { let x = scale(package::math::PI); }"
+            .into(),
+    );
+    resolver.add_module("util", "fn scale(x: f32) -> f32 { return x; }".into());
+    resolver.add_module("math", "const PI = 3.14;".into());
+
+    let graph = ImportGraph::new(ModulePath::from_path("/main"), &resolver).unwrap();
+    let json = serde_json::to_string_pretty(&graph.to_graph_json(&resolver).unwrap()).unwrap();
+    let expected = r#"{
+  "nodes": [
+    {
+      "path": "package::main",
+      "resource": null,
+      "entry": true
+    },
+    {
+      "path": "package::math",
+      "resource": null,
+      "entry": false
+    },
+    {
+      "path": "package::util",
+      "resource": null,
+      "entry": false
+    }
+  ],
+  "edges": [
+    {
+      "from": "package::main",
+      "to": "package::math",
+      "items": []
+    },
+    {
+      "from": "package::main",
+      "to": "package::util",
+      "items": [
+        {
+          "name": "missing",
+          "resolved": false
+        },
+        {
+          "name": "scale",
+          "resolved": true
+        }
+      ]
+    }
+  ]
+}"#;
+    assert_eq!(json, expected);
+}
@@ -0,0 +1,257 @@
+use std::collections::{BTreeSet, HashMap};
+
+use wgsl_parse::syntax::{DeclarationKind, Expression, GlobalDeclaration, Ident, TranslationUnit};
+
+use crate::syntax_util::{all_types, for_each_type_mut};
+
+/// Declaration groups, in output order.
+fn group(decl: &GlobalDeclaration) -> u8 {
+    match decl {
+        GlobalDeclaration::Declaration(decl) => match decl.kind {
+            DeclarationKind::Const | DeclarationKind::Override => 0,
+            DeclarationKind::Let | DeclarationKind::Var(_) => 2,
+        },
+        GlobalDeclaration::ConstAssert(_) => 0,
+        GlobalDeclaration::TypeAlias(_) | GlobalDeclaration::Struct(_) => 1,
+        GlobalDeclaration::Function(_) => 3,
+        GlobalDeclaration::Void => 4,
+    }
+}
+
+/// Indices of the module-scope declarations referenced by `decl`.
+fn dependencies<'a>(
+    decl: &'a GlobalDeclaration,
+    decls: &'a HashMap<Ident, usize>,
+) -> impl Iterator<Item = usize> + 'a {
+    all_types(decl).filter_map(|ty| decls.get(&ty.ident).copied())
+}
+
+/// Reorder module-scope declarations: consts and overrides first, then types, then
+/// variables, then functions.
+///
+/// A declaration is never moved before a declaration it refers to, e.g. a const
+/// initialized with a struct constructor stays after the struct. Within a group, the
+/// original order is preserved.
+pub(crate) fn hoist_consts(wgsl: &mut TranslationUnit) {
+    let decls = &wgsl.global_declarations;
+    let idents = decls
+        .iter()
+        .enumerate()
+        .filter_map(|(i, decl)| decl.ident().map(|id| (id.clone(), i)))
+        .collect::<HashMap<_, _>>();
+
+    let mut dependents = vec![Vec::new(); decls.len()];
+    let mut pending = vec![0usize; decls.len()];
+    for (i, decl) in decls.iter().enumerate() {
+        for dep in dependencies(decl, &idents) {
+            if dep != i {
+                dependents[dep].push(i);
+                pending[i] += 1;
+            }
+        }
+    }
+
+    // topological sort, picking the lowest (group, index) among ready declarations.
+    let mut ready = (0..decls.len())
+        .filter(|i| pending[*i] == 0)
+        .map(|i| (group(&decls[i]), i))
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(decls.len());
+    while let Some((_, i)) = ready.pop_first() {
+        order.push(i);
+        for &j in &dependents[i] {
+            pending[j] -= 1;
+            if pending[j] == 0 {
+                ready.insert((group(&decls[j]), j));
+            }
+        }
+    }
+
+    // cycles are invalid WGSL, but we don't want to lose declarations.
+    if order.len() != decls.len() {
+        return;
+    }
+
+    let mut decls = std::mem::take(&mut wgsl.global_declarations)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    wgsl.global_declarations = order
+        .into_iter()
+        .map(|i| decls[i].take().unwrap())
+        .collect();
+}
+
+/// Reorder struct and alias declarations so that each comes after the type declarations
+/// it refers to. Other declarations are not moved.
+///
+/// Assembly emits modules in import order, so an imported struct `A` with a field of an
+/// imported struct `B` would otherwise precede `B`.
+pub(crate) fn order_types(wgsl: &mut TranslationUnit) {
+    fn is_type(decl: &GlobalDeclaration) -> bool {
+        matches!(
+            decl,
+            GlobalDeclaration::TypeAlias(_) | GlobalDeclaration::Struct(_)
+        )
+    }
+
+    fn visit(
+        i: usize,
+        decls: &[GlobalDeclaration],
+        idents: &HashMap<Ident, usize>,
+        visited: &mut [bool],
+        order: &mut Vec<usize>,
+    ) {
+        if visited[i] {
+            return;
+        }
+        visited[i] = true;
+        for dep in dependencies(&decls[i], idents) {
+            if is_type(&decls[dep]) {
+                visit(dep, decls, idents, visited, order);
+            }
+        }
+        order.push(i);
+    }
+
+    let decls = &wgsl.global_declarations;
+    let idents = decls
+        .iter()
+        .enumerate()
+        .filter(|(_, decl)| is_type(decl))
+        .filter_map(|(i, decl)| decl.ident().map(|id| (id.clone(), i)))
+        .collect::<HashMap<_, _>>();
+
+    let mut visited = vec![false; decls.len()];
+    let mut order = Vec::with_capacity(decls.len());
+    for i in 0..decls.len() {
+        if is_type(&decls[i]) {
+            visit(i, decls, &idents, &mut visited, &mut order);
+        } else {
+            visited[i] = true;
+            order.push(i);
+        }
+    }
+
+    let mut decls = std::mem::take(&mut wgsl.global_declarations)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    wgsl.global_declarations = order
+        .into_iter()
+        .map(|i| decls[i].take().unwrap())
+        .collect();
+}
+
+/// Merge module-scope consts with identical initializers and types into the first of
+/// them, and make references to the others point at it.
+///
+/// Only consts initialized with a call, e.g. an array or struct constructor, are merged:
+/// they are the large ones. Idents are compared by name, which is fine after mangling:
+/// two initializers that print the same refer to the same declarations. Merging can
+/// make more consts identical, so it is repeated until nothing changes.
+pub(crate) fn dedup_consts(wgsl: &mut TranslationUnit) {
+    loop {
+        let mut first = HashMap::new();
+        let mut merged = HashMap::new();
+        for decl in &wgsl.global_declarations {
+            let GlobalDeclaration::Declaration(decl) = decl else {
+                continue;
+            };
+            let is_call = decl
+                .initializer
+                .as_ref()
+                .is_some_and(|init| matches!(init.node(), Expression::FunctionCall(_)));
+            if decl.kind != DeclarationKind::Const || !is_call {
+                continue;
+            }
+            // the declaration without its name: attributes, type and initializer.
+            let mut anonymous = decl.clone();
+            anonymous.ident = Ident::new(String::new());
+            let kept = first
+                .entry(anonymous.to_string())
+                .or_insert_with(|| decl.ident.clone());
+            if *kept != decl.ident {
+                merged.insert(decl.ident.clone(), kept.clone());
+            }
+        }
+        if merged.is_empty() {
+            return;
+        }
+
+        wgsl.global_declarations
+            .retain(|decl| decl.ident().is_none_or(|id| !merged.contains_key(id)));
+        for_each_type_mut(wgsl, &mut |ty| {
+            if let Some(kept) = merged.get(&ty.ident) {
+                ty.ident = kept.clone();
+            }
+        });
+    }
+}
+
+#[test]
+fn test_hoist_consts() {
+    use crate::SyntaxUtil;
+    let mut wgsl = wgsl_parse::parse_str(
+        "fn f() -> S { return S(c); }
+        var<private> v: S = s;
+        struct S { x: u32 }
+        const s = S(c);
+        alias T = u32;
+        const c: T = 1u;
+        override o = 2u;",
+    )
+    .unwrap();
+    wgsl.retarget_idents();
+    hoist_consts(&mut wgsl);
+    let names = wgsl
+        .global_declarations
+        .iter()
+        .map(|decl| decl.ident().unwrap().to_string())
+        .collect::<Vec<_>>();
+    // `o` has no dependencies. `c` depends on the alias `T` and `s` on the struct `S`.
+    assert_eq!(names, ["o", "S", "T", "c", "s", "v", "f"]);
+}
+
+#[test]
+fn test_dedup_consts() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::{a, b};
+        @compute @workgroup_size(1) fn main() {
+            let x = a::LUT[0] + b::LUT[1] + b::TYPED[2] + a::first();
+        }"
+        .into(),
+    );
+    resolver.add_module(
+        "a",
+        "const LUT = array(1.0, 2.0, 3.0);
+        const FIRST = array(LUT[0]);
+        fn first() -> f32 { return FIRST[0]; }"
+            .into(),
+    );
+    resolver.add_module(
+        "b",
+        "const LUT = array(1.0, 2.0, 3.0);
+        const TYPED: array<f32, 3> = array(1.0, 2.0, 3.0);"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.dedup_consts(true);
+    let res = compiler.compile("main").unwrap();
+    let names = res
+        .syntax
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.ident())
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>();
+    // `b::LUT` is merged into `a::LUT`, the explicitly typed const is kept.
+    assert!(names.contains(&"package_a_LUT".to_string()));
+    assert!(!names.contains(&"package_b_LUT".to_string()));
+    assert!(names.contains(&"package_b_TYPED".to_string()));
+    let output = res.to_string();
+    assert!(output.contains("let x = package_a_LUT[0] + package_a_LUT[1] + package_b_TYPED[2]"));
+}
@@ -1,18 +1,33 @@
 use std::{
+    borrow::Cow,
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
     rc::Rc,
 };
 
-use wgsl_parse::syntax::{
-    self, GlobalDeclaration, Ident, ImportContent, ImportStatement, ModulePath, TranslationUnit,
-    TypeExpression,
+use itertools::Itertools;
+use wesl_macros::{query, query_mut};
+use wgsl_parse::{
+    span::Span,
+    syntax::{
+        self, Attribute, Attributes, CompoundStatement, CustomAttribute, DeclarationKind,
+        ExportStatement, Expression, ExpressionNode, GlobalDeclaration, Ident, ImportContent,
+        ImportStatement, ModulePath, Statement, TranslationUnit, TypeExpression,
+    },
+    Decorated,
 };
 
-use crate::{visit::Visit, Mangler, ResolveError, Resolver, SyntaxUtil};
+use crate::{
+    builtin::BUILTIN_NAMES,
+    syntax_util::{self, all_types},
+    visit::Visit,
+    Diagnostic, Mangler, ResolveError, Resolver, SyntaxUtil, Warning,
+};
 
 type Imports = HashMap<Ident, (ModulePath, Ident)>;
-type Modules = HashMap<ModulePath, Rc<RefCell<Module>>>;
+type Exports = HashMap<String, (ModulePath, Ident)>; // lookup (exported name, (path, ident))
+pub(crate) type Modules = HashMap<ModulePath, Rc<RefCell<Module>>>;
 
 /// Error produced during import resolution.
 #[derive(Clone, Debug, thiserror::Error)]
@@ -23,17 +38,51 @@ pub enum ImportError {
     ResolveError(#[from] ResolveError),
     #[error("module `{0}` has no declaration `{1}`")]
     MissingDecl(ModulePath, String),
+    #[error("module `{0}` has no declarations, cannot import `{1}` from it")]
+    EmptyModule(ModulePath, String),
+    #[error("`{0}` is imported in a block and is not visible outside of it")]
+    OutOfScope(String),
+    #[error("`{0}` is imported with `@no_mangle`, but another declaration has the same name")]
+    NoMangleCollision(String),
+    #[error("the path `{1}` starts with `{0}`, which is also a declaration of this module; rename the declaration, or write `package::` or `super::` to refer to a module of this package")]
+    PathCollision(String, ModulePath),
+    #[error("`{0}` is imported by the root module and also declared locally, which is not supported with `preserve_entry_verbatim`")]
+    ShadowedImport(String),
+    #[error("the root module has {0}, which is not supported with `preserve_entry_verbatim`")]
+    VerbatimUnsupported(&'static str),
+    #[error("`{0}` is imported with `import type`, it cannot be used as a value")]
+    TypeOnlyValue(String),
+    #[error("module `{0}` exceeds the maximum import depth of {1}")]
+    MaxDepthExceeded(ModulePath, usize),
+    #[error("module `{0}` exceeds the maximum number of modules of {1}")]
+    MaxModulesExceeded(ModulePath, usize),
 }
 
 type E = ImportError;
 
+/// Limits on the size of the import graph. Default: no limits.
+///
+/// Use-case: guard against pathological import graphs when compiling untrusted shaders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResolveLimits {
+    /// Maximum length of the import chain from the root module to any module.
+    /// The root module has depth 0.
+    pub max_depth: Option<usize>,
+    /// Maximum number of modules loaded, including the root module.
+    pub max_total_modules: Option<usize>,
+}
+
 #[derive(Debug)]
 pub(crate) struct Module {
     pub(crate) source: TranslationUnit,
     pub(crate) path: ModulePath,
+    relative_base: ModulePath, // the path against which relative paths are resolved
     idents: HashMap<Ident, usize>, // lookup (ident, decl_index)
     treated_idents: RefCell<HashSet<Ident>>, // used idents that have already been usage-analyzed
-    imports: Imports,
+    pub(crate) imports: Imports,
+    exports: Exports,
+    unused_imports: Vec<Ident>,
+    depth: usize, // length of the import chain through which the module was first reached
 }
 
 impl Module {
@@ -44,7 +93,8 @@ impl Module {
             .enumerate()
             .filter_map(|(i, decl)| decl.ident().map(|id| (id.clone(), i)))
             .collect::<HashMap<_, _>>();
-        let imports = flatten_imports(&source.imports, &path)?;
+        let relative_base = relative_base(&source, &path);
+        let mut imports = flatten_imports(&source.imports, &relative_base)?;
 
         for id in idents.keys() {
             if imports
@@ -55,20 +105,223 @@ impl Module {
             }
         }
 
+        check_path_collisions(&source, &idents)?;
+        check_type_only_imports(&source)?;
+        let exports = flatten_exports(&source.exports, &imports, &idents, &path)?;
+
+        // block-scoped imports shadow module-scope names, like local declarations.
+        for block in block_imports(&source) {
+            imports.extend(flatten_imports(block, &relative_base)?);
+        }
+
+        let unused_imports = find_unused_imports(&source, &imports);
+
         Ok(Self {
             source,
             path,
+            relative_base,
             idents,
             treated_idents: Default::default(),
             imports,
+            exports,
+            unused_imports,
+            depth: 0,
         })
     }
+
+    /// Error if a name imported in a block is referred to outside of that block.
+    ///
+    /// `builtins` are names that are in scope everywhere besides the WGSL builtins, see
+    /// [`crate::CompileOptions::extra_builtins`].
+    pub(crate) fn check_block_scopes(&self, builtins: &HashSet<String>) -> Result<(), E> {
+        let block_names = block_imports(&self.source)
+            .into_iter()
+            .flat_map(|block| syntax_util::flatten_imports(block).map(|id| id.to_string()))
+            .collect::<HashSet<_>>();
+        if block_names.is_empty() {
+            Ok(())
+        } else {
+            check_out_of_scope(&self.source, &block_names, builtins)
+        }
+    }
+
+    /// The error for a missing declaration `name` in this module.
+    fn missing_decl(&self, name: &str) -> E {
+        if self.idents.is_empty() && self.exports.is_empty() {
+            E::EmptyModule(self.path.clone(), name.to_string())
+        } else {
+            E::MissingDecl(self.path.clone(), name.to_string())
+        }
+    }
+
+    /// Imported items that are never referred to in this module.
+    pub(crate) fn unused_imports(&self) -> &[Ident] {
+        &self.unused_imports
+    }
+
+    /// The module path and name of the global declaration that a type expression refers
+    /// to, if it is declared or imported by this module, like [`Resolutions::retarget`].
+    /// Re-exports are not followed.
+    pub(crate) fn target(&self, ty: &TypeExpression) -> Option<(ModulePath, String)> {
+        if let Some(path) = &ty.path {
+            let path = resolve_inline_path(path, &self.relative_base, &self.imports);
+            Some((path, ty.ident.to_string()))
+        } else if let Some((path, ident)) = self.imports.get(&ty.ident) {
+            Some((path.clone(), ident.to_string()))
+        } else {
+            self.idents
+                .contains_key(&ty.ident)
+                .then(|| (self.path.clone(), ty.ident.to_string()))
+        }
+    }
+
+    /// Whether the declaration is part of [`Resolutions::assemble`].
+    fn is_assembled(&self, decl: &GlobalDeclaration, strip: bool) -> bool {
+        !strip
+            || decl.is_const_assert()
+            || decl
+                .ident()
+                .is_some_and(|id| self.treated_idents.borrow().contains(id))
+    }
+}
+
+/// Items imported with `import type` must only appear in type positions: declaration
+/// types, template arguments, parameter and return types. Referring to them in an
+/// expression, including calling them as constructors, is an error.
+///
+/// Must be called after [`SyntaxUtil::retarget_idents`]: references to the items are
+/// recognized by their ident.
+fn check_type_only_imports(source: &TranslationUnit) -> Result<(), E> {
+    fn rec(content: &ImportContent, res: &mut HashSet<Ident>) {
+        match content {
+            ImportContent::Item(item) => {
+                res.insert(item.rename.as_ref().unwrap_or(&item.ident).clone());
+            }
+            ImportContent::Collection(coll) => {
+                for import in coll {
+                    rec(&import.content, res);
+                }
+            }
+        }
+    }
+    fn check_expr(expr: &ExpressionNode, type_only: &HashSet<Ident>) -> Result<(), E> {
+        let ty = match expr.node() {
+            Expression::TypeOrIdentifier(ty) => Some(ty),
+            Expression::FunctionCall(call) => Some(&call.ty),
+            _ => None,
+        };
+        if let Some(ty) = ty.filter(|ty| type_only.contains(&ty.ident)) {
+            return Err(E::TypeOnlyValue(ty.ident.to_string()));
+        }
+        for expr in Visit::<ExpressionNode>::visit(expr.node()) {
+            check_expr(expr, type_only)?;
+        }
+        Ok(())
+    }
+
+    let mut type_only = HashSet::new();
+    let imports = std::iter::once(source.imports.as_slice()).chain(block_imports(source));
+    for import in imports.flatten().filter(|import| import.type_only) {
+        rec(&import.content, &mut type_only);
+    }
+    if type_only.is_empty() {
+        return Ok(());
+    }
+    for expr in Visit::<ExpressionNode>::visit(source) {
+        check_expr(expr, &type_only)?;
+    }
+    Ok(())
+}
+
+/// The first segment of a package path (e.g. `foo` in `import foo::bar;` or in the inline
+/// path `foo::bar(x)`) must not be the name of a module declaration. It refers to a
+/// package, but reads like a reference to the declaration.
+fn check_path_collisions(
+    source: &TranslationUnit,
+    idents: &HashMap<Ident, usize>,
+) -> Result<(), E> {
+    let check = |path: &ModulePath| match path.first() {
+        Some(first) if path.origin.is_package() => {
+            if idents.keys().any(|id| *id.name() == *first) {
+                Err(E::PathCollision(first.to_string(), path.clone()))
+            } else {
+                Ok(())
+            }
+        }
+        _ => Ok(()),
+    };
+    fn rec(
+        content: &ImportContent,
+        path: &ModulePath,
+        check: &impl Fn(&ModulePath) -> Result<(), E>,
+    ) -> Result<(), E> {
+        if !path.is_empty() {
+            return check(path);
+        }
+        if let ImportContent::Collection(coll) = content {
+            for import in coll {
+                let path = ModulePath::new(path.origin, import.path.clone());
+                rec(&import.content, &path, check)?;
+            }
+        }
+        Ok(())
+    }
+    for imports in std::iter::once(source.imports.as_slice()).chain(block_imports(source)) {
+        for import in imports {
+            rec(&import.content, &import.path, &check)?;
+        }
+    }
+    all_types(source)
+        .filter_map(|ty| ty.path.as_ref())
+        .try_for_each(check)
+}
+
+/// Find imported items that are not referred to in the module.
+///
+/// The source must have been retargeted with [`SyntaxUtil::retarget_idents`], so that
+/// references share the ident of the import item. It must be called before references
+/// are retargeted to the imported declarations, see [`resolve_lazy`].
+fn find_unused_imports(source: &TranslationUnit, imports: &Imports) -> Vec<Ident> {
+    let mut used = HashSet::new();
+    let mut used_prefixes = HashSet::new();
+    for ty in all_types(source) {
+        match &ty.path {
+            Some(path) if path.origin.is_package() => {
+                // inline paths can refer to an imported module alias.
+                if let Some(prefix) = path.first() {
+                    used_prefixes.insert(prefix.to_string());
+                }
+            }
+            Some(_) => {}
+            None => {
+                used.insert(ty.ident.clone());
+            }
+        }
+    }
+    // re-exported items are used by the modules importing them.
+    let exported = source
+        .exports
+        .iter()
+        .flat_map(|export| &export.items)
+        .map(|item| item.ident.to_string())
+        .collect::<HashSet<_>>();
+
+    imports
+        .keys()
+        .filter(|id| {
+            let name = id.name();
+            !used.contains(*id) && !used_prefixes.contains(&*name) && !exported.contains(&*name)
+        })
+        .cloned()
+        .collect()
 }
 
 #[derive(Debug)]
 pub(crate) struct Resolutions {
     modules: Modules,
     order: Vec<ModulePath>,
+    limits: ResolveLimits,
+    mangle_root: bool,
 }
 
 impl Resolutions {
@@ -77,8 +330,18 @@ impl Resolutions {
         Resolutions {
             modules: Default::default(),
             order: Default::default(),
+            limits: Default::default(),
+            mangle_root: false,
         }
     }
+    pub(crate) fn set_limits(&mut self, limits: ResolveLimits) {
+        self.limits = limits;
+    }
+    /// Also mangle the private declarations of the root module. See
+    /// [`crate::CompileOptions::mangle_entry_locals`].
+    pub(crate) fn set_mangle_root(&mut self, val: bool) {
+        self.mangle_root = val;
+    }
     pub(crate) fn root_module(&self) -> Rc<RefCell<Module>> {
         self.modules.get(self.root_path()).unwrap().clone() // safety: new() requires push_module
     }
@@ -88,6 +351,10 @@ impl Resolutions {
     pub(crate) fn modules(&self) -> impl Iterator<Item = Rc<RefCell<Module>>> + '_ {
         self.order.iter().map(|i| self.modules[i].clone())
     }
+    /// Paths of the modules, in load order: the root module first.
+    pub(crate) fn module_paths(&self) -> impl Iterator<Item = &ModulePath> {
+        self.order.iter()
+    }
     pub(crate) fn push_module(&mut self, module: Module) -> Rc<RefCell<Module>> {
         let path = module.path.clone();
         let module = Rc::new(RefCell::new(module));
@@ -95,9 +362,59 @@ impl Resolutions {
         self.order.push(path);
         module
     }
+    /// Load a module from the resolver, checking the [`ResolveLimits`].
+    fn load_module(
+        &mut self,
+        path: &ModulePath,
+        depth: usize,
+        resolver: &impl Resolver,
+    ) -> Result<Module, E> {
+        if let Some(max) = self.limits.max_depth {
+            if depth > max {
+                return Err(E::MaxDepthExceeded(path.clone(), max));
+            }
+        }
+        if let Some(max) = self.limits.max_total_modules {
+            if self.modules.len() >= max {
+                return Err(E::MaxModulesExceeded(path.clone(), max));
+            }
+        }
+        let mut source = resolver.resolve_module(path)?;
+        source.retarget_idents();
+        let mut module = Module::new(source, path.clone())?;
+        module.depth = depth;
+        Ok(module)
+    }
 }
 
-fn resolve_inline_path(
+/// The path against which the relative paths of a module are resolved: the module path,
+/// unless the module has a `@base` directive. The directive wins over the module
+/// location.
+fn relative_base(source: &TranslationUnit, path: &ModulePath) -> ModulePath {
+    match &source.base {
+        Some(base) => {
+            // not `ModulePath::from_path`, which strips the extension: `lib.v2` is a
+            // directory name.
+            let mut res = ModulePath::new(syntax::PathOrigin::Absolute, Vec::new());
+            for comp in std::path::Path::new(base).components() {
+                match comp {
+                    std::path::Component::Normal(comp) => res.push(&comp.to_string_lossy()),
+                    std::path::Component::ParentDir => {
+                        res.components.pop();
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(name) = path.last() {
+                res.push(name);
+            }
+            res
+        }
+        None => path.clone(),
+    }
+}
+
+pub(crate) fn resolve_inline_path(
     path: &ModulePath,
     parent_path: &ModulePath,
     imports: &Imports,
@@ -146,15 +463,14 @@ pub fn resolve_lazy<'a>(
 ) -> Result<(), E> {
     fn load_module(
         path: &ModulePath,
+        depth: usize,
         resolutions: &mut Resolutions,
         resolver: &impl Resolver,
     ) -> Result<Rc<RefCell<Module>>, E> {
         if let Some(module) = resolutions.modules.get(path) {
             Ok(module.clone())
         } else {
-            let mut source = resolver.resolve_module(path)?;
-            source.retarget_idents();
-            let module = Module::new(source, path.clone())?;
+            let module = resolutions.load_module(path, depth, resolver)?;
 
             // const_asserts of used modules must be included.
             // https://github.com/wgsl-tooling-wg/wesl-spec/issues/66
@@ -178,11 +494,9 @@ pub fn resolve_lazy<'a>(
         resolutions: &mut Resolutions,
         resolver: &impl Resolver,
     ) -> Result<(), E> {
-        let (ident, n) = module
-            .idents
-            .iter()
-            .find(|(id, _)| *id.name() == ident)
-            .ok_or_else(|| E::MissingDecl(module.path.clone(), ident.to_string()))?;
+        let Some((ident, n)) = module.idents.iter().find(|(id, _)| *id.name() == ident) else {
+            return resolve_export(module, ident, resolutions, resolver);
+        };
 
         if module.treated_idents.borrow().contains(ident) {
             return Ok(());
@@ -194,6 +508,43 @@ pub fn resolve_lazy<'a>(
         resolve_decl(module, decl, resolutions, resolver)
     }
 
+    /// Follow re-exports to the original declaration and resolve it.
+    fn resolve_export(
+        module: &Module,
+        name: &str,
+        resolutions: &mut Resolutions,
+        resolver: &impl Resolver,
+    ) -> Result<(), E> {
+        let (mut path, mut ident) = module
+            .exports
+            .get(name)
+            .cloned()
+            .ok_or_else(|| module.missing_decl(name))?;
+        let mut visited = HashSet::new();
+
+        loop {
+            // a re-export cycle never reaches a declaration.
+            if !visited.insert((path.clone(), ident.to_string())) {
+                return Err(E::MissingDecl(path, ident.to_string()));
+            }
+            let ext_mod = if path == module.path {
+                None
+            } else {
+                Some(load_module(&path, module.depth + 1, resolutions, resolver)?)
+            };
+            let ext_mod = ext_mod.as_ref().map(|module| module.borrow());
+            let ext_mod = ext_mod.as_deref().unwrap_or(module);
+            let name = ident.to_string();
+            if ext_mod.idents.keys().any(|id| *id.name() == name) {
+                return resolve_ident(ext_mod, &name, resolutions, resolver);
+            }
+            match ext_mod.exports.get(&name) {
+                Some(next) => (path, ident) = next.clone(),
+                None => return Err(ext_mod.missing_decl(&name)),
+            }
+        }
+    }
+
     fn resolve_ty(
         module: &Module,
         ty: &TypeExpression,
@@ -206,7 +557,7 @@ pub fn resolve_lazy<'a>(
         }
 
         let (ext_path, ext_id) = if let Some(path) = &ty.path {
-            let res = resolve_inline_path(path, &module.path, &module.imports);
+            let res = resolve_inline_path(path, &module.relative_base, &module.imports);
             (res, ty.ident.clone())
         } else if let Some((path, ident)) = module.imports.get(&ty.ident) {
             (path.clone(), ident.clone())
@@ -231,7 +582,7 @@ pub fn resolve_lazy<'a>(
         }
 
         // load the external module for this external ident
-        let ext_mod = load_module(&ext_path, resolutions, resolver)?;
+        let ext_mod = load_module(&ext_path, module.depth + 1, resolutions, resolver)?;
         resolve_ident(&ext_mod.borrow(), &ext_id.name(), resolutions, resolver)?;
         Ok(())
     }
@@ -250,7 +601,7 @@ pub fn resolve_lazy<'a>(
     }
 
     let path = resolutions.root_path().clone();
-    let module = load_module(&path, resolutions, resolver)?;
+    let module = load_module(&path, 0, resolutions, resolver)?;
 
     for id in keep {
         resolve_ident(&module.borrow(), &id.name(), resolutions, resolver)?;
@@ -272,7 +623,7 @@ pub fn resolve_eager(resolutions: &mut Resolutions, resolver: &impl Resolver) ->
         }
 
         let (ext_path, ext_id) = if let Some(path) = &ty.path {
-            let res = resolve_inline_path(path, &module.path, &module.imports);
+            let res = resolve_inline_path(path, &module.relative_base, &module.imports);
             (res, ty.ident.clone())
         } else if let Some((path, ident)) = module.imports.get(&ty.ident) {
             (path.clone(), ident.clone())
@@ -281,34 +632,21 @@ pub fn resolve_eager(resolutions: &mut Resolutions, resolver: &impl Resolver) ->
             return Ok(());
         };
 
-        // if the import path points to a local decl, we stop here
-        if ext_path == module.path {
-            if module.idents.contains_key(&ty.ident) {
-                return Ok(());
-            } else {
-                return Err(E::MissingDecl(ext_path, ty.ident.to_string()));
-            }
-        }
-
         // load the external module for this external ident
-        let ext_mod = if let Some(module) = resolutions.modules.get(&ext_path) {
-            module.clone()
-        } else {
-            let mut source = resolver.resolve_module(&ext_path)?;
-            source.retarget_idents();
-            let module = resolutions.push_module(Module::new(source, ext_path.clone())?);
+        if ext_path != module.path && !resolutions.modules.contains_key(&ext_path) {
+            let module = resolutions.load_module(&ext_path, module.depth + 1, resolver)?;
+            let module = resolutions.push_module(module);
             resolve_module(&module.borrow(), resolutions, resolver)?;
-            module
-        };
+        }
 
         // get the ident of the external declaration pointed to by the type
-        if !ext_mod
-            .borrow() // safety: only 1 module is borrowed at a time, the current one.
-            .idents
-            .iter()
-            .any(|(id, _)| *id.name() == *ext_id.name())
-        {
-            return Err(E::MissingDecl(ext_path.clone(), ext_id.to_string()));
+        if find_decl(module, &resolutions.modules, &ext_path, &ext_id.name()).is_none() {
+            let name = ext_id.name();
+            return Err(if ext_path == module.path {
+                module.missing_decl(&name)
+            } else {
+                resolutions.modules[&ext_path].borrow().missing_decl(&name)
+            });
         }
         Ok(())
     }
@@ -319,8 +657,8 @@ pub fn resolve_eager(resolutions: &mut Resolutions, resolver: &impl Resolver) ->
     ) -> Result<(), E> {
         for (path, _) in module.imports.values() {
             if !resolutions.modules.contains_key(path) {
-                let source = resolver.resolve_module(path)?;
-                let module = resolutions.push_module(Module::new(source, path.clone())?);
+                let module = resolutions.load_module(path, module.depth + 1, resolver)?;
+                let module = resolutions.push_module(module);
                 resolve_module(&module.borrow(), resolutions, resolver)?;
             }
         }
@@ -337,6 +675,391 @@ pub fn resolve_eager(resolutions: &mut Resolutions, resolver: &impl Resolver) ->
     Ok(())
 }
 
+/// The import graph of a WESL program, for incremental rebuilds.
+///
+/// The graph is built once from the root module, then kept up-to-date by feeding it
+/// file-change events with [`ImportGraph::on_change`]. Only the changed module is
+/// re-resolved, not the whole program.
+#[derive(Clone, Debug)]
+pub struct ImportGraph {
+    root: ModulePath,
+    dependencies: HashMap<ModulePath, HashSet<ModulePath>>,
+    limits: ResolveLimits,
+}
+
+impl ImportGraph {
+    /// Build the import graph of all modules reachable from `root`.
+    pub fn new(root: ModulePath, resolver: &impl Resolver) -> Result<Self, E> {
+        Self::with_limits(root, resolver, ResolveLimits::default())
+    }
+
+    /// Build the import graph of all modules reachable from `root`, checking the limits
+    /// here and in [`Self::on_change`].
+    pub fn with_limits(
+        root: ModulePath,
+        resolver: &impl Resolver,
+        limits: ResolveLimits,
+    ) -> Result<Self, E> {
+        let mut graph = Self {
+            root: root.clone(),
+            dependencies: Default::default(),
+            limits,
+        };
+        graph.load(root, 0, resolver)?;
+        Ok(graph)
+    }
+
+    /// The root module of the graph.
+    pub fn root(&self) -> &ModulePath {
+        &self.root
+    }
+
+    /// Whether the module is part of the graph.
+    pub fn contains(&self, path: &ModulePath) -> bool {
+        self.dependencies.contains_key(path)
+    }
+
+    /// All modules in the graph.
+    pub fn modules(&self) -> impl Iterator<Item = &ModulePath> {
+        self.dependencies.keys()
+    }
+
+    /// Modules directly imported by the module `path`.
+    pub fn dependencies(&self, path: &ModulePath) -> impl Iterator<Item = &ModulePath> {
+        self.dependencies.get(path).into_iter().flatten()
+    }
+
+    /// Modules that directly import the module `path`.
+    pub fn importers<'a>(&'a self, path: &'a ModulePath) -> impl Iterator<Item = &'a ModulePath> {
+        self.dependencies
+            .iter()
+            .filter(move |(_, deps)| deps.contains(path))
+            .map(|(importer, _)| importer)
+    }
+
+    /// A hash of each module, that changes when the source of the module or of a module
+    /// it imports, transitively, changes.
+    ///
+    /// Declarations compiled from a module with the same hash and the same options are
+    /// identical, which is how [`crate::FragmentCache`] keys its fragments.
+    pub fn semantic_hashes(&self, resolver: &impl Resolver) -> Result<HashMap<ModulePath, u64>, E> {
+        // the hash of each source, with its path.
+        let sources = self
+            .modules()
+            .map(|path| {
+                let mut hasher = DefaultHasher::new();
+                path.hash(&mut hasher);
+                resolver.resolve_source(path)?.hash(&mut hasher);
+                Ok((path, hasher.finish()))
+            })
+            .collect::<Result<HashMap<_, _>, E>>()?;
+
+        // imports can be cyclic: the hash of a module combines the source hashes of all
+        // modules reachable from it, in any order.
+        let hashes = self
+            .modules()
+            .map(|path| {
+                let mut reachable = HashSet::new();
+                let mut stack = vec![path];
+                while let Some(path) = stack.pop() {
+                    if reachable.insert(path) {
+                        stack.extend(self.dependencies(path));
+                    }
+                }
+                let hash = reachable
+                    .iter()
+                    .fold(0u64, |hash, path| hash.wrapping_add(sources[path]));
+                (path.clone(), hash)
+            })
+            .collect();
+        Ok(hashes)
+    }
+
+    /// Notify the graph that the module `path` changed.
+    ///
+    /// Returns the modules that must be re-resolved: the changed module and all modules
+    /// that import it, transitively. Modules that are not part of the graph are ignored.
+    ///
+    /// The changed module is reloaded to update its imports: newly imported modules are
+    /// added to the graph, and modules that are no longer reachable from the root are
+    /// removed. On error, e.g. when the changed module fails to parse or a limit is
+    /// exceeded, the graph is left unchanged.
+    pub fn on_change(
+        &mut self,
+        path: &ModulePath,
+        resolver: &impl Resolver,
+    ) -> Result<HashSet<ModulePath>, E> {
+        let Some(depth) = self.depths().remove(path) else {
+            return Ok(HashSet::new());
+        };
+
+        let mut affected = HashSet::new();
+        let mut stack = vec![path.clone()];
+        while let Some(path) = stack.pop() {
+            if affected.insert(path.clone()) {
+                stack.extend(self.importers(&path).cloned());
+            }
+        }
+
+        // the new graph is built in a copy, so that errors leave the graph unchanged.
+        // Modules only reachable through the changed module are removed before it is
+        // reloaded, so that they do not count towards the limits.
+        let mut graph = self.clone();
+        graph.dependencies.insert(path.clone(), HashSet::new());
+        graph.prune();
+        graph.dependencies.remove(path);
+        graph.load(path.clone(), depth, resolver)?;
+        let depths = graph.depths();
+        if let Some(max) = graph.limits.max_depth {
+            if let Some((path, _)) = depths.iter().find(|(_, depth)| **depth > max) {
+                return Err(E::MaxDepthExceeded(path.clone(), max));
+            }
+        }
+        *self = graph;
+        Ok(affected)
+    }
+
+    /// Load the modules reachable from `path` that are not in the graph yet. `depth` is
+    /// the length of the import chain from the root to `path`.
+    fn load(&mut self, path: ModulePath, depth: usize, resolver: &impl Resolver) -> Result<(), E> {
+        let mut queue = VecDeque::from([(path, depth)]);
+        while let Some((path, depth)) = queue.pop_front() {
+            if self.contains(&path) {
+                continue;
+            }
+            if let Some(max) = self.limits.max_depth {
+                if depth > max {
+                    return Err(E::MaxDepthExceeded(path, max));
+                }
+            }
+            if let Some(max) = self.limits.max_total_modules {
+                if self.dependencies.len() >= max {
+                    return Err(E::MaxModulesExceeded(path, max));
+                }
+            }
+            let source = resolver.resolve_module(&path)?;
+            let deps = module_dependencies(&source, &path)?;
+            queue.extend(
+                deps.iter()
+                    .filter(|dep| !self.contains(dep))
+                    .map(|dep| (dep.clone(), depth + 1)),
+            );
+            self.dependencies.insert(path, deps);
+        }
+        Ok(())
+    }
+
+    /// Remove modules not reachable from the root.
+    fn prune(&mut self) {
+        let depths = self.depths();
+        self.dependencies
+            .retain(|path, _| depths.contains_key(path));
+    }
+
+    /// The length of the shortest import chain from the root to each reachable module.
+    fn depths(&self) -> HashMap<ModulePath, usize> {
+        let mut depths = HashMap::new();
+        let mut queue = VecDeque::from([(self.root.clone(), 0)]);
+        while let Some((path, depth)) = queue.pop_front() {
+            if depths.contains_key(&path) {
+                continue;
+            }
+            queue.extend(self.dependencies(&path).map(|dep| (dep.clone(), depth + 1)));
+            depths.insert(path, depth);
+        }
+        depths
+    }
+}
+
+/// Check that the imports of all modules reachable from `root` resolve: the imported
+/// modules exist and parse, and the imported items are declared or re-exported by them.
+///
+/// This is the cheapest correctness gate for an import graph: modules are parsed, but
+/// identifiers are not linked, names are not mangled and nothing is assembled. Unlike
+/// compilation, it reports all errors instead of stopping at the first one.
+///
+/// Import cycles between modules are valid and not reported. A cycle of re-exports
+/// never reaches a declaration, the item is reported as missing. Cycles between
+/// declarations are detected by validation, they are out of scope here.
+///
+/// Conditional compilation is not applied, see [`crate::Wesl::check_imports`] for that.
+pub fn check_imports(root: &ModulePath, resolver: &impl Resolver) -> Result<(), Vec<E>> {
+    let mut modules = Modules::new();
+    let mut order = Vec::new();
+    let mut failed = HashSet::new();
+    let mut errors = Vec::new();
+
+    let mut stack = vec![root.clone()];
+    while let Some(path) = stack.pop() {
+        if modules.contains_key(&path) || failed.contains(&path) {
+            continue;
+        }
+        let module = resolver
+            .resolve_module(&path)
+            .map_err(E::from)
+            .and_then(|mut source| {
+                let deps = module_dependencies(&source, &path)?;
+                source.retarget_idents();
+                Ok((Module::new(source, path.clone())?, deps))
+            });
+        match module {
+            Ok((module, deps)) => {
+                stack.extend(deps);
+                stack.extend(module.exports.values().map(|(path, _)| path.clone()));
+                order.push(path.clone());
+                modules.insert(path, Rc::new(RefCell::new(module)));
+            }
+            Err(e) => {
+                errors.push(e);
+                failed.insert(path);
+            }
+        }
+    }
+
+    for path in &order {
+        let module = modules[path].borrow();
+        let imports = module
+            .imports
+            .values()
+            .filter(|(path, _)| !failed.contains(path))
+            .sorted_by_key(|(path, item)| (path.to_string(), item.to_string()));
+        for (path, item) in imports {
+            if find_decl(&module, &modules, path, &item.name()).is_none() {
+                errors.push(modules[path].borrow().missing_decl(&item.name()));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// The imported items that the declaration `name` of the module `path` refers to
+/// directly, as `(module path, item name)` pairs, without duplicates.
+///
+/// Both items imported with import statements and inline paths (e.g.
+/// `package::noise::perlin(x)`) are reported, with the module path they are imported
+/// from. Re-exports are not followed. Use-cases are documentation tools and
+/// dependency-aware rebuilds.
+pub fn declaration_dependencies(
+    path: &ModulePath,
+    name: &str,
+    resolver: &impl Resolver,
+) -> Result<Vec<(ModulePath, String)>, E> {
+    let mut source = resolver.resolve_module(path)?;
+    source.retarget_idents();
+    let module = Module::new(source, path.clone())?;
+    let decl = module
+        .source
+        .global_declarations
+        .iter()
+        .find(|decl| decl.ident().is_some_and(|id| *id.name() == name))
+        .ok_or_else(|| module.missing_decl(name))?;
+
+    let mut deps = Vec::new();
+    for ty in all_types(decl) {
+        let dep = if let Some(path) = &ty.path {
+            let path = resolve_inline_path(path, &module.relative_base, &module.imports);
+            Some((path, ty.ident.to_string()))
+        } else {
+            module
+                .imports
+                .get(&ty.ident)
+                .map(|(path, item)| (path.clone(), item.to_string()))
+        };
+        if let Some(dep) = dep {
+            if !deps.contains(&dep) {
+                deps.push(dep);
+            }
+        }
+    }
+    Ok(deps)
+}
+
+/// Modules referenced by a module, through import statements or inline paths.
+fn module_dependencies(
+    source: &TranslationUnit,
+    path: &ModulePath,
+) -> Result<HashSet<ModulePath>, E> {
+    let base = relative_base(source, path);
+    let imports = flatten_imports(&source.imports, &base)?;
+    let mut deps = imports
+        .values()
+        .map(|(path, _)| path.clone())
+        .collect::<HashSet<_>>();
+    for block in block_imports(source) {
+        let block = flatten_imports(block, &base)?;
+        deps.extend(block.into_values().map(|(path, _)| path));
+    }
+    for inline_path in all_types(source).filter_map(|ty| ty.path.as_ref()) {
+        deps.insert(resolve_inline_path(inline_path, &base, &imports));
+    }
+    deps.remove(path);
+    Ok(deps)
+}
+
+/// Entry points and resource bindings (`@group` / `@binding` variables).
+fn is_host_visible(decl: &GlobalDeclaration) -> bool {
+    decl.attributes().iter().any(|attr| {
+        matches!(
+            attr,
+            Attribute::Vertex
+                | Attribute::Fragment
+                | Attribute::Compute
+                | Attribute::Group(_)
+                | Attribute::Binding(_)
+        )
+    })
+}
+
+pub(crate) fn is_deprecated_attr(attr: &Attribute) -> bool {
+    matches!(attr, Attribute::Deprecated(_))
+}
+
+fn is_no_mangle_attr(attr: &Attribute) -> bool {
+    matches!(attr, Attribute::Custom(CustomAttribute { name, .. }) if name == "no_mangle")
+}
+
+/// The deprecation of a declaration marked `@deprecated` or `@deprecated("message")`:
+/// `Some(message)` if deprecated.
+fn deprecation(decl: &GlobalDeclaration) -> Option<Option<String>> {
+    decl.attributes().iter().find_map(|attr| match attr {
+        Attribute::Deprecated(message) => Some(message.clone()),
+        _ => None,
+    })
+}
+
+/// Find the span of the innermost expression referring to `ident`.
+fn find_ident_span(expr: &ExpressionNode, ident: &Ident) -> Option<Span> {
+    let found = match expr.node() {
+        Expression::TypeOrIdentifier(ty) => ty.ident == *ident,
+        Expression::FunctionCall(call) => call.ty.ident == *ident,
+        _ => false,
+    };
+    if found {
+        return Some(expr.span().clone());
+    }
+    match expr.node() {
+        Expression::Parenthesized(e) => find_ident_span(&e.expression, ident),
+        Expression::NamedComponent(e) => find_ident_span(&e.base, ident),
+        Expression::Indexing(e) => {
+            find_ident_span(&e.base, ident).or_else(|| find_ident_span(&e.index, ident))
+        }
+        Expression::Unary(e) => find_ident_span(&e.operand, ident),
+        Expression::Binary(e) => {
+            find_ident_span(&e.left, ident).or_else(|| find_ident_span(&e.right, ident))
+        }
+        Expression::FunctionCall(e) => e
+            .arguments
+            .iter()
+            .find_map(|arg| find_ident_span(arg, ident)),
+        Expression::Literal(_) | Expression::TypeOrIdentifier(_) => None,
+    }
+}
+
 /// Flatten imports to a list of module paths.
 pub(crate) fn flatten_imports(
     imports: &[ImportStatement],
@@ -376,14 +1099,337 @@ pub(crate) fn flatten_imports(
     Ok(res)
 }
 
+/// The blocks directly nested in a statement.
+pub(crate) fn nested_blocks(stmt: &Statement) -> impl Iterator<Item = &CompoundStatement> {
+    query!(stmt.{
+        Statement::Compound,
+        Statement::If.{
+            if_clause.body,
+            else_if_clauses.[].body,
+            else_clause.[].body,
+        },
+        Statement::Switch.clauses.[].body,
+        Statement::Loop.{ body, continuing.[].body },
+        Statement::For.body,
+        Statement::While.body,
+    })
+}
+
+pub(crate) fn nested_blocks_mut(
+    stmt: &mut Statement,
+) -> impl Iterator<Item = &mut CompoundStatement> {
+    query_mut!(stmt.{
+        Statement::Compound,
+        Statement::If.{
+            if_clause.body,
+            else_if_clauses.[].body,
+            else_clause.[].body,
+        },
+        Statement::Switch.clauses.[].body,
+        Statement::Loop.{ body, continuing.[].body },
+        Statement::For.body,
+        Statement::While.body,
+    })
+}
+
+/// The block-scoped imports of a module, one list per block.
+fn block_imports(source: &TranslationUnit) -> Vec<&[ImportStatement]> {
+    fn rec<'a>(block: &'a CompoundStatement, res: &mut Vec<&'a [ImportStatement]>) {
+        if !block.imports.is_empty() {
+            res.push(&block.imports);
+        }
+        for stmt in &block.statements {
+            for block in nested_blocks(stmt) {
+                rec(block, res);
+            }
+        }
+    }
+    let mut res = Vec::new();
+    for decl in &source.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            rec(&f.body, &mut res);
+        }
+    }
+    res
+}
+
+/// Remove the block-scoped imports, which are not valid WGSL.
+pub(crate) fn remove_block_imports(wesl: &mut TranslationUnit) {
+    fn rec(block: &mut CompoundStatement) {
+        block.imports.clear();
+        for stmt in &mut block.statements {
+            for block in nested_blocks_mut(stmt) {
+                rec(block);
+            }
+        }
+    }
+    for decl in &mut wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            rec(&mut f.body);
+        }
+    }
+}
+
+/// Error if a name of `block_names` is referred to where no declaration of that name is
+/// in scope: module-scope declarations and imports, function parameters, local
+/// declarations and the imports of the enclosing blocks, in the order of the source.
+fn check_out_of_scope(
+    source: &TranslationUnit,
+    block_names: &HashSet<String>,
+    builtins: &HashSet<String>,
+) -> Result<(), E> {
+    type Scope<'a> = Cow<'a, HashSet<String>>;
+    struct Ctx<'a> {
+        block_names: &'a HashSet<String>,
+        builtins: &'a HashSet<String>,
+    }
+
+    fn check_ty(ty: &TypeExpression, scope: &Scope, ctx: &Ctx) -> Result<(), E> {
+        all_types(ty).chain(std::iter::once(ty)).try_for_each(|ty| {
+            let name = ty.ident.name();
+            if ty.path.is_none()
+                && ctx.block_names.contains(&*name)
+                && !scope.contains(&*name)
+                && !BUILTIN_NAMES.contains(&name.as_str())
+                && !ctx.builtins.contains(&*name)
+            {
+                return Err(E::OutOfScope(name.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    /// Check the type expressions of `tys` that are not in one of the `nested` blocks.
+    fn check_outside<'a>(
+        tys: impl Iterator<Item = &'a TypeExpression>,
+        nested: impl Iterator<Item = &'a CompoundStatement>,
+        scope: &Scope,
+        ctx: &Ctx,
+    ) -> Result<(), E> {
+        let nested = nested
+            .flat_map(|block| &block.statements)
+            .flat_map(|stmt| Visit::<TypeExpression>::visit(stmt.node()))
+            .map(|ty| ty as *const TypeExpression)
+            .collect::<HashSet<_>>();
+        tys.filter(|ty| !nested.contains(&(*ty as *const _)))
+            .try_for_each(|ty| check_ty(ty, scope, ctx))
+    }
+
+    /// Returns the scope at the end of the block.
+    fn check_block<'a>(
+        block: &CompoundStatement,
+        scope: &Scope<'a>,
+        ctx: &Ctx,
+    ) -> Result<Scope<'a>, E> {
+        let mut scope = scope.clone();
+        if !block.imports.is_empty() {
+            scope
+                .to_mut()
+                .extend(syntax_util::flatten_imports(&block.imports).map(|id| id.to_string()));
+        }
+        for stmt in &block.statements {
+            check_stmt(stmt.node(), &mut scope, ctx)?;
+        }
+        Ok(scope)
+    }
+
+    fn check_stmt(stmt: &Statement, scope: &mut Scope, ctx: &Ctx) -> Result<(), E> {
+        match stmt {
+            Statement::Declaration(decl) => {
+                for ty in Visit::<TypeExpression>::visit(stmt) {
+                    check_ty(ty, scope, ctx)?;
+                }
+                scope.to_mut().insert(decl.ident.to_string());
+            }
+            // the initializer is in scope of the condition, update and body.
+            Statement::For(s) => {
+                let mut scope = scope.clone();
+                if let Some(init) = &s.initializer {
+                    check_stmt(init.node(), &mut scope, ctx)?;
+                }
+                let tys = query!(s.{
+                    attributes.[].(x => Visit::<TypeExpression>::visit(x)),
+                    condition.[].(x => Visit::<TypeExpression>::visit(&**x)),
+                    body.attributes.[].(x => Visit::<TypeExpression>::visit(x)),
+                });
+                for ty in tys {
+                    check_ty(ty, &scope, ctx)?;
+                }
+                if let Some(update) = &s.update {
+                    check_stmt(update.node(), &mut scope.clone(), ctx)?;
+                }
+                check_block(&s.body, &scope, ctx)?;
+            }
+            // the continuing statement is in scope of the loop body.
+            Statement::Loop(s) => {
+                let tys = query!(s.{
+                    attributes.[].(x => Visit::<TypeExpression>::visit(x)),
+                    body.attributes.[].(x => Visit::<TypeExpression>::visit(x)),
+                });
+                for ty in tys {
+                    check_ty(ty, scope, ctx)?;
+                }
+                let scope = check_block(&s.body, scope, ctx)?;
+                if let Some(cont) = &s.continuing {
+                    let scope = check_block(&cont.body, &scope, ctx)?;
+                    let tys = query!(cont.break_if.[].expression.(x => Visit::<TypeExpression>::visit(&**x)));
+                    for ty in tys {
+                        check_ty(ty, &scope, ctx)?;
+                    }
+                }
+            }
+            _ => {
+                let tys = Visit::<TypeExpression>::visit(stmt);
+                check_outside(tys, nested_blocks(stmt), scope, ctx)?;
+                for block in nested_blocks(stmt) {
+                    check_block(block, scope, ctx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let ctx = Ctx {
+        block_names,
+        builtins,
+    };
+    let scope: Scope = Cow::Owned(
+        source
+            .global_declarations
+            .iter()
+            .filter_map(|decl| decl.ident())
+            .map(|id| id.to_string())
+            .chain(syntax_util::flatten_imports(&source.imports).map(|id| id.to_string()))
+            .collect(),
+    );
+    for decl in &source.global_declarations {
+        let tys = Visit::<TypeExpression>::visit(decl);
+        if let GlobalDeclaration::Function(f) = decl {
+            check_outside(tys, std::iter::once(&f.body), &scope, &ctx)?;
+            let mut scope = scope.clone();
+            scope
+                .to_mut()
+                .extend(f.parameters.iter().map(|p| p.ident.to_string()));
+            check_block(&f.body, &scope, &ctx)?;
+        } else {
+            for ty in tys {
+                check_ty(ty, &scope, &ctx)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Resolve re-exported items to the module and declaration they point to.
+fn flatten_exports(
+    exports: &[ExportStatement],
+    imports: &Imports,
+    idents: &HashMap<Ident, usize>,
+    path: &ModulePath,
+) -> Result<Exports, E> {
+    let mut res = Exports::new();
+
+    for item in exports.iter().flat_map(|export| &export.items) {
+        let name = item.ident.to_string();
+        let local = idents.keys().find(|id| *id.name() == name);
+        let target = imports
+            .iter()
+            .find(|(id, _)| *id.name() == name)
+            .map(|(_, target)| target.clone())
+            .or_else(|| local.map(|id| (path.clone(), id.clone())))
+            .ok_or_else(|| E::MissingDecl(path.clone(), name.clone()))?;
+
+        let export_name = item.rename.as_ref().unwrap_or(&item.ident).to_string();
+        // a renamed export cannot take the name of another item of the module.
+        let renamed = export_name != name;
+        let taken = idents
+            .keys()
+            .chain(imports.keys())
+            .any(|id| *id.name() == export_name);
+        if renamed && taken || res.contains_key(&export_name) {
+            return Err(E::DuplicateSymbol(export_name));
+        }
+        res.insert(export_name, target);
+    }
+
+    Ok(res)
+}
+
+/// Find the declaration named `name` in the module at `path`, following re-exports.
+///
+/// `module` is the module currently being processed, which may be mutably borrowed.
+pub(crate) fn find_decl(
+    module: &Module,
+    modules: &Modules,
+    path: &ModulePath,
+    name: &str,
+) -> Option<Ident> {
+    let mut path = path.clone();
+    let mut name = name.to_string();
+    let mut visited = HashSet::new();
+
+    while visited.insert((path.clone(), name.clone())) {
+        let find = |module: &Module| match module.idents.keys().find(|id| *id.name() == name) {
+            Some(id) => Ok(id.clone()),
+            None => Err(module.exports.get(&name).cloned()),
+        };
+        let found = if path == module.path {
+            find(module)
+        } else {
+            // safety: only 1 module is borrowed at a time, the current one.
+            find(&modules.get(&path)?.borrow())
+        };
+        match found {
+            Ok(id) => return Some(id),
+            Err(Some((next_path, next_id))) => {
+                path = next_path;
+                name = next_id.to_string();
+            }
+            Err(None) => return None,
+        }
+    }
+    None
+}
+
+/// File extensions (e.g. `import package::utils.wgsl::foo;`) are not valid in
+/// identifiers. Components with a `.` are escaped before mangling: they are prefixed
+/// with `__`, which identifiers cannot start with, `_` becomes `__` and `.` becomes `_d`.
+/// So `utils.wgsl`, `utils.wesl` and `utils::wgsl` do not collide.
+fn mangle_path(path: &ModulePath) -> ModulePath {
+    let components = path
+        .components
+        .iter()
+        .map(|comp| {
+            if comp.contains('.') {
+                format!("__{}", comp.replace('_', "__").replace('.', "_d"))
+            } else {
+                comp.clone()
+            }
+        })
+        .collect();
+    ModulePath::new(path.origin, components)
+}
+
 pub(crate) fn mangle_decls<'a>(
     wgsl: &'a mut TranslationUnit,
     path: &'a ModulePath,
     mangler: &impl Mangler,
+    keep: &HashSet<Ident>,
 ) {
     wgsl.global_declarations
         .iter_mut()
+        // override names are host-visible (pipeline constants), they must stay stable.
+        .filter(|decl| {
+            !matches!(
+                decl,
+                GlobalDeclaration::Declaration(syntax::Declaration {
+                    kind: DeclarationKind::Override,
+                    ..
+                })
+            )
+        })
         .filter_map(|decl| decl.ident_mut())
+        .filter(|ident| !keep.contains(*ident))
         .for_each(|ident| {
             let new_name = mangler.mangle(path, &ident.name());
             ident.rename(new_name.clone());
@@ -391,85 +1437,316 @@ pub(crate) fn mangle_decls<'a>(
 }
 
 impl Resolutions {
+    /// Find references to `@deprecated` declarations of other modules. Returns the path of
+    /// the referring module and the warning, with the use site span if it is in an
+    /// expression.
+    ///
+    /// Must be called after [`Self::retarget`].
+    pub(crate) fn deprecated_uses(&self) -> Vec<(ModulePath, Diagnostic<Warning>)> {
+        let mut deprecated = HashMap::new();
+        for module in self.modules() {
+            let module = module.borrow();
+            for decl in &module.source.global_declarations {
+                if let (Some(id), Some(message)) = (decl.ident(), deprecation(decl)) {
+                    deprecated.insert(id.clone(), (module.path.clone(), message));
+                }
+            }
+        }
+
+        let mut res = Vec::new();
+        if deprecated.is_empty() {
+            return res;
+        }
+
+        for module in self.modules() {
+            let module = module.borrow();
+            for decl in &module.source.global_declarations {
+                let mut seen = HashSet::new();
+                for id in all_types(decl).map(|ty| &ty.ident) {
+                    let Some((path, message)) = deprecated.get(id) else {
+                        continue;
+                    };
+                    if *path == module.path || !seen.insert(id) {
+                        continue;
+                    }
+                    let warning = Warning::Deprecated {
+                        name: id.to_string(),
+                        message: message.clone(),
+                    };
+                    let mut diagnostic = Diagnostic::from(warning);
+                    if let Some(decl_id) = decl.ident() {
+                        diagnostic = diagnostic.with_declaration(decl_id.to_string());
+                    }
+                    let span = Visit::<ExpressionNode>::visit(decl)
+                        .find_map(|expr| find_ident_span(expr, id));
+                    if let Some(span) = span {
+                        diagnostic = diagnostic.with_span(span);
+                    }
+                    res.push((module.path.clone(), diagnostic));
+                }
+            }
+        }
+        res
+    }
+
+    /// Remove the `@deprecated` attributes, which are not valid WGSL.
+    pub(crate) fn remove_deprecated_attrs(&mut self) {
+        for module in self.modules() {
+            let mut module = module.borrow_mut();
+            for attrs in Visit::<Attributes>::visit_mut(&mut module.source) {
+                attrs.retain(|attr| !is_deprecated_attr(attr));
+            }
+        }
+    }
+
     pub fn retarget(&mut self) {
+        fn retarget_ty(ty: &mut TypeExpression, module: &Module, modules: &Modules) {
+            // first, the recursive call: template arguments can refer to external types,
+            // e.g. `array<Foo, 2>(...)`.
+            for ty in Visit::<TypeExpression>::visit_mut(ty) {
+                retarget_ty(ty, module, modules);
+            }
+
+            let (ext_path, ext_id) = if let Some(path) = &ty.path {
+                let res = resolve_inline_path(path, &module.relative_base, &module.imports);
+                (res, ty.ident.clone())
+            } else if let Some((path, ident)) = module.imports.get(&ty.ident) {
+                (path.clone(), ident.clone())
+            } else {
+                // points to a local decl, we stop here.
+                return;
+            };
+
+            // the import path points to a local decl or a loaded external module
+            if ext_path == module.path || modules.contains_key(&ext_path) {
+                // get the ident of the external declaration pointed to by the type
+                let ext_id = find_decl(module, modules, &ext_path, &ext_id.name())
+                    .expect("external declaration not found");
+                ty.path = None;
+                ty.ident = ext_id;
+            }
+        }
+
         for module in self.modules.values() {
             let mut module = module.borrow_mut();
-            let module = &mut *module;
-            for ty in Visit::<TypeExpression>::visit_mut(&mut module.source) {
-                let (ext_path, ext_id) = if let Some(path) = &ty.path {
-                    let res = resolve_inline_path(path, &module.path, &module.imports);
-                    (res, ty.ident.clone())
-                } else if let Some((path, ident)) = module.imports.get(&ty.ident) {
-                    (path.clone(), ident.clone())
-                } else {
-                    // points to a local decl, we stop here.
+            let mut source = std::mem::take(&mut module.source);
+            for ty in Visit::<TypeExpression>::visit_mut(&mut source) {
+                retarget_ty(ty, &module, &self.modules);
+            }
+            module.source = source;
+        }
+    }
+
+    /// The declarations imported with `@no_mangle` by any module.
+    ///
+    /// Must be called before [`Self::mangle`], it looks up declarations by name.
+    fn no_mangle_decls(&self) -> HashSet<Ident> {
+        let mut res = HashSet::new();
+        for module in self.modules() {
+            let module = module.borrow();
+            let imports = module
+                .source
+                .imports
+                .iter()
+                .filter(|import| import.attributes.iter().any(is_no_mangle_attr));
+            for import in imports {
+                // errors are reported by `Module::new`, this cannot fail.
+                let Ok(items) =
+                    flatten_imports(std::slice::from_ref(import), &module.relative_base)
+                else {
                     continue;
                 };
+                for (path, ident) in items.values() {
+                    if let Some(decl) = find_decl(&module, &self.modules, path, &ident.name()) {
+                        res.insert(decl);
+                    }
+                }
+            }
+        }
+        res
+    }
 
-                // if the import path points to a local decl
-                if ext_path == module.path {
-                    let ext_id = module
-                        .idents
+    /// Mangle the declarations of all modules except the root module.
+    ///
+    /// Declarations imported with `@no_mangle` keep their name. It is an error if that
+    /// name collides with another declaration.
+    pub fn mangle(&mut self, mangler: &impl Mangler) -> Result<(), E> {
+        let keep = self.no_mangle_decls();
+        let root_path = self.root_path().clone();
+        for (path, module) in self.modules.iter_mut() {
+            let mut module = module.borrow_mut();
+            if path != &root_path {
+                mangle_decls(&mut module.source, &mangle_path(path), mangler, &keep);
+            } else if self.mangle_root {
+                // entry points and resource bindings are host-visible.
+                let mut keep = keep.clone();
+                keep.extend(
+                    module
+                        .source
+                        .global_declarations
                         .iter()
-                        .find(|(id, _)| *id.name() == *ext_id.name())
-                        .map(|(id, _)| id.clone())
-                        .expect("external declaration not found");
-                    ty.path = None;
-                    ty.ident = ext_id;
+                        .filter(|decl| is_host_visible(decl))
+                        .filter_map(|decl| decl.ident().cloned()),
+                );
+                mangle_decls(&mut module.source, &mangle_path(path), mangler, &keep);
+            }
+        }
+
+        if !keep.is_empty() {
+            let mut names = HashMap::<String, usize>::new();
+            for module in self.modules() {
+                let module = module.borrow();
+                for id in module
+                    .source
+                    .global_declarations
+                    .iter()
+                    .filter_map(|d| d.ident())
+                {
+                    *names.entry(id.to_string()).or_default() += 1;
                 }
-                // load the external module for this external ident
-                else if let Some(module) = self.modules.get(&ext_path) {
-                    // get the ident of the external declaration pointed to by the type
-                    let ext_id = module
-                        .borrow() // safety: only 1 module is borrowed at a time, the current one.
-                        .idents
-                        .iter()
-                        .find(|(id, _)| *id.name() == *ext_id.name())
-                        .map(|(id, _)| id.clone())
-                        .expect("external declaration not found");
+            }
+            if let Some(id) = keep.iter().find(|id| names.get(&*id.name()) > Some(&1)) {
+                return Err(E::NoMangleCollision(id.to_string()));
+            }
+        }
+        Ok(())
+    }
 
-                    ty.path = None;
-                    ty.ident = ext_id;
+    /// The module path and name of each global declaration, before mangling. Pass them to
+    /// [`Self::mangle_collisions`] after mangling.
+    pub(crate) fn decl_origins(&self) -> Vec<(Ident, ModulePath, String)> {
+        self.modules()
+            .flat_map(|module| {
+                let module = module.borrow();
+                module
+                    .source
+                    .global_declarations
+                    .iter()
+                    .filter_map(|decl| decl.ident())
+                    .map(|id| (id.clone(), module.path.clone(), id.to_string()))
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// Find declarations that were given the same name by the mangler. Returns the path
+    /// of the module of the shadowing declaration and the warning, which names both
+    /// origins.
+    ///
+    /// Minted names are already unique, but weak manglers (e.g. [`crate::NoMangler`]) can
+    /// mint a name that is taken by an unmangled declaration of the root module.
+    /// Declarations with the same name in the same module are duplicates, those are
+    /// reported by validation instead.
+    pub(crate) fn mangle_collisions(
+        origins: &[(Ident, ModulePath, String)],
+    ) -> Vec<(ModulePath, Warning)> {
+        let mut seen = HashMap::<String, (&ModulePath, &str)>::new();
+        let mut res = Vec::new();
+        for (id, path, name) in origins {
+            let mangled = id.to_string();
+            match seen.get(&mangled) {
+                Some((first_path, first_name)) => {
+                    if *first_path != path || *first_name != name {
+                        let warning = Warning::MangleCollision {
+                            name: mangled,
+                            first: format!("{first_path}::{first_name}"),
+                            second: format!("{path}::{name}"),
+                        };
+                        res.push((path.clone(), warning));
+                    }
+                }
+                None => {
+                    seen.insert(mangled, (path, name));
                 }
             }
         }
+        res
     }
 
-    pub fn mangle(&mut self, mangler: &impl Mangler) {
-        let root_path = self.root_path().clone();
-        for (path, module) in self.modules.iter_mut() {
-            if path != &root_path {
-                let mut module = module.borrow_mut();
-                mangle_decls(&mut module.source, path, mangler);
+    /// The module path and position of each global declaration.
+    pub(crate) fn decl_positions(&self) -> HashMap<Ident, (ModulePath, usize)> {
+        self.modules()
+            .flat_map(|module| {
+                let module = module.borrow();
+                module
+                    .idents
+                    .iter()
+                    .map(|(id, n)| (id.clone(), (module.path.clone(), *n)))
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// The declaration that each name of each module refers to, by module path and
+    /// position: the declarations of the module and its exports, followed to the original
+    /// declaration.
+    ///
+    /// Must be called before [`Self::mangle`], it looks up declarations by name.
+    pub(crate) fn decl_index(&self) -> HashMap<(ModulePath, String), (ModulePath, usize)> {
+        let positions = self.decl_positions();
+        let mut res = HashMap::new();
+        for module in self.modules() {
+            let module = module.borrow();
+            let names = module.idents.keys().map(|id| id.to_string());
+            let names = names.chain(module.exports.keys().cloned()).collect_vec();
+            for name in names {
+                let decl = find_decl(&module, &self.modules, &module.path, &name);
+                if let Some(pos) = decl.and_then(|id| positions.get(&id)) {
+                    res.insert((module.path.clone(), name), pos.clone());
+                }
             }
         }
+        res
+    }
+
+    /// The module path and position of the declarations of [`Self::assemble`], in order.
+    pub(crate) fn provenance(&self, strip: bool) -> Vec<(ModulePath, usize)> {
+        self.modules()
+            .flat_map(|module| {
+                let module = module.borrow();
+                module
+                    .source
+                    .global_declarations
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, decl)| module.is_assembled(decl, strip))
+                    .map(|(n, _)| (module.path.clone(), n))
+                    .collect_vec()
+            })
+            .collect()
+    }
+
+    /// The import statements of all modules, in resolution order.
+    pub(crate) fn import_statements(&self) -> Vec<String> {
+        self.modules()
+            .flat_map(|module| {
+                let module = module.borrow();
+                module
+                    .source
+                    .imports
+                    .iter()
+                    .map(|import| format!("import {import}"))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
     pub fn assemble(&self, strip: bool) -> TranslationUnit {
         let mut wesl = TranslationUnit::default();
         for module in self.modules() {
             let module = module.borrow();
-            if strip {
-                wesl.global_declarations.extend(
-                    module
-                        .source
-                        .global_declarations
-                        .iter()
-                        .filter(|decl| {
-                            decl.is_const_assert()
-                                || decl
-                                    .ident()
-                                    .is_some_and(|id| module.treated_idents.borrow().contains(id))
-                        })
-                        .cloned(),
-                );
-            } else {
-                wesl.global_declarations
-                    .extend(module.source.global_declarations.clone());
-            }
+            wesl.global_declarations.extend(
+                module
+                    .source
+                    .global_declarations
+                    .iter()
+                    .filter(|decl| module.is_assembled(decl, strip))
+                    .cloned(),
+            );
             wesl.global_directives
                 .extend(module.source.global_directives.clone());
         }
+        remove_block_imports(&mut wesl);
         // TODO: <https://github.com/wgsl-tooling-wg/wesl-spec/issues/71>
         // currently the behavior is:
         // * include all directives used (if strip)
@@ -478,3 +1755,850 @@ impl Resolutions {
         wesl
     }
 }
+
+#[test]
+fn test_override_id() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::consts::threshold;
+        @compute @workgroup_size(1) fn main() { let x = threshold; }"
+            .into(),
+    );
+    resolver.add_module("consts", "@id(3) override threshold: f32 = 0.5;".into());
+    let wgsl = crate::compile(
+        &ModulePath::from_path("/main"),
+        &resolver,
+        &EscapeMangler,
+        &CompileOptions::default(),
+    )
+    .unwrap();
+    let out = wgsl.to_string();
+    assert!(out.contains("@id(3)\noverride threshold: f32 = 0.5;"));
+    assert!(out.contains("let x = threshold;"));
+}
+
+#[test]
+fn test_array_constructor() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::types::Foo;
+        @compute @workgroup_size(1) fn main() {
+            let a = array<Foo, 2>(Foo(1), Foo(2));
+            let m = array<array<Foo, 1>, 1>(array<Foo, 1>(Foo(3)));
+        }"
+        .into(),
+    );
+    resolver.add_module("types", "struct Foo { x: i32 }".into());
+    let wgsl = crate::compile(
+        &ModulePath::from_path("/main"),
+        &resolver,
+        &EscapeMangler,
+        &CompileOptions::default(),
+    )
+    .unwrap();
+    let out = wgsl.to_string();
+    assert!(!out.contains(" Foo") && !out.contains("<Foo"));
+    assert_eq!(out.matches("types_Foo").count(), 7);
+}
+
+#[test]
+fn test_import_graph_on_change() {
+    use crate::VirtualResolver;
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::a::fa; import package::b::fb;
+        fn main() { fa(); fb(); }"
+            .into(),
+    );
+    resolver.add_module("a", "import package::leaf::f; fn fa() { f(); }".into());
+    resolver.add_module("b", "fn fb() {}".into());
+    resolver.add_module("leaf", "fn f() {}".into());
+
+    let path = |p: &str| ModulePath::from_path(p);
+    let mut graph = ImportGraph::new(path("/main"), &resolver).unwrap();
+    assert_eq!(graph.modules().count(), 4);
+
+    let affected = graph.on_change(&path("/leaf"), &resolver).unwrap();
+    let expected = HashSet::from_iter([path("/leaf"), path("/a"), path("/main")]);
+    assert_eq!(affected, expected);
+
+    // `a` no longer imports `leaf`, which is pruned from the graph.
+    resolver.add_module("a", "fn fa() {}".into());
+    let affected = graph.on_change(&path("/a"), &resolver).unwrap();
+    assert_eq!(affected, HashSet::from_iter([path("/a"), path("/main")]));
+    assert!(!graph.contains(&path("/leaf")));
+
+    // errors leave the graph unchanged.
+    resolver.add_module("a", "import package::leaf::f; fn fa( {}".into());
+    assert!(graph.on_change(&path("/a"), &resolver).is_err());
+    resolver.add_module("a", "import package::missing::f; fn fa() {}".into());
+    assert!(graph.on_change(&path("/a"), &resolver).is_err());
+    assert_eq!(graph.modules().count(), 3);
+    assert!(graph.dependencies(&path("/a")).next().is_none());
+    assert_eq!(
+        graph.importers(&path("/a")).collect::<Vec<_>>(),
+        [&path("/main")]
+    );
+}
+
+#[test]
+fn test_import_graph_limits() {
+    use crate::VirtualResolver;
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("main", "import package::a::fa; fn main() { fa(); }".into());
+    resolver.add_module("a", "fn fa() {}".into());
+    resolver.add_module("b", "import package::c::fc; fn fb() { fc(); }".into());
+    resolver.add_module("c", "fn fc() {}".into());
+
+    let path = |p: &str| ModulePath::from_path(p);
+    let limits = ResolveLimits {
+        max_depth: Some(2),
+        max_total_modules: Some(3),
+    };
+    let mut graph = ImportGraph::with_limits(path("/main"), &resolver, limits).unwrap();
+
+    // `main -> a -> b -> c` is too deep. The graph is unchanged.
+    resolver.add_module("a", "import package::b::fb; fn fa() { fb(); }".into());
+    let err = graph.on_change(&path("/a"), &resolver).unwrap_err();
+    assert!(matches!(err, E::MaxDepthExceeded(ref p, 2) if *p == path("/c")));
+    assert_eq!(graph.modules().count(), 2);
+
+    // `main -> {a, b} -> c` is within the depth limit, but has 4 modules.
+    resolver.add_module("a", "fn fa() {}".into());
+    resolver.add_module(
+        "main",
+        "import package::a::fa; import package::b::fb; fn main() { fa(); fb(); }".into(),
+    );
+    let err = graph.on_change(&path("/main"), &resolver).unwrap_err();
+    assert!(matches!(err, E::MaxModulesExceeded(_, 3)));
+    assert_eq!(graph.modules().count(), 2);
+
+    // replacing a module by another one does not count the removed module.
+    resolver.add_module("main", "import package::b::fb; fn main() { fb(); }".into());
+    graph.on_change(&path("/main"), &resolver).unwrap();
+    assert_eq!(graph.modules().count(), 3);
+    assert!(!graph.contains(&path("/a")));
+}
+
+#[test]
+fn test_resolve_limits() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::a::fa; @compute @workgroup_size(1) fn main() { fa(); }".into(),
+    );
+    resolver.add_module("a", "import package::b::fb; fn fa() { fb(); }".into());
+    resolver.add_module("b", "import package::c::fc; fn fb() { fc(); }".into());
+    resolver.add_module("c", "fn fc() {}".into());
+
+    let compile = |limits| {
+        let options = CompileOptions {
+            limits,
+            ..Default::default()
+        };
+        let root = ModulePath::from_path("/main");
+        crate::compile(&root, &resolver, &EscapeMangler, &options).map(|_| ())
+    };
+
+    let limits = ResolveLimits {
+        max_depth: Some(2),
+        ..Default::default()
+    };
+    let err = compile(limits).unwrap_err();
+    assert!(matches!(
+        *err.error,
+        crate::Error::ImportError(E::MaxDepthExceeded(ref path, 2)) if *path == ModulePath::from_path("/c")
+    ));
+    assert!(err.to_string().contains("maximum import depth"));
+
+    let limits = ResolveLimits {
+        max_total_modules: Some(3),
+        ..Default::default()
+    };
+    let err = compile(limits).unwrap_err();
+    assert!(matches!(
+        *err.error,
+        crate::Error::ImportError(E::MaxModulesExceeded(_, 3))
+    ));
+    assert!(err.to_string().contains("maximum number of modules"));
+
+    let limits = ResolveLimits {
+        max_depth: Some(3),
+        max_total_modules: Some(4),
+    };
+    compile(limits).unwrap();
+}
+
+#[test]
+fn test_pointer_types() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::types::Foo;
+        fn f(p: ptr<function, Foo>) -> f32 { return (*p).x; }
+        fn g(p: ptr<storage, Foo, read_write>) { (*p).x = 1.0; }
+        fn h(p: ptr<private, array<Foo, 2>>) {}
+        @compute @workgroup_size(1) fn main() {
+            var foo = Foo(1.0);
+            let x = f(&foo);
+        }"
+        .into(),
+    );
+    resolver.add_module("types", "struct Foo { x: f32 }".into());
+    let options = CompileOptions {
+        strip: false,
+        ..Default::default()
+    };
+    let wgsl = crate::compile(
+        &ModulePath::from_path("/main"),
+        &resolver,
+        &EscapeMangler,
+        &options,
+    )
+    .unwrap()
+    .to_string();
+    assert!(wgsl.contains("p: ptr<function, package_types_Foo>"));
+    assert!(wgsl.contains("p: ptr<storage, package_types_Foo, read_write>"));
+    assert!(wgsl.contains("p: ptr<private, array<package_types_Foo, 2>>"));
+
+    // address spaces and access modes are template arguments, re-emitted verbatim.
+    let spellings = [
+        "ptr<function, f32>",
+        "ptr<function, f32, read_write>",
+        "ptr<private, vec4<f32>>",
+        "ptr<workgroup, atomic<u32>>",
+        "ptr<uniform, array<vec4f, 4>>",
+        "ptr<storage, array<u32>, read>",
+    ];
+    for ty in spellings {
+        let source = format!("fn f(p: {ty}) {{}}");
+        let wgsl = wgsl_parse::parse_str(&source).unwrap();
+        assert_eq!(
+            wgsl.to_string().lines().next().unwrap(),
+            format!("fn f(p: {ty}) {{")
+        );
+    }
+}
+
+#[test]
+fn test_deprecated_import() {
+    use crate::{VirtualResolver, Wesl};
+    let main = "import package::lib::{old_fn, new_fn};
+        @compute @workgroup_size(1) fn main() { let x = new_fn() + old_fn(); }";
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("main", main.into());
+    resolver.add_module(
+        "lib",
+        r#"@deprecated("use new_fn instead") fn old_fn() -> u32 { return new_fn(); }
+        fn new_fn() -> u32 { return 1u; }"#
+            .into(),
+    );
+    let res = Wesl::new("")
+        .set_custom_resolver(resolver)
+        .compile("main")
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    assert_eq!(res.warnings.len(), 1);
+    let warning = &res.warnings[0];
+    assert_eq!(
+        warning.error.to_string(),
+        "use of deprecated `old_fn`: use new_fn instead"
+    );
+    assert_eq!(warning.module_path, Some(ModulePath::from_path("/main")));
+    assert_eq!(warning.declaration.as_deref(), Some("main"));
+    let span = warning.span.clone().unwrap();
+    assert_eq!(&main[span.range()], "old_fn()");
+    assert!(!res.to_string().contains("@deprecated"));
+}
+
+#[test]
+fn test_nested_imported_structs() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::a::A;
+        import package::b::B;
+        @group(0) @binding(0) var<storage> buf: A;
+        @compute @workgroup_size(1) fn main() { let b: B = buf.b; }"
+            .into(),
+    );
+    resolver.add_module(
+        "a",
+        "import package::b::B;
+        struct A { b: B, bs: array<B, 2> }"
+            .into(),
+    );
+    resolver.add_module("b", "struct B { x: f32 }".into());
+    let options = CompileOptions {
+        strip: false,
+        order_types: true,
+        ..Default::default()
+    };
+    let wgsl = crate::compile(
+        &ModulePath::from_path("/main"),
+        &resolver,
+        &EscapeMangler,
+        &options,
+    )
+    .unwrap();
+    let structs = wgsl
+        .global_declarations
+        .iter()
+        .filter(|decl| decl.is_struct())
+        .map(|decl| decl.ident().unwrap().to_string())
+        .collect::<Vec<_>>();
+    // `B` is imported by both `main` and `a`, but emitted once, before `A`.
+    assert_eq!(structs, ["package_b_B", "package_a_A"]);
+    let wgsl = wgsl.to_string();
+    assert!(wgsl.contains("var<storage> buf: package_a_A;"));
+    assert!(wgsl.contains("let b: package_b_B = buf.b;"));
+    assert!(wgsl.contains("b: package_b_B,"));
+    assert!(wgsl.contains("bs: array<package_b_B, 2>"));
+}
+
+#[test]
+fn test_preserve_import_comments() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{helper, Data};
+        @compute @workgroup_size(1) fn main() { let d = Data(helper()); }"
+            .into(),
+    );
+    resolver.add_module(
+        "util",
+        "import super::consts::N;
+        struct Data { x: u32 }
+        fn helper() -> u32 { return N; }"
+            .into(),
+    );
+    resolver.add_module("consts", "const N = 1u;".into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.preserve_import_comments(true);
+    let res = compiler.compile("main").unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(
+        res.import_comments,
+        [
+            "import package::util::{ helper, Data };",
+            "import super::consts::N;"
+        ]
+    );
+    let output = res.to_string();
+    let mut lines = output.lines();
+    assert_eq!(
+        lines.next(),
+        Some("// import package::util::{ helper, Data };")
+    );
+    assert_eq!(lines.next(), Some("// import super::consts::N;"));
+    // the output is still plain WGSL, comments included.
+    let wgsl = wgsl_parse::parse_str(&output).unwrap();
+    assert!(wgsl.imports.is_empty());
+    assert_eq!(wgsl.to_string(), res.syntax.to_string());
+}
+
+#[test]
+fn test_reexport_facade() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::prelude::{ add, Point };
+        import package::math::add as add2;
+        @compute @workgroup_size(1) fn main() { let p = Point(add(1.0, 2.0)); let y = add2(p.x, 1.0); }"
+            .into(),
+    );
+    resolver.add_module(
+        "prelude",
+        "import package::math::{ add, Vec };
+        export { add, Vec as Point };"
+            .into(),
+    );
+    resolver.add_module(
+        "math",
+        "struct Vec { x: f32 }
+        fn add(a: f32, b: f32) -> f32 { return a + b; }"
+            .into(),
+    );
+
+    // `export` round-trips through the parser.
+    let source = resolver
+        .resolve_module(&ModulePath::from_path("/prelude"))
+        .unwrap();
+    assert_eq!(
+        source.exports[0].to_string(),
+        "export { add, Vec as Point };"
+    );
+
+    for lazy in [true, false] {
+        let options = CompileOptions {
+            lazy,
+            ..Default::default()
+        };
+        let wgsl = crate::compile(
+            &ModulePath::from_path("/main"),
+            &resolver,
+            &EscapeMangler,
+            &options,
+        )
+        .unwrap()
+        .to_string();
+        // the original declarations are emitted once, under the original module path.
+        assert_eq!(wgsl.matches("fn package_math_add(").count(), 1);
+        assert_eq!(wgsl.matches("struct package_math_Vec ").count(), 1);
+        assert!(wgsl.contains("let p = package_math_Vec(package_math_add(1.0, 2.0));"));
+        assert!(wgsl.contains("let y = package_math_add(p.x, 1.0);"));
+        assert!(!wgsl.contains("prelude"));
+    }
+
+    // re-exports of items that are not in scope are an error.
+    resolver.add_module("prelude", "export add;".into());
+    let res = crate::compile(
+        &ModulePath::from_path("/main"),
+        &resolver,
+        &EscapeMangler,
+        &CompileOptions::default(),
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_empty_modules() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let compile = |resolver: &VirtualResolver, lazy: bool| {
+        let options = CompileOptions {
+            lazy,
+            ..Default::default()
+        };
+        let root = ModulePath::from_path("/main");
+        crate::compile(&root, resolver, &EscapeMangler, &options)
+    };
+
+    // empty and comment-only files parse to an empty translation unit.
+    for source in ["", "  \n", "// nothing here\n/* nor /* here */ */"] {
+        assert_eq!(
+            wgsl_parse::parse_str(source).unwrap(),
+            TranslationUnit::default()
+        );
+
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", source.into());
+        for lazy in [true, false] {
+            let wgsl = compile(&resolver, lazy).unwrap();
+            assert!(wgsl.global_declarations.is_empty());
+            assert_eq!(wgsl.to_string(), "\n");
+        }
+    }
+
+    // importing from an empty module is a missing declaration error.
+    for source in ["", "// only a comment"] {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module(
+            "main",
+            "import package::empty::foo;
+            @compute @workgroup_size(1) fn main() { foo(); }"
+                .into(),
+        );
+        resolver.add_module("empty", source.into());
+        for lazy in [true, false] {
+            let err = compile(&resolver, lazy).unwrap_err();
+            assert!(matches!(
+                &*err.error,
+                crate::Error::ImportError(E::EmptyModule(path, name))
+                    if path.to_string() == "package::empty" && name == "foo"
+            ));
+            assert_eq!(
+                err.error.to_string(),
+                "module `package::empty` has no declarations, cannot import `foo` from it"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_no_mangle_import() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let compile = |main: &str, lazy: bool| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", main.into());
+        resolver.add_module(
+            "abi",
+            "fn entry() -> u32 { return helper(); }
+            fn helper() -> u32 { return 1u; }"
+                .into(),
+        );
+        let options = CompileOptions {
+            lazy,
+            ..Default::default()
+        };
+        let root = ModulePath::from_path("/main");
+        crate::compile(&root, &resolver, &EscapeMangler, &options)
+    };
+
+    for lazy in [true, false] {
+        let wgsl = compile(
+            "@no_mangle import package::abi::entry;
+            import package::abi::helper;
+            @compute @workgroup_size(1) fn main() { let x = entry() + helper(); }",
+            lazy,
+        )
+        .unwrap()
+        .to_string();
+        assert!(wgsl.contains("fn entry() -> u32 {\n    return package_abi_helper();"));
+        assert!(wgsl.contains("let x = entry() + package_abi_helper();"));
+        assert!(!wgsl.contains("package_abi_entry"));
+
+        let err = compile(
+            "@no_mangle import package::abi::helper as abi_helper;
+            fn helper() {}
+            @compute @workgroup_size(1) fn main() { helper(); let x = abi_helper(); }",
+            lazy,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            &*err.error,
+            crate::Error::ImportError(E::NoMangleCollision(name)) if name == "helper"
+        ));
+    }
+}
+
+#[test]
+fn test_block_scoped_imports() {
+    use crate::{CompileOptions, EscapeMangler, VirtualResolver};
+    let compile_with = |main: &str, lazy: bool, builtins: &[&str]| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", main.into());
+        resolver.add_module(
+            "util",
+            "fn scale(x: f32) -> f32 { return x * 2.0; }
+            fn offset(x: f32) -> f32 { return x + 1.0; }"
+                .into(),
+        );
+        let options = CompileOptions {
+            lazy,
+            extra_builtins: builtins.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        };
+        let root = ModulePath::from_path("/main");
+        crate::compile(&root, &resolver, &EscapeMangler, &options)
+    };
+    let compile = |main: &str, lazy: bool| compile_with(main, lazy, &[]);
+
+    let source = "@compute @workgroup_size(1) fn main() {
+        import package::util::scale;
+        let x = scale(2.0);
+        {
+            import package::util::offset as shift;
+            let y = shift(x);
+        }
+    }";
+    let parsed = wgsl_parse::parse_str(source).unwrap().to_string();
+    assert!(parsed.contains("{\n    import package::util::scale;\n    let x = scale(2.0);"));
+    assert_eq!(wgsl_parse::parse_str(&parsed).unwrap().to_string(), parsed);
+
+    for lazy in [true, false] {
+        let wgsl = compile(source, lazy).unwrap().to_string();
+        assert!(wgsl.contains("let x = package_util_scale(2.0);"));
+        assert!(wgsl.contains("let y = package_util_offset(x);"));
+        assert!(!wgsl.contains("import"));
+
+        for misuse in [
+            "@compute @workgroup_size(1) fn main() {
+                { import package::util::scale; let x = scale(1.0); }
+                let y = scale(2.0);
+            }",
+            "fn f() -> f32 { import package::util::scale; return scale(1.0); }
+            @compute @workgroup_size(1) fn main() { let y = scale(2.0); }",
+        ] {
+            let err = compile(misuse, lazy).unwrap_err();
+            assert!(matches!(
+                &*err.error,
+                crate::Error::ImportError(E::OutOfScope(name)) if name == "scale"
+            ));
+            // a user-provided builtin of the same name is in scope everywhere.
+            assert!(compile_with(misuse, lazy, &["scale"]).is_ok());
+        }
+
+        // local declarations and parameters of the same name are in scope.
+        for shadowed in [
+            "@compute @workgroup_size(1) fn main() {
+                { import package::util::scale; let x = scale(1.0); }
+                let scale = 2.0;
+                let y = scale;
+            }",
+            "fn f() -> f32 { import package::util::scale; return scale(1.0); }
+            fn g(scale: f32) -> f32 { return scale; }
+            @compute @workgroup_size(1) fn main() {
+                for (var scale = 0; scale < 2; scale++) { let y = scale; }
+                loop { let scale = 1; continuing { break if scale > 0; } }
+            }",
+        ] {
+            compile(shadowed, lazy).unwrap_or_else(|e| panic!("{e}"));
+        }
+    }
+}
+
+#[test]
+fn test_check_imports() {
+    use crate::VirtualResolver;
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{scale, missing};
+        import package::nope::thing;
+        @compute @workgroup_size(1) fn main() { let x = scale(thing) + missing; }"
+            .into(),
+    );
+    resolver.add_module(
+        "util",
+        "import package::math::double;
+        fn scale(x: f32) -> f32 { return double(x); }"
+            .into(),
+    );
+    resolver.add_module(
+        "math",
+        "fn double(x: f32) -> f32 { return x * 2.0; }".into(),
+    );
+    let root = ModulePath::from_path("/main");
+
+    let errors = check_imports(&root, &resolver).unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(errors
+        .iter()
+        .any(|e| matches!(e, E::ResolveError(ResolveError::ModuleNotFound(path, _)) if path.to_string() == "package::nope")));
+    assert!(errors
+        .iter()
+        .any(|e| matches!(e, E::MissingDecl(path, name) if path.to_string() == "package::util" && name == "missing")));
+
+    resolver.add_module(
+        "main",
+        "import package::util::scale;
+        @compute @workgroup_size(1) fn main() { let x = scale(1.0); }"
+            .into(),
+    );
+    check_imports(&root, &resolver).unwrap();
+
+    // modules can import each other, but re-exports cannot go in circles.
+    resolver.add_module(
+        "math",
+        "import package::util::scale;
+        fn double(x: f32) -> f32 { return x * 2.0; }
+        fn quad(x: f32) -> f32 { return scale(scale(x)); }"
+            .into(),
+    );
+    check_imports(&root, &resolver).unwrap();
+    resolver.add_module(
+        "main",
+        "import package::a::item;
+        @compute @workgroup_size(1) fn main() { let x = item; }"
+            .into(),
+    );
+    resolver.add_module("a", "import package::b::item; export item;".into());
+    resolver.add_module("b", "import package::a::item; export item;".into());
+    // the imports of `main`, `a` and `b` are all missing.
+    let errors = check_imports(&root, &resolver).unwrap_err();
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e, E::MissingDecl(_, name) if name == "item")));
+}
+
+#[test]
+fn test_declaration_dependencies() {
+    use crate::VirtualResolver;
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::noise::{perlin, Params as P};
+        import package::hash::hash2;
+        fn local(x: f32) -> f32 { return x; }
+        fn sample(p: vec2f, params: P) -> f32 {
+            return perlin(p, params) + local(f32(hash2(p))) + package::hash::hash1(p.x);
+        }
+        fn other(p: vec2f) -> u32 { return hash2(p); }"
+            .into(),
+    );
+    let path = |s: &str| ModulePath::from_path(s);
+
+    let deps = declaration_dependencies(&path("/main"), "sample", &resolver).unwrap();
+    assert_eq!(
+        deps,
+        [
+            (path("/noise"), "Params".to_string()),
+            (path("/noise"), "perlin".to_string()),
+            (path("/hash"), "hash2".to_string()),
+            (path("/hash"), "hash1".to_string()),
+        ]
+    );
+    let deps = declaration_dependencies(&path("/main"), "local", &resolver).unwrap();
+    assert!(deps.is_empty());
+    let err = declaration_dependencies(&path("/main"), "nope", &resolver).unwrap_err();
+    assert!(matches!(err, E::MissingDecl(_, name) if name == "nope"));
+}
+
+#[test]
+fn test_path_collisions() {
+    use crate::VirtualResolver;
+    let compile = |source: &str| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", source.into());
+        resolver.add_module("util", "fn scale(x: f32) -> f32 { return x; }".into());
+        crate::Wesl::new("")
+            .set_custom_resolver(resolver)
+            .compile("main")
+    };
+    let collision = |source: &str| match compile(source) {
+        Err(crate::Error::Error(err)) => match &*err.error {
+            crate::Error::ImportError(E::PathCollision(name, path)) => {
+                (name.clone(), path.to_string())
+            }
+            err => panic!("unexpected error: {err}"),
+        },
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("expected a path collision"),
+    };
+
+    let source = "import util::scale;
+        const util = 1.0;
+        @compute @workgroup_size(1) fn main() { let x = scale(util); }";
+    assert_eq!(collision(source), ("util".to_string(), "util".to_string()));
+    let err = compile(source).err().unwrap().to_string();
+    assert!(err.contains("rename the declaration, or write `package::`"));
+
+    let source = "fn util() {}
+        @compute @workgroup_size(1) fn main() { let x = util::scale(1.0); util(); }";
+    assert_eq!(collision(source), ("util".to_string(), "util".to_string()));
+
+    // `package::` disambiguates.
+    compile(
+        "import package::util::scale;
+        const util = 1.0;
+        @compute @workgroup_size(1) fn main() { let x = scale(util); }",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_type_only_imports() {
+    use crate::VirtualResolver;
+    let compile = |source: &str| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", source.into());
+        resolver.add_module(
+            "shapes",
+            "struct Circle { radius: f32 }
+            const UNIT = Circle(1.0);"
+                .into(),
+        );
+        crate::Wesl::new("")
+            .set_custom_resolver(resolver)
+            .compile("main")
+    };
+
+    // type positions are fine, and the qualifier survives a round-trip through display.
+    let source = "import type package::shapes::Circle;
+        import package::shapes::UNIT;
+        fn area(c: Circle) -> f32 { return c.radius * c.radius; }
+        @compute @workgroup_size(1) fn main() { var c: array<Circle, 2>; let a = area(UNIT); }";
+    compile(source).unwrap();
+    let wesl = source.parse::<TranslationUnit>().unwrap();
+    assert!(wesl.imports[0].type_only && !wesl.imports[1].type_only);
+    assert_eq!(wesl.imports[0].to_string(), "type package::shapes::Circle;");
+
+    // constructing the type is a value use.
+    let Err(crate::Error::Error(err)) = compile(
+        "import type package::shapes::Circle;
+        @compute @workgroup_size(1) fn main() { let c = Circle(2.0); }",
+    ) else {
+        panic!("expected a type-only import error")
+    };
+    assert!(
+        matches!(&*err.error, crate::Error::ImportError(E::TypeOnlyValue(name)) if name == "Circle")
+    );
+}
+
+#[test]
+fn test_conditional_imports() {
+    use crate::VirtualResolver;
+    let compile = |heavy: bool| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module(
+            "main",
+            "@if(HEAVY) import package::heavy::stuff;
+            @compute @workgroup_size(1) fn main() {
+                @if(HEAVY) { let x = stuff(); }
+            }"
+            .into(),
+        );
+        if heavy {
+            resolver.add_module("heavy", "fn stuff() -> u32 { return 1u; }".into());
+        }
+        let mut compiler = crate::Wesl::new("").set_custom_resolver(resolver);
+        compiler.set_feature("HEAVY", heavy);
+        compiler.compile("main")
+    };
+
+    // the feature is off: the module is never resolved, so its absence is not an error.
+    let res = compile(false).unwrap();
+    assert!(!res.to_string().contains("stuff"));
+
+    let res = compile(true).unwrap();
+    assert!(res.to_string().contains("fn package_heavy_stuff()"));
+}
+
+#[test]
+fn test_constructor_forms() {
+    use crate::VirtualResolver;
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{Light, Scalar, N};
+        @compute @workgroup_size(1) fn main() {
+            let a = Light(1.0);
+            let b = array<Light, 2>(a, a);
+            let c = array(Light(2.0), a);
+            let d = array<Light, N>();
+            let e = vec3<Scalar>(1.0);
+            let f = vec3(Scalar(1.0));
+            let g = bitcast<Scalar>(1u);
+            let h = array<array<Light, 1>, 1>(array<Light, 1>(a));
+            let i = package::util::Light(3.0);
+            let j = array<package::util::Light, 1>(a);
+        }"
+        .into(),
+    );
+    resolver.add_module(
+        "util",
+        "struct Light { power: f32 }
+        alias Scalar = f32;
+        const N = 3;"
+            .into(),
+    );
+    let res = crate::Wesl::new("")
+        .set_custom_resolver(resolver)
+        .compile("main")
+        .unwrap();
+    let expected = "
+            let a = package_util_Light(1.0);
+            let b = array<package_util_Light, 2>(a, a);
+            let c = array(package_util_Light(2.0), a);
+            let d = array<package_util_Light, package_util_N>();
+            let e = vec3<package_util_Scalar>(1.0);
+            let f = vec3(package_util_Scalar(1.0));
+            let g = bitcast<package_util_Scalar>(1u);
+            let h = array<array<package_util_Light, 1>, 1>(array<package_util_Light, 1>(a));
+            let i = package_util_Light(3.0);
+            let j = array<package_util_Light, 1>(a);";
+    let output = res.to_string();
+    for line in expected.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        assert!(output.contains(line), "missing `{line}` in:\n{output}");
+    }
+}
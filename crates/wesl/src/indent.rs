@@ -0,0 +1,66 @@
+use std::fmt::Display;
+
+/// Indentation of nested blocks in the output. See [`crate::CompileOptions::indent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    /// Indent with this number of spaces per level.
+    Spaces(usize),
+    /// Indent with one tab per level.
+    Tabs,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Self::Spaces(4)
+    }
+}
+
+impl Display for Indent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indent::Spaces(n) => write!(f, "{:n$}", ""),
+            Indent::Tabs => write!(f, "\t"),
+        }
+    }
+}
+
+#[test]
+fn test_indent() {
+    use crate::{CompileOptions, VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "@compute @workgroup_size(1) fn main() {
+        var x = 0;
+        for (var i = 0; i < 4; i++) { if i > 1 { x += i; } }
+        }"
+        .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+
+    compiler.set_options(CompileOptions {
+        indent: Indent::Spaces(2),
+        ..Default::default()
+    });
+    let expected = "@compute @workgroup_size(1)
+fn main() {
+  var x = 0;
+  for (var i = 0; i < 4; i++) {
+    if i > 1 {
+      x += i;
+    }
+  }
+}
+";
+    assert_eq!(compiler.compile("main").unwrap().to_string(), expected);
+
+    compiler.set_options(CompileOptions {
+        indent: Indent::Tabs,
+        ..Default::default()
+    });
+    let tabs = compiler.compile("main").unwrap().to_string();
+    assert_eq!(tabs, expected.replace("  ", "\t"));
+
+    let watch = crate::WatchCompiler::new(compiler, "main").unwrap();
+    assert_eq!(watch.output(), tabs);
+}
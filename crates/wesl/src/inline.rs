@@ -0,0 +1,154 @@
+use wgsl_parse::syntax::{
+    DeclarationKind, Expression, ExpressionNode, GlobalDeclaration, Ident, LiteralExpression,
+    Statement, StatementNode, TranslationUnit,
+};
+
+use crate::{syntax_util::all_types, visit::Visit};
+
+/// The value of a `let` initializer that can be inlined: a literal. Abstract literals are
+/// concretized like the `let` declaration does, so the inlined value has the same type.
+fn inlinable_value(expr: &Expression) -> Option<LiteralExpression> {
+    match expr {
+        Expression::Literal(lit) => match *lit {
+            LiteralExpression::AbstractInt(n) => i32::try_from(n).ok().map(LiteralExpression::I32),
+            LiteralExpression::AbstractFloat(n) => {
+                let n = n as f32;
+                n.is_finite().then_some(LiteralExpression::F32(n))
+            }
+            lit => Some(lit),
+        },
+        Expression::Parenthesized(p) => inlinable_value(&p.expression),
+        _ => None,
+    }
+}
+
+/// Find the untyped `let` declarations with an inlinable initializer.
+fn find_candidates(stmt: &StatementNode, res: &mut Vec<(Ident, LiteralExpression)>) {
+    if let Statement::Declaration(decl) = stmt.node() {
+        if decl.kind == DeclarationKind::Let && decl.ty.is_none() {
+            if let Some(value) = decl.initializer.as_ref().and_then(|e| inlinable_value(e)) {
+                res.push((decl.ident.clone(), value));
+            }
+        }
+    }
+    for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+        find_candidates(stmt, res);
+    }
+}
+
+/// The number of references to `ident` in the statements. The ident is shared by the
+/// declaration and its references.
+fn count_references(stmts: &[StatementNode], ident: &Ident) -> usize {
+    stmts
+        .iter()
+        .flat_map(|stmt| all_types(stmt.node()))
+        .filter(|ty| ty.ident == *ident)
+        .count()
+}
+
+fn substitute(expr: &mut ExpressionNode, ident: &Ident, value: LiteralExpression) -> bool {
+    if let Expression::TypeOrIdentifier(ty) = expr.node() {
+        if ty.ident == *ident {
+            *expr.node_mut() = Expression::Literal(value);
+            return true;
+        }
+    }
+    let rec = |expr: &mut ExpressionNode| substitute(expr, ident, value);
+    match expr.node_mut() {
+        Expression::Literal(_) | Expression::TypeOrIdentifier(_) => false,
+        Expression::Parenthesized(p) => rec(&mut p.expression),
+        Expression::NamedComponent(c) => rec(&mut c.base),
+        Expression::Indexing(i) => rec(&mut i.base) || rec(&mut i.index),
+        Expression::Unary(u) => rec(&mut u.operand),
+        Expression::Binary(b) => rec(&mut b.left) || rec(&mut b.right),
+        Expression::FunctionCall(call) => call.arguments.iter_mut().any(rec),
+    }
+}
+
+fn remove_decl(stmt: &mut StatementNode, ident: &Ident) -> bool {
+    if let Statement::Declaration(decl) = stmt.node() {
+        if decl.ident == *ident {
+            *stmt.node_mut() = Statement::Void;
+            return true;
+        }
+    }
+    Visit::<StatementNode>::visit_mut(stmt.node_mut()).any(|stmt| remove_decl(stmt, ident))
+}
+
+/// Inline `let` declarations initialized with a literal and used exactly once, e.g.
+/// `let x = 2.0; return v * x;` becomes `return v * 2f;`.
+///
+/// This is an opt-in pass, intended for minification. It is conservative: only literal
+/// initializers are inlined, which have no side effects and do not depend on mutable
+/// state. Declarations with an explicit type are left alone.
+///
+/// Identifiers must be retargeted, see [`crate::SyntaxUtil::retarget_idents`].
+pub fn inline_single_use_lets(wesl: &mut TranslationUnit) {
+    for decl in &mut wesl.global_declarations {
+        let GlobalDeclaration::Function(func) = decl else {
+            continue;
+        };
+        let mut candidates = Vec::new();
+        for stmt in &func.body.statements {
+            find_candidates(stmt, &mut candidates);
+        }
+        if candidates.is_empty() {
+            continue;
+        }
+        for (ident, value) in candidates {
+            if count_references(&func.body.statements, &ident) != 1 {
+                continue;
+            }
+            let found = func.body.statements.iter_mut().any(|stmt| {
+                Visit::<ExpressionNode>::visit_mut(stmt.node_mut())
+                    .any(|expr| substitute(expr, &ident, value))
+            });
+            // the use must be found, otherwise the declaration is kept.
+            if found {
+                func.body
+                    .statements
+                    .iter_mut()
+                    .any(|stmt| remove_decl(stmt, &ident));
+            }
+        }
+        func.body.remove_voids();
+    }
+}
+
+#[test]
+fn test_inline_single_use_lets() {
+    use crate::SyntaxUtil;
+    let source = "fn f(v: vec3f, c: bool) -> vec3f {
+        let a = 2.0;
+        let b = (3u);
+        let n = 1;
+        let multi = 1.0;
+        let typed: f32 = 1;
+        let call = g();
+        if c {
+            return v;
+        } else {
+            let w = v * a;
+            return w * f32(b) * f32(n);
+        }
+        return v * multi + multi * typed * call;
+    }";
+    let expected = "fn f(v: vec3f, c: bool) -> vec3f {
+    let multi = 1.0;
+    let typed: f32 = 1;
+    let call = g();
+    if c {
+        return v;
+    }
+    else {
+        let w = v * 2f;
+        return w * f32(3u) * f32(1i);
+    }
+    return v * multi + multi * typed * call;
+}
+";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+    inline_single_use_lets(&mut wgsl);
+    assert_eq!(wgsl.to_string(), expected);
+}
@@ -1,46 +1,110 @@
 #![doc = include_str!("../README.md")]
 
+pub mod builder;
+#[cfg(feature = "serde")]
+mod bundle;
 #[cfg(feature = "eval")]
 pub mod eval;
 #[cfg(feature = "generics")]
 mod generics;
+#[cfg(feature = "serde")]
+mod graph;
+#[cfg(feature = "naga")]
+mod naga;
 #[cfg(feature = "package")]
 mod package;
+#[cfg(feature = "eval")]
+mod reflect;
 
+mod bindings;
 mod builtin;
 mod condcomp;
+mod entry_io;
 mod error;
+mod feature;
+mod fragments;
+mod hoist;
 mod import;
+mod indent;
+mod inline;
 mod lower;
 mod mangle;
+mod metrics;
+mod normalize;
+mod parens;
+mod rename;
 mod resolve;
 mod sourcemap;
 mod strip;
 mod syntax_util;
+mod unreachable;
 mod validate;
+mod verbatim;
 mod visit;
+mod warning;
+mod watch;
 
+pub use bindings::{BindingAllocator, ResourceBinding};
+pub use builder::UnitBuilder;
 #[cfg(feature = "eval")]
 pub use eval::{Eval, EvalError, Exec};
 
 #[cfg(feature = "generics")]
 pub use generics::GenericsError;
 
+#[cfg(feature = "naga")]
+pub use naga::NagaError;
+
 #[cfg(feature = "package")]
 pub use package::PkgBuilder;
 
+#[cfg(feature = "eval")]
+pub use reflect::{BindingReflection, Layout, MemberLayout, ReflectionInfo, ResourceKind};
+
+#[cfg(feature = "serde")]
+pub use bundle::{BindingInfo, EntryPointInfo, MangledItem, ShaderBundle};
+#[cfg(feature = "serde")]
+pub use graph::{GraphEdge, GraphItem, GraphJson, GraphNode};
+
 pub use condcomp::CondCompError;
+pub use entry_io::{normalize_entry_params, EntryParams};
 pub use error::{Diagnostic, Error};
-pub use import::ImportError;
+pub use feature::Feature;
+pub use fragments::{DisplayEmitter, Emitter, FragmentCache};
+pub use import::{
+    check_imports, declaration_dependencies, ImportError, ImportGraph, ResolveLimits,
+};
+pub use indent::Indent;
+pub use inline::inline_single_use_lets;
 pub use lower::lower;
-pub use mangle::{CacheMangler, EscapeMangler, HashMangler, Mangler, NoMangler, UnicodeMangler};
+pub use mangle::{
+    CacheMangler, EscapeMangler, GlslSafeMangler, HashMangler, MangleMap, Mangler, NoMangler,
+    RandomMangler, UnicodeMangler,
+};
+pub use metrics::UnitMetrics;
+pub use normalize::{normalize_types, TypeSpelling};
+pub use parens::remove_redundant_parens;
+pub use rename::RenameError;
+#[cfg(feature = "log")]
+pub use resolve::LoggingResolver;
 pub use resolve::{
-    FileResolver, NoResolver, PkgModule, PkgResolver, Preprocessor, ResolveError, Resolver, Router,
-    StandardResolver, VirtualResolver,
+    ContentStoreResolver, DataUriResolver, FileResolver, FnResolver, NoResolver, OverlayResolver,
+    PkgModule, PkgResolver, Preprocessor, ResolveError, ResolvedUnitHook, Resolver, Router,
+    StandardResolver, StdResolver, SyncCacheResolver, VirtualResolver,
 };
-pub use sourcemap::{BasicSourceMap, SourceMap, SourceMapper};
+pub use sourcemap::{BasicSourceMap, ColumnUnit, LineIndex, SourceMap, SourceMapper};
+pub use strip::{assemble_filtered, trim_to_bindings, EntryPointFilter, ShaderStage, StageChunks};
 pub use syntax_util::SyntaxUtil;
-pub use validate::{validate_wesl, validate_wgsl, ValidateError};
+pub use unreachable::unreachable_code;
+pub use validate::{
+    validate_attributes, validate_declaration, validate_wesl, validate_wesl_with_builtins,
+    validate_wgsl, validate_wgsl_with_builtins, validate_workgroup_size, ValidateError,
+    ValidationContext, WorkgroupLimits,
+};
+#[cfg(feature = "eval")]
+pub use validate::{validate_const_functions, validate_struct_layouts};
+pub use warning::{DenyWarnings, DisplayWarning, Warning};
+pub use watch::WatchCompiler;
 pub use wgsl_parse::syntax;
 pub use wgsl_parse::syntax::ModulePath;
 
@@ -51,8 +115,13 @@ use std::{
 };
 
 use import::{Module, Resolutions};
+use mangle::SeededMangler;
+use resolve::{CacheResolver, HookResolver};
 use strip::strip_except;
-use wgsl_parse::syntax::{Ident, PathOrigin, TranslationUnit};
+use wgsl_parse::{
+    display_indented,
+    syntax::{Ident, PathOrigin, TranslationUnit},
+};
 
 /// Compilation options. Used in [`compile`] and [`Wesl::set_options`].
 #[derive(Debug)]
@@ -66,6 +135,83 @@ pub struct CompileOptions {
     pub lazy: bool,
     pub keep: Option<Vec<String>>,
     pub features: HashMap<String, bool>,
+    /// Additional names treated as built-in declarations by the validator, e.g. builtins
+    /// injected by a preprocessor or supported by a custom WGSL implementation.
+    pub extra_builtins: HashSet<String>,
+    /// Which warnings are escalated to errors. Default: none.
+    pub deny_warnings: DenyWarnings,
+    /// Warn about imported items that are never used. Default: `false`.
+    pub warn_unused_imports: bool,
+    /// Warn about statements that come after a `return`, `break`, `continue` or
+    /// `discard`. Default: `false`.
+    pub warn_unreachable_code: bool,
+    /// Limits on the size of the import graph. Default: no limits.
+    pub limits: ResolveLimits,
+    /// Reorder the output: consts and overrides first, then types, then variables, then
+    /// functions. Declarations still come after their dependencies.
+    pub hoist_consts: bool,
+    /// Reorder struct and alias declarations so that each comes after the types it
+    /// refers to. Other declarations are not moved.
+    pub order_types: bool,
+    /// Mangled names of a previous compilation, reused for the same items before minting
+    /// new names. See [`CompileResult::mangle_map`].
+    pub mangle_map: MangleMap,
+    /// Keep the import statements of all modules as comments at the top of the output,
+    /// to see what the output was composed of. See [`CompileResult::import_comments`].
+    pub preserve_import_comments: bool,
+    /// Called on each module after it is parsed (and conditionally translated), before
+    /// imports are linked and names mangled. Default: none.
+    pub on_resolved_unit: Option<ResolvedUnitHook>,
+    /// Error if the output has no `@vertex`, `@fragment` or `@compute` function.
+    /// Default: false.
+    pub require_entry_point: bool,
+    /// Custom attributes allowed by the validator, in addition to the standard WGSL
+    /// attributes and those consumed by the compiler. Other attributes are errors when
+    /// set. Default: none, any attribute is allowed. See [`validate_attributes`].
+    pub known_attributes: Option<HashSet<String>>,
+    /// Indentation of nested blocks in the output. Default: 4 spaces.
+    pub indent: Indent,
+    /// Also mangle the declarations of the root module, except entry points, resource
+    /// bindings and overrides. Use it to embed several compiled shaders in a shared
+    /// namespace without collisions of their private helpers. Default: false.
+    pub mangle_entry_locals: bool,
+    /// Replace uses of type aliases with the aliased type and remove the alias
+    /// declarations. Some backends do not like type aliases. Default: false.
+    pub inline_type_aliases: bool,
+    /// Assign `@group` and `@binding` attributes to resource variables declared without
+    /// them. The assignment is reported by [`CompileResult::bindings`]. Default: none.
+    pub auto_bindings: Option<BindingAllocator>,
+    /// Check the `@workgroup_size` of compute entry points against these limits. See
+    /// [`validate_workgroup_size`]. Default: none.
+    pub workgroup_limits: Option<WorkgroupLimits>,
+    /// Validate the final output with naga, after all other transforms. Errors are
+    /// reported in the source modules, like other validation errors. Default: false.
+    #[cfg(feature = "naga")]
+    pub naga: bool,
+    /// Rewrite `while` loops to `loop` statements with a leading `if !(cond) { break; }`,
+    /// for backends that only handle the canonical `loop` form. Default: false.
+    pub lower_while_loops: bool,
+    /// Emit the root module source as written, followed by the imported declarations.
+    /// Import statements are removed from the root source, and references to imported
+    /// declarations are replaced with their mangled names. The rest of the root text,
+    /// including comments and formatting, is untouched. See [`CompileResult::verbatim`].
+    ///
+    /// The root module must be plain WGSL apart from its imports: the root text does not
+    /// go through conditional compilation or the other transforms. Default: false.
+    pub preserve_entry_verbatim: bool,
+    /// Merge module-scope consts that have the same type and the same constructor
+    /// initializer, e.g. identical lookup tables declared in several modules. References
+    /// are rewritten to the merged declaration. Default: false.
+    pub dedup_consts: bool,
+    /// Check that every struct of the output has a well-defined memory layout: only the
+    /// last member may be a runtime-sized array, and every member type must have a size
+    /// and an alignment. This requires the `eval` feature. Default: false.
+    #[cfg(feature = "eval")]
+    pub validate_layouts: bool,
+    /// If stripping is enabled, also keep the entry points of the root module selected by
+    /// the filter, in addition to the names in [`Self::keep`]. When set and `keep` is unset,
+    /// only the selected entry points are kept. Default: none.
+    pub keep_filter: Option<EntryPointFilter>,
 }
 
 impl Default for CompileOptions {
@@ -80,6 +226,31 @@ impl Default for CompileOptions {
             lazy: true,
             keep: Default::default(),
             features: Default::default(),
+            extra_builtins: Default::default(),
+            deny_warnings: Default::default(),
+            warn_unused_imports: false,
+            warn_unreachable_code: false,
+            limits: Default::default(),
+            hoist_consts: false,
+            order_types: false,
+            mangle_map: Default::default(),
+            preserve_import_comments: false,
+            on_resolved_unit: None,
+            require_entry_point: false,
+            known_attributes: None,
+            indent: Default::default(),
+            mangle_entry_locals: false,
+            inline_type_aliases: false,
+            auto_bindings: None,
+            workgroup_limits: None,
+            #[cfg(feature = "naga")]
+            naga: false,
+            lower_while_loops: false,
+            preserve_entry_verbatim: false,
+            dedup_consts: false,
+            #[cfg(feature = "eval")]
+            validate_layouts: false,
+            keep_filter: None,
         }
     }
 }
@@ -170,6 +341,7 @@ impl Wesl<StandardResolver> {
                 lazy: true,
                 keep: None,
                 features: Default::default(),
+                ..Default::default()
             },
             use_sourcemap: true,
             resolver: StandardResolver::new(base),
@@ -200,6 +372,7 @@ impl Wesl<StandardResolver> {
                 lazy: true,
                 keep: None,
                 features: Default::default(),
+                ..Default::default()
             },
             use_sourcemap: true,
             resolver: StandardResolver::new(base),
@@ -250,6 +423,7 @@ impl Wesl<NoResolver> {
                 lazy: false,
                 keep: None,
                 features: Default::default(),
+                ..Default::default()
             },
             use_sourcemap: false,
             resolver: NoResolver,
@@ -400,6 +574,162 @@ impl<R: Resolver> Wesl<R> {
         self.options.features.remove(feat);
         self
     }
+    /// Register additional built-in names, which are not reported as undefined symbols
+    /// during validation.
+    ///
+    /// Use this when a preprocessor or a custom WGSL implementation injects builtins.
+    pub fn add_builtins<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        self.options
+            .extra_builtins
+            .extend(names.into_iter().map(str::to_string));
+        self
+    }
+    /// Reorder the output so that global consts and overrides come first, then types,
+    /// then variables, then functions. Some backends are happier with that order.
+    ///
+    /// Declarations are never moved before declarations they depend on.
+    pub fn hoist_consts(&mut self, val: bool) -> &mut Self {
+        self.options.hoist_consts = val;
+        self
+    }
+    /// Reorder struct and alias declarations so that each comes after the types it
+    /// refers to, e.g. an imported struct after the imported struct of its fields.
+    ///
+    /// WGSL does not require it, but some backends and readers prefer it.
+    pub fn order_types(&mut self, val: bool) -> &mut Self {
+        self.options.order_types = val;
+        self
+    }
+    /// Keep the import statements of all modules as comments at the top of the output.
+    /// Useful to debug where the output declarations come from.
+    ///
+    /// See [`CompileResult::import_comments`].
+    pub fn preserve_import_comments(&mut self, val: bool) -> &mut Self {
+        self.options.preserve_import_comments = val;
+        self
+    }
+    /// Transform each module after it is resolved, before imports are linked and names
+    /// are mangled. Declarations added by the callback are mangled like the others.
+    ///
+    /// See [`CompileOptions::on_resolved_unit`].
+    pub fn on_resolved_unit(
+        &mut self,
+        hook: impl Fn(&ModulePath, &mut TranslationUnit) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.options.on_resolved_unit = Some(ResolvedUnitHook::new(hook));
+        self
+    }
+    /// Error if the output has no entry point function. Useful for pipeline assets,
+    /// where an entry module without entry points is almost always a mistake.
+    pub fn require_entry_point(&mut self, val: bool) -> &mut Self {
+        self.options.require_entry_point = val;
+        self
+    }
+    /// Only allow the given custom attributes, in addition to the standard WGSL
+    /// attributes. Unknown attributes, e.g. a typo like `@vertx`, are validation errors.
+    ///
+    /// See [`CompileOptions::known_attributes`].
+    pub fn known_attributes<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        self.options
+            .known_attributes
+            .get_or_insert_with(Default::default)
+            .extend(names.into_iter().map(str::to_string));
+        self
+    }
+    /// Mangle the private declarations of the root module too.
+    ///
+    /// See [`CompileOptions::mangle_entry_locals`].
+    pub fn mangle_entry_locals(&mut self, val: bool) -> &mut Self {
+        self.options.mangle_entry_locals = val;
+        self
+    }
+    /// Inline the type aliases in the output.
+    ///
+    /// See [`CompileOptions::inline_type_aliases`].
+    pub fn inline_type_aliases(&mut self, val: bool) -> &mut Self {
+        self.options.inline_type_aliases = val;
+        self
+    }
+    /// Assign bindings to resource variables declared without `@group` and `@binding`.
+    ///
+    /// See [`CompileOptions::auto_bindings`].
+    pub fn auto_bindings(&mut self, allocator: BindingAllocator) -> &mut Self {
+        self.options.auto_bindings = Some(allocator);
+        self
+    }
+    /// Check that compute workgroup sizes are within the device limits.
+    ///
+    /// See [`CompileOptions::workgroup_limits`].
+    pub fn workgroup_limits(&mut self, limits: WorkgroupLimits) -> &mut Self {
+        self.options.workgroup_limits = Some(limits);
+        self
+    }
+    /// Enable naga validation of the output.
+    ///
+    /// See [`CompileOptions::naga`].
+    #[cfg(feature = "naga")]
+    pub fn use_naga(&mut self, val: bool) -> &mut Self {
+        self.options.naga = val;
+        self
+    }
+    /// Rewrite `while` loops to `loop` statements.
+    ///
+    /// See [`CompileOptions::lower_while_loops`].
+    pub fn lower_while_loops(&mut self, val: bool) -> &mut Self {
+        self.options.lower_while_loops = val;
+        self
+    }
+    /// Emit the root module source as written, followed by the imported declarations.
+    ///
+    /// See [`CompileOptions::preserve_entry_verbatim`].
+    pub fn preserve_entry_verbatim(&mut self, val: bool) -> &mut Self {
+        self.options.preserve_entry_verbatim = val;
+        self
+    }
+    /// Merge identical module-scope const arrays and structs into one declaration.
+    ///
+    /// See [`CompileOptions::dedup_consts`].
+    pub fn dedup_consts(&mut self, val: bool) -> &mut Self {
+        self.options.dedup_consts = val;
+        self
+    }
+    /// Check that every struct of the output has a well-defined memory layout. Requires
+    /// the `eval` feature.
+    ///
+    /// See [`CompileOptions::validate_layouts`].
+    #[cfg(feature = "eval")]
+    pub fn validate_layouts(&mut self, val: bool) -> &mut Self {
+        self.options.validate_layouts = val;
+        self
+    }
+    /// Indent nested blocks of the output with spaces or tabs. Default: 4 spaces.
+    pub fn indent(&mut self, indent: Indent) -> &mut Self {
+        self.options.indent = indent;
+        self
+    }
+    /// Escalate warnings to errors, in all modules or only in select modules.
+    ///
+    /// ```rust
+    /// # use wesl::{DenyWarnings, ModulePath, Wesl};
+    /// // be strict with first-party code, but not with vendored dependencies.
+    /// let vendor = ModulePath::from_path("/vendor");
+    /// let mut compiler = Wesl::new("src/shaders");
+    /// compiler.deny_warnings(DenyWarnings::modules(move |path| !path.starts_with(&vendor)));
+    /// ```
+    pub fn deny_warnings(&mut self, deny: DenyWarnings) -> &mut Self {
+        self.options.deny_warnings = deny;
+        self
+    }
+    /// Warn about imported items that are never used, see [`Warning::UnusedImport`].
+    pub fn warn_unused_imports(&mut self, val: bool) -> &mut Self {
+        self.options.warn_unused_imports = val;
+        self
+    }
+    /// Warn about unreachable statements, see [`Warning::UnreachableCode`].
+    pub fn warn_unreachable_code(&mut self, val: bool) -> &mut Self {
+        self.options.warn_unreachable_code = val;
+        self
+    }
     /// Remove unused declarations from the final WGSL output.
     ///
     /// Unused declarations are all declarations not used (directly or indirectly) by any
@@ -458,6 +788,24 @@ impl<R: Resolver> Wesl<R> {
         self.options.keep = Some(entries);
         self
     }
+
+    /// If stripping is enabled, keep the entry points selected by `filter`, which is
+    /// called with the name, stage and attributes of each entry point of the root module.
+    /// See [`CompileOptions::keep_filter`].
+    ///
+    /// ```rust
+    /// # use wesl::{ShaderStage, Wesl};
+    /// let mut compiler = Wesl::new("");
+    /// compiler.keep_entrypoints_where(|_, stage, _| stage == ShaderStage::Compute);
+    /// ```
+    pub fn keep_entrypoints_where(
+        &mut self,
+        filter: impl Fn(&str, ShaderStage, &[syntax::Attribute]) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.options.keep_filter = Some(EntryPointFilter::new(filter));
+        self
+    }
+
     /// If stripping is enabled, keep all entrypoints in the root WESL module.
     /// This is the default. See [`Wesl::keep_entrypoints`].
     ///
@@ -469,6 +817,7 @@ impl<R: Resolver> Wesl<R> {
     /// Spec: not yet available.
     pub fn keep_all_entrypoints(&mut self) -> &mut Self {
         self.options.keep = None;
+        self.options.keep_filter = None;
         self
     }
 }
@@ -479,10 +828,27 @@ impl<R: Resolver> Wesl<R> {
 /// [`Wesl`] was invoked with sourcemapping enabled.
 ///
 /// This type implements `Display`, call `to_string()` to get the compiled WGSL.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CompileResult {
     pub syntax: TranslationUnit,
     pub sourcemap: Option<BasicSourceMap>,
+    /// Warnings emitted during compilation, that were not escalated to errors.
+    pub warnings: Vec<Diagnostic<Warning>>,
+    /// Mangled names of this compilation. Pass it to [`CompileOptions::mangle_map`] to
+    /// keep names stable across recompilations.
+    pub mangle_map: MangleMap,
+    /// Import statements of the modules composing the output, if
+    /// [`CompileOptions::preserve_import_comments`] is set. They are displayed as `//`
+    /// comments before the output.
+    pub import_comments: Vec<String>,
+    /// Indentation used when displaying the output. See [`CompileOptions::indent`].
+    pub indent: Indent,
+    /// The root module source with the imported declarations appended, if
+    /// [`CompileOptions::preserve_entry_verbatim`] is set. It is displayed instead of
+    /// [`Self::syntax`].
+    pub verbatim: Option<String>,
+    /// Modules that the output is composed of, the root module first.
+    pub modules: Vec<ModulePath>,
 }
 
 impl CompileResult {
@@ -493,7 +859,44 @@ impl CompileResult {
 
 impl Display for CompileResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.syntax.fmt(f)
+        for import in &self.import_comments {
+            writeln!(f, "// {import}")?;
+        }
+        if !self.import_comments.is_empty() {
+            writeln!(f)?;
+        }
+        if let Some(verbatim) = &self.verbatim {
+            f.write_str(verbatim)
+        } else {
+            display_indented(&self.syntax, &self.indent.to_string()).fmt(f)
+        }
+    }
+}
+
+/// The result of [`Wesl::compile_project`].
+pub struct ProjectReport {
+    /// The entry points, in the order they were given, with their compilation result.
+    pub results: Vec<(ModulePath, Result<CompileResult, Error>)>,
+}
+
+impl ProjectReport {
+    /// Whether all entry points compiled successfully.
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(|(_, res)| res.is_ok())
+    }
+
+    /// The entry points that compiled successfully.
+    pub fn successes(&self) -> impl Iterator<Item = (&ModulePath, &CompileResult)> {
+        self.results
+            .iter()
+            .filter_map(|(path, res)| res.as_ref().ok().map(|res| (path, res)))
+    }
+
+    /// The entry points that failed to compile, with their error.
+    pub fn failures(&self) -> impl Iterator<Item = (&ModulePath, &Error)> {
+        self.results
+            .iter()
+            .filter_map(|(path, res)| res.as_ref().err().map(|err| (path, err)))
     }
 }
 
@@ -634,23 +1037,123 @@ impl<R: Resolver> Wesl<R> {
     /// # WESL Reference
     /// Spec: not available yet.
     pub fn compile(&self, root: impl Into<ModulePath>) -> Result<CompileResult, Error> {
+        self.compile_with(root.into(), &self.resolver, &mut Default::default())
+    }
+
+    /// Compile several entry points, e.g. all the shaders of a project.
+    ///
+    /// Unlike [`Wesl::compile`], a failing entry point does not abort the build: the
+    /// report contains the result of each entry point. Modules shared by several entry
+    /// points are parsed once.
+    pub fn compile_project<P: Into<ModulePath>>(
+        &self,
+        entries: impl IntoIterator<Item = P>,
+    ) -> ProjectReport {
+        let resolver = CacheResolver::new(&self.resolver);
+        let results = entries
+            .into_iter()
+            .map(|root| {
+                let root = root.into();
+                let res = self.compile_with(root.clone(), &resolver, &mut Default::default());
+                (root, res)
+            })
+            .collect();
+        ProjectReport { results }
+    }
+
+    /// Check that all imports reachable from `root` resolve, without compiling.
+    ///
+    /// Conditional compilation is applied first if enabled, with the configured
+    /// features. See [`check_imports`].
+    pub fn check_imports(&self, root: impl Into<ModulePath>) -> Result<(), Vec<ImportError>> {
         let mut root = root.into();
+        root.origin = PathOrigin::Absolute;
+        if self.options.condcomp {
+            let resolver = Preprocessor::new(&self.resolver, |wesl| {
+                condcomp::run(wesl, &self.options.features)?;
+                Ok(())
+            });
+            check_imports(&root, &resolver)
+        } else {
+            check_imports(&root, &self.resolver)
+        }
+    }
+
+    fn compile_with(
+        &self,
+        mut root: ModulePath,
+        resolver: &impl Resolver,
+        outputs: &mut CompileOutputs,
+    ) -> Result<CompileResult, Error> {
         root.origin = PathOrigin::Absolute; // we force absolute paths
 
-        if self.use_sourcemap {
+        let (syntax, sourcemap) = if self.use_sourcemap {
             let (syntax, sourcemap) =
-                compile_sourcemap(&root, &self.resolver, &self.mangler, &self.options);
-            Ok(CompileResult {
-                syntax: syntax?,
-                sourcemap: Some(sourcemap),
-            })
+                compile_sourcemap_impl(&root, resolver, &self.mangler, &self.options, outputs);
+            (syntax?, Some(sourcemap))
         } else {
-            let syntax = compile(&root, &self.resolver, &self.mangler, &self.options);
-            Ok(CompileResult {
-                syntax: syntax?,
-                sourcemap: None,
-            })
+            let syntax = compile_impl(&root, resolver, &self.mangler, &self.options, outputs);
+            (syntax?, None)
+        };
+        self.finish(&root, resolver, syntax, sourcemap, outputs)
+    }
+
+    /// Build the [`CompileResult`] of a compiled syntax tree: the verbatim output and the
+    /// final validation.
+    fn finish(
+        &self,
+        root: &ModulePath,
+        resolver: &impl Resolver,
+        syntax: TranslationUnit,
+        sourcemap: Option<BasicSourceMap>,
+        outputs: &mut CompileOutputs,
+    ) -> Result<CompileResult, Error> {
+        let verbatim = self.verbatim(root, resolver, &syntax, &outputs.mangle_map)?;
+        let res = CompileResult {
+            syntax,
+            sourcemap,
+            warnings: std::mem::take(&mut outputs.warnings),
+            mangle_map: std::mem::take(&mut outputs.mangle_map),
+            import_comments: std::mem::take(&mut outputs.import_comments),
+            indent: self.options.indent,
+            verbatim,
+            modules: std::mem::take(&mut outputs.modules),
+        };
+        // last, on the text that is actually emitted.
+        #[cfg(feature = "naga")]
+        if self.options.naga {
+            naga::naga_validate(&res.syntax, res.to_string()).map_err(|e| {
+                match &res.sourcemap {
+                    Some(sourcemap) => e
+                        .with_sourcemap(sourcemap)
+                        .unmangle(Some(sourcemap), Some(&self.mangler)),
+                    None => e,
+                }
+            })?;
         }
+        Ok(res)
+    }
+
+    /// The root module source spliced with the imported declarations, if
+    /// [`CompileOptions::preserve_entry_verbatim`] is set.
+    fn verbatim(
+        &self,
+        root: &ModulePath,
+        resolver: &impl Resolver,
+        syntax: &TranslationUnit,
+        mangle_map: &MangleMap,
+    ) -> Result<Option<String>, Error> {
+        if !self.options.preserve_entry_verbatim {
+            return Ok(None);
+        }
+        let source = resolver.resolve_source(root)?;
+        let (module, spans) = wgsl_parse::parse_str_with_spans(&source).map_err(|e| {
+            Diagnostic::from(e)
+                .with_module_path(root.clone(), resolver.display_name(root))
+                .with_source(source.to_string())
+        })?;
+        let res = verbatim::splice_entry(&source, &spans, root, &module, syntax, mangle_map)?;
+        Ok(Some(res))
     }
 
     /// Compile a WESL program from a root file and output the result in rust's `OUT_DIR`.
@@ -688,12 +1191,27 @@ impl<R: Resolver> Wesl<R> {
 }
 
 /// What idents to keep from the root module. They should be either:
+/// * options.keep idents that exist and entrypoints matching options.keep_filter, if
+///   options.keep_filter is set and options.strip is enabled,
 /// * options.keep idents that exist, if it is set and options.strip is enabled,
 /// * all entrypoints, if options.strip is enabled and options.keep is not set,
 /// * all named declarations, if options.strip is disabled.
-fn keep_idents(wesl: &TranslationUnit, keep: &Option<Vec<String>>, strip: bool) -> HashSet<Ident> {
-    if strip {
-        if let Some(keep) = keep {
+fn keep_idents(wesl: &TranslationUnit, options: &CompileOptions) -> HashSet<Ident> {
+    if options.strip {
+        if let Some(filter) = &options.keep_filter {
+            let keep = options.keep.as_deref().unwrap_or_default();
+            wesl.global_declarations
+                .iter()
+                .filter(|decl| {
+                    filter.matches(decl)
+                        || decl
+                            .ident()
+                            .is_some_and(|id| keep.iter().any(|name| name == &*id.name()))
+                })
+                .filter_map(|decl| decl.ident())
+                .cloned()
+                .collect()
+        } else if let Some(keep) = &options.keep {
             wesl.global_declarations
                 .iter()
                 .filter_map(|decl| {
@@ -717,11 +1235,14 @@ fn keep_idents(wesl: &TranslationUnit, keep: &Option<Vec<String>>, strip: bool)
     }
 }
 
-fn compile_pre_assembly(
-    root: &ModulePath,
-    resolver: &impl Resolver,
-    options: &CompileOptions,
-) -> Result<(Resolutions, HashSet<Ident>), Error> {
+/// The resolver of the modules of a compilation: conditional compilation and the
+/// [`CompileOptions::on_resolved_unit`] hook run on each module.
+fn compile_resolver<'a>(
+    resolver: &'a impl Resolver,
+    options: &'a CompileOptions,
+) -> Box<dyn Resolver + 'a> {
+    // condcomp runs on each module as it is resolved, before its imports are: imports
+    // gated by an `@if` that evaluates to false are never resolved.
     let resolver: Box<dyn Resolver> = if options.condcomp {
         Box::new(Preprocessor::new(resolver, |wesl| {
             condcomp::run(wesl, &options.features)?;
@@ -730,14 +1251,29 @@ fn compile_pre_assembly(
     } else {
         Box::new(resolver)
     };
+    match &options.on_resolved_unit {
+        Some(hook) => Box::new(HookResolver { resolver, hook }),
+        None => resolver,
+    }
+}
+
+fn compile_pre_assembly(
+    root: &ModulePath,
+    resolver: &impl Resolver,
+    options: &CompileOptions,
+    warnings: &mut Vec<Diagnostic<Warning>>,
+) -> Result<(Resolutions, HashSet<Ident>), Error> {
+    let resolver = compile_resolver(resolver, options);
 
     let mut wesl = resolver.resolve_module(root)?;
     wesl.retarget_idents();
-    let keep = keep_idents(&wesl, &options.keep, options.strip);
+    let keep = keep_idents(&wesl, options);
 
     let mut resolutions = Resolutions::new();
     let module = Module::new(wesl, root.clone())?;
     resolutions.push_module(module);
+    resolutions.set_limits(options.limits);
+    resolutions.set_mangle_root(options.mangle_entry_locals);
 
     if options.imports {
         if options.lazy {
@@ -747,18 +1283,95 @@ fn compile_pre_assembly(
         }
     }
 
+    for module in resolutions.modules() {
+        let module = module.borrow();
+        module
+            .check_block_scopes(&options.extra_builtins)
+            .map_err(|e| {
+                Diagnostic::from(e)
+                    .with_module_path(module.path.clone(), resolver.display_name(&module.path))
+            })?;
+        if options.warn_unused_imports {
+            for id in module.unused_imports() {
+                let warning = Diagnostic::from(Warning::UnusedImport(id.to_string()))
+                    .with_module_path(module.path.clone(), resolver.display_name(&module.path));
+                options.deny_warnings.emit(warning, warnings)?;
+            }
+        }
+        if options.warn_unreachable_code {
+            for warning in unreachable_code(&module.source) {
+                let warning = warning
+                    .with_module_path(module.path.clone(), resolver.display_name(&module.path));
+                options.deny_warnings.emit(warning, warnings)?;
+            }
+        }
+    }
+
+    for (path, warning) in resolutions.deprecated_uses() {
+        let disp_name = resolver.display_name(&path);
+        let warning = warning.with_module_path(path, disp_name);
+        options.deny_warnings.emit(warning, warnings)?;
+    }
+    resolutions.remove_deprecated_attrs();
+
     if options.validate {
+        // references to imported declarations are linked to the other modules.
+        let external = resolutions
+            .modules()
+            .flat_map(|module| {
+                let module = module.borrow();
+                let decls = module.source.global_declarations.iter();
+                decls
+                    .filter_map(|decl| decl.ident().cloned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<HashSet<_>>();
         for module in resolutions.modules() {
             let module = module.borrow();
-            validate_wesl(&module.source).map_err(|d| {
-                d.with_module_path(module.path.clone(), resolver.display_name(&module.path))
-            })?;
+            validate::validate_module(&module.source, &options.extra_builtins, &external)
+                .and_then(|()| match &options.known_attributes {
+                    Some(known) => validate_attributes(&module.source, known),
+                    None => Ok(()),
+                })
+                .map_err(|d| {
+                    d.with_module_path(module.path.clone(), resolver.display_name(&module.path))
+                })?;
         }
     }
 
     Ok((resolutions, keep))
 }
 
+/// Report declarations that were mangled to the same name.
+///
+/// The assembled output would fail validation with a less helpful duplicate declaration
+/// error, so collisions are errors when validation is enabled.
+fn emit_mangle_collisions(
+    origins: &[(Ident, ModulePath, String)],
+    resolver: &impl Resolver,
+    options: &CompileOptions,
+    warnings: &mut Vec<Diagnostic<Warning>>,
+) -> Result<(), Diagnostic<Error>> {
+    for (path, warning) in Resolutions::mangle_collisions(origins) {
+        let disp_name = resolver.display_name(&path);
+        match warning {
+            Warning::MangleCollision {
+                name,
+                first,
+                second,
+            } if options.validate => {
+                let error = ValidateError::MangleCollision(name, first, second);
+                return Err(Diagnostic::from(error).with_module_path(path, disp_name));
+            }
+            warning => {
+                let warning = Diagnostic::from(warning).with_module_path(path, disp_name);
+                options.deny_warnings.emit(warning, warnings)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn compile_post_assembly(
     wesl: &mut TranslationUnit,
     options: &CompileOptions,
@@ -769,30 +1382,105 @@ fn compile_post_assembly(
         generics::generate_variants(wesl)?;
         generics::replace_calls(wesl)?;
     };
+    if options.inline_type_aliases {
+        lower::inline_type_aliases(wesl)?;
+    }
+    if options.require_entry_point && wesl.entry_points().next().is_none() {
+        return Err(ValidateError::NoEntryPoint.into());
+    }
     if options.validate {
-        validate_wgsl(wesl)?;
+        validate_wgsl_with_builtins(wesl, &options.extra_builtins)?;
     }
     if options.lower {
         lower(wesl)?;
     }
+    if options.lower_while_loops {
+        lower::lower_while_loops(wesl);
+    }
+    if let Some(limits) = &options.workgroup_limits {
+        validate_workgroup_size(wesl, limits)?;
+    }
+    #[cfg(feature = "eval")]
+    if options.validate_layouts {
+        validate_struct_layouts(wesl)?;
+    }
     if options.strip {
         strip_except(wesl, keep);
     }
+    if options.dedup_consts {
+        hoist::dedup_consts(wesl);
+    }
+    // after stripping, so that unused resources do not take up bindings.
+    if let Some(allocator) = &options.auto_bindings {
+        bindings::assign_bindings(wesl, allocator);
+    }
+    if options.order_types {
+        hoist::order_types(wesl);
+    }
+    if options.hoist_consts {
+        hoist::hoist_consts(wesl);
+    }
     Ok(())
 }
 
+/// What a compilation produces besides the syntax tree. See [`CompileResult`].
+#[derive(Default)]
+struct CompileOutputs {
+    /// Warnings that were not escalated to errors.
+    warnings: Vec<Diagnostic<Warning>>,
+    mangle_map: MangleMap,
+    /// Filled if [`CompileOptions::preserve_import_comments`] is set.
+    import_comments: Vec<String>,
+    modules: Vec<ModulePath>,
+    /// Set to keep the syntax tree before the post-assembly passes in `assembly`.
+    keep_assembly: bool,
+    assembly: Option<watch::Assembly>,
+}
+
 /// Low-level version of [`Wesl::compile`].
+///
+/// Warnings that are not escalated to errors are discarded.
 pub fn compile(
     root: &ModulePath,
     resolver: &impl Resolver,
     mangler: &impl Mangler,
     options: &CompileOptions,
 ) -> Result<TranslationUnit, Diagnostic<Error>> {
-    let (mut resolutions, keep) = compile_pre_assembly(root, resolver, options)?;
-    resolutions.mangle(mangler);
-    let mut assembly = resolutions.assemble(options.strip && options.lazy);
+    compile_impl(root, resolver, mangler, options, &mut Default::default())
+}
+
+fn compile_impl(
+    root: &ModulePath,
+    resolver: &impl Resolver,
+    mangler: &impl Mangler,
+    options: &CompileOptions,
+    outputs: &mut CompileOutputs,
+) -> Result<TranslationUnit, Diagnostic<Error>> {
+    mangler.reset();
+    let warnings = &mut outputs.warnings;
+    let (mut resolutions, keep) = compile_pre_assembly(root, resolver, options, warnings)?;
+    if options.preserve_import_comments {
+        outputs.import_comments = resolutions.import_statements();
+    }
+    outputs.modules = resolutions.module_paths().cloned().collect();
+    let index = outputs
+        .keep_assembly
+        .then(|| watch::ModuleIndex::new(&resolutions));
+    let mangler = SeededMangler::new(mangler, &options.mangle_map);
+    let origins = resolutions.decl_origins();
+    resolutions.mangle(&mangler)?;
+    emit_mangle_collisions(&origins, resolver, options, warnings)?;
+    std::mem::drop(origins); // origins hold idents use-counts
+    outputs.mangle_map = mangler.finish();
+    let strip = options.strip && options.lazy;
+    let mut assembly = resolutions.assemble(strip);
+    if let Some(index) = index {
+        let provenance = resolutions.provenance(strip);
+        outputs.assembly = Some(watch::Assembly::new(index, &assembly, provenance, &keep));
+    }
     std::mem::drop(resolutions); // resolutions hold idents use-counts
     compile_post_assembly(&mut assembly, options, &keep)?;
+    feature::emit_missing_features(&assembly, options, warnings)?;
     Ok(assembly)
 }
 
@@ -803,13 +1491,51 @@ pub fn compile_sourcemap(
     mangler: &impl Mangler,
     options: &CompileOptions,
 ) -> (Result<TranslationUnit, Error>, BasicSourceMap) {
-    let sourcemapper = SourceMapper::new(root, resolver, mangler);
+    compile_sourcemap_impl(root, resolver, mangler, options, &mut Default::default())
+}
 
-    match compile_pre_assembly(root, &sourcemapper, options) {
+fn compile_sourcemap_impl(
+    root: &ModulePath,
+    resolver: &impl Resolver,
+    mangler: &impl Mangler,
+    options: &CompileOptions,
+    outputs: &mut CompileOutputs,
+) -> (Result<TranslationUnit, Error>, BasicSourceMap) {
+    mangler.reset();
+    let warnings = &mut outputs.warnings;
+    let seeded = SeededMangler::new(mangler, &options.mangle_map);
+    let sourcemapper = SourceMapper::new(root, resolver, &seeded);
+
+    match compile_pre_assembly(root, &sourcemapper, options, warnings) {
         Ok((mut resolutions, keep)) => {
-            resolutions.mangle(&sourcemapper);
+            if options.preserve_import_comments {
+                outputs.import_comments = resolutions.import_statements();
+            }
+            outputs.modules = resolutions.module_paths().cloned().collect();
+            let index = outputs
+                .keep_assembly
+                .then(|| watch::ModuleIndex::new(&resolutions));
+            let origins = resolutions.decl_origins();
+            let mangled = resolutions
+                .mangle(&sourcemapper)
+                .map_err(Diagnostic::from)
+                .and_then(|()| emit_mangle_collisions(&origins, &sourcemapper, options, warnings));
+            std::mem::drop(origins); // origins hold idents use-counts
             let sourcemap = sourcemapper.finish();
-            let mut assembly = resolutions.assemble(options.strip && options.lazy);
+            if let Err(e) = mangled {
+                let err = Err(e
+                    .with_sourcemap(&sourcemap)
+                    .unmangle(Some(&sourcemap), Some(&mangler))
+                    .into());
+                return (err, sourcemap);
+            }
+            outputs.mangle_map = seeded.finish();
+            let strip = options.strip && options.lazy;
+            let mut assembly = resolutions.assemble(strip);
+            if let Some(index) = index {
+                let provenance = resolutions.provenance(strip);
+                outputs.assembly = Some(watch::Assembly::new(index, &assembly, provenance, &keep));
+            }
             std::mem::drop(resolutions); // resolutions hold idents use-counts
             let res = compile_post_assembly(&mut assembly, options, &keep)
                 .map_err(|e| {
@@ -819,6 +1545,10 @@ pub fn compile_sourcemap(
                         .unmangle(Some(&sourcemap), Some(&mangler))
                         .into()
                 })
+                .and_then(|()| {
+                    feature::emit_missing_features(&assembly, options, warnings)
+                        .map_err(|e| e.with_output(assembly.to_string()).into())
+                })
                 .map(|()| assembly);
             (res, sourcemap)
         }
@@ -892,3 +1622,227 @@ fn test_send_sync() {
     fn assert_send_sync<T: Send + Sync>() {}
     assert_send_sync::<Wesl<StandardResolver>>();
 }
+
+#[test]
+fn test_require_entry_point() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("lib", "fn helper() -> f32 { return 1.0; }".into());
+    resolver.add_module(
+        "main",
+        "@fragment fn main() -> @location(0) vec4f { return vec4f(1.0); }".into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.require_entry_point(true);
+
+    let Err(err) = compiler.compile("lib") else {
+        panic!("expected an error");
+    };
+    assert!(matches!(
+        err,
+        Error::Error(Diagnostic { error, .. })
+            if matches!(*error, Error::ValidateError(ValidateError::NoEntryPoint))
+    ));
+    assert!(compiler.compile("main").is_ok());
+}
+
+#[test]
+fn test_known_attributes() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "@vertx fn vs() -> @builtin(position) vec4f { return vec4f(); }
+        @compute @workgroup_size(1) @engine_entry fn main() {}"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    // no allowlist: any attribute passes.
+    assert!(compiler.compile("main").is_ok());
+
+    compiler.known_attributes(["engine_entry"]);
+    let Err(err) = compiler.compile("main") else {
+        panic!("expected an error");
+    };
+    assert!(matches!(
+        err,
+        Error::Error(Diagnostic { error, .. })
+            if matches!(&*error, Error::ValidateError(ValidateError::UnknownAttribute(name)) if name == "vertx")
+    ));
+    compiler.known_attributes(["vertx"]);
+    assert!(compiler.compile("main").is_ok());
+}
+
+#[test]
+fn test_mangle_entry_locals() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "a",
+        "@group(0) @binding(0) var<storage, read_write> out_a: array<f32>;
+        fn helper() -> f32 { return 1.0; }
+        @compute @workgroup_size(1) fn main_a() { out_a[0] = helper(); }"
+            .into(),
+    );
+    resolver.add_module(
+        "b",
+        "override scale: f32 = 1.0;
+        fn helper() -> f32 { return scale; }
+        @fragment fn main_b() -> @location(0) vec4f { return vec4f(helper()); }"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    let a = compiler.compile("a").unwrap().to_string();
+    let b = compiler.compile("b").unwrap().to_string();
+    assert!(a.contains("fn helper()") && b.contains("fn helper()"));
+
+    compiler.mangle_entry_locals(true);
+    let a = compiler.compile("a").unwrap().to_string();
+    let b = compiler.compile("b").unwrap().to_string();
+    assert!(a.contains("fn package_a_helper()") && a.contains("out_a[0] = package_a_helper();"));
+    assert!(b.contains("fn package_b_helper()") && b.contains("return scale;"));
+    // host-visible names are stable.
+    assert!(a.contains("fn main_a()") && a.contains("var<storage, read_write> out_a:"));
+    assert!(b.contains("fn main_b()") && b.contains("override scale: f32"));
+}
+
+#[test]
+fn test_strip_eager() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::f;
+        @compute @workgroup_size(1) fn main() { let x = f(); }"
+            .into(),
+    );
+    resolver.add_module(
+        "util",
+        "fn f() -> f32 { return 1.0; } fn unused() -> f32 { return 2.0; }".into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.set_options(CompileOptions {
+        lazy: false,
+        ..Default::default()
+    });
+    let output = compiler.compile("main").unwrap().to_string();
+    assert!(output.contains("package_util_f"), "{output}");
+    assert!(!output.contains("package_util_unused"), "{output}");
+}
+
+#[test]
+fn test_mangle_collisions() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::a::f;
+        fn h() -> f32 { return 0.0; }
+        @compute @workgroup_size(1) fn main() { let x = f() + h(); }"
+            .into(),
+    );
+    resolver.add_module(
+        "a",
+        "fn h() -> f32 { return 1.0; } fn f() -> f32 { return h(); }".into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    assert!(compiler.compile("main").unwrap().warnings.is_empty());
+
+    compiler.set_mangler(ManglerKind::None);
+    let Some(Error::Error(err)) = compiler.compile("main").err() else {
+        panic!("expected a diagnostic");
+    };
+    assert!(matches!(
+        *err.error,
+        Error::ValidateError(ValidateError::MangleCollision(..))
+    ));
+
+    compiler.set_options(CompileOptions {
+        validate: false,
+        ..Default::default()
+    });
+    let res = compiler.compile("main").unwrap();
+    let warnings = res
+        .warnings
+        .iter()
+        .map(|w| w.error.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        ["`package::main::h` and `package::a::h` are both mangled to `h`, one will shadow the other"]
+    );
+    assert_eq!(
+        res.warnings[0].module_path,
+        Some(ModulePath::from_path("/a"))
+    );
+
+    compiler.deny_warnings(DenyWarnings::All);
+    assert!(compiler.compile("main").is_err());
+}
+
+#[test]
+fn test_compile_project() {
+    use std::cell::Cell;
+    struct CountingResolver<'a> {
+        resolver: VirtualResolver<'a>,
+        util_parses: Cell<usize>,
+    }
+    impl Resolver for CountingResolver<'_> {
+        fn resolve_source<'b>(
+            &'b self,
+            path: &ModulePath,
+        ) -> Result<std::borrow::Cow<'b, str>, ResolveError> {
+            self.resolver.resolve_source(path)
+        }
+        fn source_to_module(
+            &self,
+            source: &str,
+            path: &ModulePath,
+        ) -> Result<TranslationUnit, ResolveError> {
+            if path.last() == Some("util") {
+                self.util_parses.set(self.util_parses.get() + 1);
+            }
+            self.resolver.source_to_module(source, path)
+        }
+    }
+
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("util", "fn scale(x: f32) -> f32 { return x * 2.0; }".into());
+    resolver.add_module(
+        "a",
+        "import package::util::scale;
+        @compute @workgroup_size(1) fn main() { let x = scale(1.0); }"
+            .into(),
+    );
+    resolver.add_module(
+        "b",
+        "import package::util::scale;
+        @fragment fn main() -> @location(0) vec4f { return vec4f(scale(0.5)); }"
+            .into(),
+    );
+    resolver.add_module(
+        "broken",
+        "import package::util::missing;
+        @compute @workgroup_size(1) fn main() { missing(); }"
+            .into(),
+    );
+    let resolver = CountingResolver {
+        resolver,
+        util_parses: Cell::new(0),
+    };
+    let compiler = Wesl::new("").set_custom_resolver(resolver);
+
+    let report = compiler.compile_project(["a", "broken", "b"]);
+    assert!(!report.is_ok());
+    let successes = report
+        .successes()
+        .map(|(path, res)| (path.to_string(), res.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(successes.len(), 2);
+    assert!(successes[0].1.contains("let x = package_util_scale(1.0);"));
+    assert!(successes[1]
+        .1
+        .contains("return vec4f(package_util_scale(0.5));"));
+    let failures = report.failures().collect::<Vec<_>>();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0.last(), Some("broken"));
+    assert!(failures[0].1.to_string().contains("missing"));
+
+    // the shared module is parsed once, and each compilation mangles its own copy.
+    assert_eq!(compiler.resolver.util_parses.get(), 1);
+}
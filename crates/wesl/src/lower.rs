@@ -1,4 +1,6 @@
-use crate::{visit::Visit, Error};
+use std::collections::HashMap;
+
+use crate::{visit::Visit, Error, ValidateError};
 
 use wgsl_parse::syntax::*;
 
@@ -6,6 +8,9 @@ use wgsl_parse::syntax::*;
 /// catch errors early and perform optimizations.
 pub fn lower(wesl: &mut TranslationUnit) -> Result<(), Error> {
     wesl.imports.clear();
+    wesl.exports.clear();
+    wesl.base = None;
+    crate::import::remove_block_imports(wesl);
 
     for attrs in Visit::<Attributes>::visit_mut(wesl) {
         attrs.retain(|attr| {
@@ -70,6 +75,59 @@ fn remove_type_aliases(wesl: &mut TranslationUnit) {
     }
 }
 
+/// Replace uses of type aliases with the aliased type expression and drop the alias
+/// declarations. Aliases of aliases are inlined recursively.
+///
+/// Unlike [`remove_type_aliases`], the syntax tree is rewritten, not just the names, so
+/// aliases of templated types (e.g. `alias V = array<vec3f, 4>;`) become proper type
+/// expressions.
+///
+/// # Errors
+/// [`ValidateError::Cycle`] if an alias refers to itself, directly or via another alias.
+pub(crate) fn inline_type_aliases(wesl: &mut TranslationUnit) -> Result<(), Error> {
+    fn inline(
+        ty: &mut TypeExpression,
+        aliases: &HashMap<Ident, TypeExpression>,
+        stack: &mut Vec<Ident>,
+    ) -> Result<(), Error> {
+        if let Some(aliased) = aliases.get(&ty.ident) {
+            if stack.contains(&ty.ident) {
+                let via = stack.last().unwrap(); // safety: the stack contains ty.ident
+                return Err(ValidateError::Cycle(ty.ident.to_string(), via.to_string()).into());
+            }
+            stack.push(ty.ident.clone());
+            *ty = aliased.clone();
+            inline(ty, aliases, stack)?;
+            stack.pop();
+        } else {
+            for ty in Visit::<TypeExpression>::visit_mut(ty) {
+                inline(ty, aliases, stack)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut aliases = HashMap::new();
+    wesl.global_declarations.retain(|decl| match decl {
+        GlobalDeclaration::TypeAlias(alias) => {
+            aliases.insert(alias.ident.clone(), alias.ty.clone());
+            false
+        }
+        _ => true,
+    });
+    if aliases.is_empty() {
+        return Ok(());
+    }
+    for ty in Visit::<TypeExpression>::visit_mut(wesl) {
+        inline(ty, &aliases, &mut Vec::new())?;
+    }
+    // aliases that are never used can be cyclic too.
+    for ty in aliases.values() {
+        inline(&mut ty.clone(), &aliases, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
 /// Eliminate all const-declarations.
 ///
 /// Replace usages of the const-declaration with its expression.
@@ -104,3 +162,125 @@ fn remove_global_consts(wesl: &mut TranslationUnit) {
             .rename(format!("({})", decl.initializer.unwrap()));
     }
 }
+
+/// Rewrite `while` loops to the equivalent `loop` with a leading break-if, for backends
+/// that only handle the canonical `loop` form. See [`LoopStatement::from`].
+pub(crate) fn lower_while_loops(wesl: &mut TranslationUnit) {
+    fn lower_stmt(stmt: &mut StatementNode) {
+        if matches!(stmt.node(), Statement::While(_)) {
+            let Statement::While(s) = std::mem::replace(stmt.node_mut(), Statement::Void) else {
+                unreachable!()
+            };
+            *stmt.node_mut() = Statement::Loop(s.into());
+        }
+        for stmt in Visit::<StatementNode>::visit_mut(stmt.node_mut()) {
+            lower_stmt(stmt);
+        }
+    }
+    for decl in &mut wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            for stmt in &mut f.body.statements {
+                lower_stmt(stmt);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_inline_type_aliases() {
+    use crate::SyntaxUtil;
+    let inline = |source: &str| {
+        let mut wesl = wgsl_parse::parse_str(source).unwrap();
+        wesl.retarget_idents();
+        inline_type_aliases(&mut wesl).map(|()| wesl.to_string())
+    };
+
+    let source = "alias T = u32;
+        fn f(x: T) -> T { let y: T = T(x); return y; }";
+    let expected = "fn f(x: u32) -> u32 {
+    let y: u32 = u32(x);
+    return y;
+}
+";
+    assert_eq!(inline(source).unwrap(), expected);
+
+    let source = "alias F = f32;
+        alias V = vec3<F>;
+        alias A = array<V, 4>;
+        var<private> a: A;
+        const v = array<V, 2>(V(1.0), V());";
+    let expected = "var<private> a: array<vec3<f32>, 4>;
+
+const v = array<vec3<f32>, 2>(vec3<f32>(1.0), vec3<f32>());
+";
+    assert_eq!(inline(source).unwrap(), expected);
+
+    let source = "alias A = array<B, 2>;
+        alias B = A;
+        var<private> a: A;";
+    let err = inline(source).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ValidateError(ValidateError::Cycle(a, b)) if a == "A" && b == "B"
+    ));
+}
+
+#[test]
+fn test_lower_while_loops() {
+    use crate::{CompileOptions, VirtualResolver, Wesl};
+    let source = "@const fn count(n: u32) -> u32 {
+        var i = 0u;
+        var s = 0u;
+        while i < n {
+            i++;
+            if i % 2u == 0u { continue; }
+            while false {}
+            s += i;
+        }
+        return s;
+    }";
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("main", source.into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.set_options(CompileOptions {
+        lower: false,
+        strip: false,
+        lower_while_loops: true,
+        ..Default::default()
+    });
+    let result = compiler.compile("main").unwrap();
+    let expected = "@const
+fn count(n: u32) -> u32 {
+    var i = 0u;
+    var s = 0u;
+    loop {
+        if !(i < n) {
+            break;
+        }
+        i++;
+        if i % 2u == 0u {
+            continue;
+        }
+        loop {
+            if !(false) {
+                break;
+            }
+        }
+        s += i;
+    }
+    return s;
+}
+";
+    assert_eq!(result.to_string(), expected);
+
+    // the loop computes the same value as the while loop: 1 + 3 + 5 + 7 + 9.
+    #[cfg(feature = "eval")]
+    {
+        let res = result.eval("count(10u)").unwrap();
+        assert_eq!(res.inst.to_string(), "25u");
+        compiler.lower_while_loops(false);
+        let result = compiler.compile("main").unwrap();
+        let res = result.eval("count(10u)").unwrap();
+        assert_eq!(res.inst.to_string(), "25u");
+    }
+}
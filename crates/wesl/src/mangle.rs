@@ -4,6 +4,7 @@ use std::fmt;
 use std::hash::DefaultHasher;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::sync::Mutex;
 
 use itertools::Itertools;
 use wgsl_parse::syntax::Expression;
@@ -37,6 +38,10 @@ pub trait Mangler {
     fn mangle_types(&self, item: &str, variant: u32, _types: &[TypeExpression]) -> String {
         format!("{item}_{variant}")
     }
+    /// Called at the start of each compilation. Manglers that remember the names they
+    /// returned must forget them, so that compilations do not depend on each other.
+    /// Implementing this is optional.
+    fn reset(&self) {}
 }
 
 impl<T: Mangler + ?Sized> Mangler for Box<T> {
@@ -49,6 +54,9 @@ impl<T: Mangler + ?Sized> Mangler for Box<T> {
     fn mangle_types(&self, item: &str, variant: u32, types: &[TypeExpression]) -> String {
         (**self).mangle_types(item, variant, types)
     }
+    fn reset(&self) {
+        (**self).reset()
+    }
 }
 
 impl<T: Mangler> Mangler for &T {
@@ -61,6 +69,9 @@ impl<T: Mangler> Mangler for &T {
     fn mangle_types(&self, item: &str, variant: u32, types: &[TypeExpression]) -> String {
         (**self).mangle_types(item, variant, types)
     }
+    fn reset(&self) {
+        (**self).reset()
+    }
 }
 
 /// A mangler that hashes the module path.
@@ -215,6 +226,279 @@ impl<T: Mangler> Mangler for CacheMangler<'_, T> {
     }
 }
 
+/// Mangled names of a compilation, keyed by `(module path, item)`.
+///
+/// Obtained from [`crate::CompileResult::mangle_map`]. Passing it to the next compilation
+/// with [`crate::CompileOptions::mangle_map`] keeps mangled names stable, which
+/// minimizes diff noise in generated output and caches.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MangleMap(HashMap<(ModulePath, String), String>);
+
+impl MangleMap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Get the mangled name of a fully-qualified item.
+    pub fn get(&self, path: &ModulePath, item: &str) -> Option<&str> {
+        self.0
+            .get(&(path.clone(), item.to_string()))
+            .map(String::as_str)
+    }
+    /// Associate a fully-qualified item with a mangled name.
+    pub fn insert(&mut self, path: ModulePath, item: String, mangled: String) {
+        self.0.insert((path, item), mangled);
+    }
+    /// Iterate over `(path, item, mangled)` triples.
+    pub fn iter(&self) -> impl Iterator<Item = (&ModulePath, &str, &str)> {
+        self.0
+            .iter()
+            .map(|((path, item), mangled)| (path, item.as_str(), mangled.as_str()))
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    fn contains_name(&self, mangled: &str) -> bool {
+        self.0.values().any(|name| name == mangled)
+    }
+    fn find_name(&self, mangled: &str) -> Option<(ModulePath, String)> {
+        self.0
+            .iter()
+            .find(|(_, name)| *name == mangled)
+            .map(|(key, _)| key.clone())
+    }
+}
+
+/// A mangler that reuses the names of a seed [`MangleMap`] before minting new ones, and
+/// records all names it returns.
+///
+/// Names minted by the inner mangler that are already taken by another item are
+/// suffixed with a number.
+pub(crate) struct SeededMangler<'a, T: Mangler> {
+    seed: &'a MangleMap,
+    minted: RefCell<MangleMap>,
+    mangler: &'a T,
+}
+
+impl<'a, T: Mangler> SeededMangler<'a, T> {
+    pub fn new(mangler: &'a T, seed: &'a MangleMap) -> Self {
+        Self {
+            seed,
+            minted: Default::default(),
+            mangler,
+        }
+    }
+    /// The names returned by this mangler.
+    pub fn finish(self) -> MangleMap {
+        self.minted.into_inner()
+    }
+}
+
+impl<T: Mangler> Mangler for SeededMangler<'_, T> {
+    fn mangle(&self, path: &ModulePath, item: &str) -> String {
+        if let Some(name) = self.minted.borrow().get(path, item) {
+            return name.to_string();
+        }
+        let name = match self.seed.get(path, item) {
+            Some(name) => name.to_string(),
+            None => {
+                let name = self.mangler.mangle(path, item);
+                let taken = |name: &str| {
+                    self.seed.contains_name(name) || self.minted.borrow().contains_name(name)
+                };
+                if taken(&name) {
+                    (1..)
+                        .map(|n| format!("{name}_{n}"))
+                        .find(|name| !taken(name))
+                        .unwrap() // safety: the range is infinite
+                } else {
+                    name
+                }
+            }
+        };
+        self.minted
+            .borrow_mut()
+            .insert(path.clone(), item.to_string(), name.clone());
+        name
+    }
+    fn unmangle(&self, mangled: &str) -> Option<(ModulePath, String)> {
+        self.minted
+            .borrow()
+            .find_name(mangled)
+            .or_else(|| self.mangler.unmangle(mangled))
+    }
+    fn mangle_types(&self, item: &str, variant: u32, types: &[TypeExpression]) -> String {
+        self.mangler.mangle_types(item, variant, types)
+    }
+}
+
+/// A mangler adapter that rewrites the names of the inner mangler to be valid GLSL
+/// identifiers too, for users that cross-compile the output to GLSL.
+///
+/// GLSL reserves identifiers containing `__` and identifiers starting with `gl_`. Runs of
+/// `n` underscores are replaced with `_n_` and a leading `gl_` is prefixed with `_`, e.g.
+/// `a__b => a_2_b` and `gl_Position => _gl_Position`. If a rewritten name was already
+/// returned for another item in the same compilation, it is suffixed with a number.
+///
+/// Names that are not mangled (e.g. declarations of the root module) are not rewritten.
+pub struct GlslSafeMangler<M: Mangler> {
+    mangler: M,
+    minted: Mutex<MangleMap>,
+}
+
+impl<M: Mangler> GlslSafeMangler<M> {
+    pub fn new(mangler: M) -> Self {
+        Self {
+            mangler,
+            minted: Default::default(),
+        }
+    }
+
+    /// Rewrite a name to avoid the patterns reserved by GLSL.
+    pub fn sanitize(name: &str) -> String {
+        let mut res = String::with_capacity(name.len());
+        let mut chars = name.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '_' {
+                let mut n = 1;
+                while chars.next_if_eq(&'_').is_some() {
+                    n += 1;
+                }
+                if n == 1 {
+                    res.push('_');
+                } else {
+                    res.push_str(&format!("_{n}_"));
+                }
+            } else {
+                res.push(c);
+            }
+        }
+        if res.starts_with("gl_") {
+            res.insert(0, '_');
+        }
+        res
+    }
+}
+
+impl<M: Mangler> Mangler for GlslSafeMangler<M> {
+    fn mangle(&self, path: &ModulePath, item: &str) -> String {
+        let mut minted = self.minted.lock().unwrap();
+        if let Some(name) = minted.get(path, item) {
+            return name.to_string();
+        }
+        let name = Self::sanitize(&self.mangler.mangle(path, item));
+        let taken = |name: &str| minted.contains_name(name);
+        let name = if taken(&name) {
+            // avoid creating a `__` when the name ends with an underscore.
+            let sep = if name.ends_with('_') { "" } else { "_" };
+            (1..)
+                .map(|n| format!("{name}{sep}{n}"))
+                .find(|name| !taken(name))
+                .unwrap() // safety: the range is infinite
+        } else {
+            name
+        };
+        minted.insert(path.clone(), item.to_string(), name.clone());
+        name
+    }
+    fn unmangle(&self, mangled: &str) -> Option<(ModulePath, String)> {
+        let found = self.minted.lock().unwrap().find_name(mangled);
+        found.or_else(|| self.mangler.unmangle(mangled))
+    }
+    fn mangle_types(&self, item: &str, variant: u32, types: &[TypeExpression]) -> String {
+        Self::sanitize(&self.mangler.mangle_types(item, variant, types))
+    }
+    fn reset(&self) {
+        *self.minted.lock().unwrap() = MangleMap::new();
+        self.mangler.reset();
+    }
+}
+
+/// A mangler that mints pseudo-random names from a seed.
+/// e.g. `foo::bar::baz item => xk3v9q0m2d7a`
+///
+/// The names do not reveal the module path nor the item name, for obfuscated builds. They
+/// are reproducible: the same seed gives the same names on every build and platform, and
+/// different seeds give different names. A name already minted for another item is drawn
+/// again.
+pub struct RandomMangler {
+    seed: u64,
+    minted: Mutex<MangleMap>,
+}
+
+impl RandomMangler {
+    /// Length of the minted names.
+    const LEN: usize = 12;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            minted: Default::default(),
+        }
+    }
+
+    /// A stable 64-bit hash of the bytes (FNV-1a). [`DefaultHasher`] is not guaranteed to
+    /// be stable across Rust releases.
+    fn hash(seed: u64, bytes: &[u8]) -> u64 {
+        let mut hash = 0xcbf29ce484222325 ^ seed;
+        for b in bytes {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    /// The `attempt`-th name drawn for the key. It starts with a letter, then letters and
+    /// digits.
+    fn draw(&self, key: &str, attempt: u64) -> String {
+        const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+        const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+        // splitmix64 steps, to spread the hash over all output characters.
+        let mut state = Self::hash(self.seed, key.as_bytes()).wrapping_add(attempt);
+        let mut next = || {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        let mut name = String::with_capacity(Self::LEN);
+        name.push(LETTERS[(next() % LETTERS.len() as u64) as usize] as char);
+        for _ in 1..Self::LEN {
+            name.push(CHARS[(next() % CHARS.len() as u64) as usize] as char);
+        }
+        name
+    }
+}
+
+impl Mangler for RandomMangler {
+    fn mangle(&self, path: &ModulePath, item: &str) -> String {
+        let mut minted = self.minted.lock().unwrap();
+        if let Some(name) = minted.get(path, item) {
+            return name.to_string();
+        }
+        let key = format!("{path}::{item}");
+        let name = (0..)
+            .map(|attempt| self.draw(&key, attempt))
+            .find(|name| !minted.contains_name(name))
+            .unwrap(); // safety: the range is infinite
+        minted.insert(path.clone(), item.to_string(), name.clone());
+        name
+    }
+    fn unmangle(&self, mangled: &str) -> Option<(ModulePath, String)> {
+        self.minted.lock().unwrap().find_name(mangled)
+    }
+    fn mangle_types(&self, item: &str, variant: u32, types: &[TypeExpression]) -> String {
+        let key = format!("{item}<{}>#{variant}", types.iter().format(", "));
+        self.draw(&key, 0)
+    }
+    fn reset(&self) {
+        *self.minted.lock().unwrap() = MangleMap::new();
+    }
+}
+
 /// A mangler that uses cryptic unicode symbols that look like :, < and >
 /// e.g. `foo::bar::baz array<f32,2> => foo::bar::baz::arrayᐸf32ˏ2ᐳ`
 ///
@@ -307,3 +591,143 @@ impl Mangler for UnicodeMangler {
         )
     }
 }
+
+#[test]
+fn test_seeded_mangle_map() {
+    use crate::{VirtualResolver, Wesl};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    // mints names in the order of mangling, which is unstable when the imports change.
+    #[derive(Default)]
+    struct CountMangler(AtomicU32);
+    impl Mangler for CountMangler {
+        fn mangle(&self, _path: &ModulePath, item: &str) -> String {
+            format!("{item}_{}", self.0.fetch_add(1, Ordering::Relaxed))
+        }
+    }
+
+    let compile = |main: &str, seed: MangleMap| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", main.to_string().into());
+        resolver.add_module("a", "fn fa() {} fn ga() {}".into());
+        resolver.add_module("b", "fn fb() {}".into());
+        let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+        compiler
+            .set_custom_mangler(CountMangler::default())
+            .set_options(crate::CompileOptions {
+                mangle_map: seed,
+                strip: false,
+                ..Default::default()
+            });
+        compiler.compile("main").unwrap_or_else(|e| panic!("{e}"))
+    };
+
+    let first = compile(
+        "import package::a::{fa, ga}; fn main() { fa(); ga(); }",
+        MangleMap::new(),
+    );
+    let second = compile(
+        "import package::b::fb; import package::a::{fa, ga}; fn main() { fb(); fa(); ga(); }",
+        first.mangle_map.clone(),
+    );
+
+    assert_eq!(first.mangle_map.len(), 2);
+    for (path, item, mangled) in first.mangle_map.iter() {
+        assert_eq!(second.mangle_map.get(path, item), Some(mangled));
+    }
+    let fb = second
+        .mangle_map
+        .get(&ModulePath::from_path("/b"), "fb")
+        .unwrap();
+    assert!(first.mangle_map.iter().all(|(_, _, mangled)| mangled != fb));
+    assert!(second.to_string().contains(&format!("fn {fb}()")));
+}
+
+#[test]
+fn test_random_mangler() {
+    let path = ModulePath::from_path("/shapes/circle");
+    let names = |seed: u64| {
+        let mangler = RandomMangler::new(seed);
+        ["area", "radius", "Circle"].map(|item| mangler.mangle(&path, item))
+    };
+    // the names only depend on the seed.
+    assert_eq!(names(42), names(42));
+    assert_ne!(names(42), names(43));
+    for name in names(42) {
+        assert_eq!(name.len(), RandomMangler::LEN);
+        assert!(name.starts_with(|c: char| c.is_ascii_lowercase()));
+        assert!(name.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert!(!name.contains("area") && !name.contains("circle"));
+    }
+
+    let mangler = RandomMangler::new(42);
+    let name = mangler.mangle(&path, "area");
+    assert_eq!(mangler.mangle(&path, "area"), name);
+    assert_eq!(
+        mangler.unmangle(&name),
+        Some((path.clone(), "area".to_string()))
+    );
+    mangler.reset();
+    assert_eq!(mangler.unmangle(&name), None);
+}
+
+#[test]
+fn test_glsl_safe_mangler() {
+    let sanitize = GlslSafeMangler::<NoMangler>::sanitize;
+    assert_eq!(sanitize("foo_bar"), "foo_bar");
+    assert_eq!(sanitize("a__b"), "a_2_b");
+    assert_eq!(sanitize("a___b__"), "a_3_b_2_");
+    assert_eq!(sanitize("gl_Position"), "_gl_Position");
+    assert_eq!(sanitize("gl__x"), "_gl_2_x");
+    assert_eq!(sanitize("glow"), "glow");
+
+    // `EscapeMangler` escapes underscores with a count, the result can contain `__`.
+    let mangler = GlslSafeMangler::new(EscapeMangler);
+    let path = ModulePath::from_path("/gl/util");
+    assert_eq!(EscapeMangler.mangle(&path, "_x"), "package_gl_util__1_x");
+    let name = mangler.mangle(&path, "_x");
+    assert_eq!(name, "package_gl_util_2_1_x");
+    assert_eq!(
+        mangler.unmangle(&name),
+        Some((path.clone(), "_x".to_string()))
+    );
+
+    let mangler = GlslSafeMangler::new(NoMangler);
+    assert_eq!(mangler.mangle(&path, "gl_FragCoord"), "_gl_FragCoord");
+    // rewritten names stay unique.
+    let a = mangler.mangle(&path, "a_2_b");
+    let b = mangler.mangle(&path, "a__b");
+    let c = mangler.mangle(&ModulePath::from_path("/other"), "a__b");
+    assert_eq!([a.as_str(), &b, &c], ["a_2_b", "a_2_b_1", "a_2_b_2"]);
+    assert_eq!(mangler.mangle(&path, "a__b"), b);
+    let d = mangler.mangle(&path, "x_2_");
+    let e = mangler.mangle(&path, "x__");
+    assert_eq!([d.as_str(), &e], ["x_2_", "x_2_1"]);
+    // names minted before a reset are not taken anymore.
+    mangler.reset();
+    assert_eq!(mangler.mangle(&path, "x__"), "x_2_");
+}
+
+#[test]
+fn test_glsl_safe_mangler_compile() {
+    use crate::{VirtualResolver, Wesl};
+
+    let mut resolver = VirtualResolver::new();
+    let main = |module: &str| {
+        format!(
+            "import package::{module}::a__b; @compute @workgroup_size(1) fn main() {{ a__b(); }}"
+        )
+    };
+    resolver.add_module("main_x", main("x").into());
+    resolver.add_module("main_y", main("y").into());
+    resolver.add_module("x", "fn a__b() {}".into());
+    resolver.add_module("y", "fn a__b() {}".into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.set_custom_mangler(GlslSafeMangler::new(NoMangler));
+
+    // each compilation starts with no minted names.
+    for root in ["main_x", "main_y", "main_x"] {
+        let wgsl = compiler.compile(root).unwrap_or_else(|e| panic!("{e}"));
+        assert!(wgsl.to_string().contains("fn a_2_b() {"));
+    }
+}
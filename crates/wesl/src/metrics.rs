@@ -0,0 +1,118 @@
+use wgsl_parse::syntax::{
+    Expression, ExpressionNode, GlobalDeclaration, Statement, StatementNode, TranslationUnit,
+};
+
+use crate::visit::Visit;
+
+/// Size and complexity metrics of a translation unit. See
+/// [`crate::SyntaxUtil::metrics`].
+///
+/// Use-case: asserting that a shader stays within a budget in CI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnitMetrics {
+    /// Global declarations: functions, structs, aliases, variables and constants.
+    /// `const_assert`s are not counted.
+    pub declarations: usize,
+    pub functions: usize,
+    /// Statements in function bodies, nested statements included. Block statements
+    /// count as one statement in addition to their contents.
+    pub statements: usize,
+    /// The depth of the deepest expression tree, e.g. 3 for `a + b * c`. Parentheses are
+    /// not counted.
+    pub max_expression_depth: usize,
+    /// A rough estimate of the number of instructions: one per operator, function call,
+    /// indexing and component access, plus one per non-block statement. It is meant to
+    /// compare shaders, not to predict the output of a GPU compiler.
+    pub instructions: usize,
+}
+
+/// The depth of an expression tree, and the number of operations in it.
+fn expr_metrics(expr: &ExpressionNode) -> (usize, usize) {
+    let children: Vec<&ExpressionNode> = match expr.node() {
+        Expression::Literal(_) | Expression::TypeOrIdentifier(_) => Vec::new(),
+        Expression::Parenthesized(p) => vec![&p.expression],
+        Expression::NamedComponent(c) => vec![&c.base],
+        Expression::Indexing(i) => vec![&i.base, &i.index],
+        Expression::Unary(u) => vec![&u.operand],
+        Expression::Binary(b) => vec![&b.left, &b.right],
+        Expression::FunctionCall(call) => call.arguments.iter().collect(),
+    };
+    let (mut depth, mut ops) = (0, 0);
+    for expr in children {
+        let (d, o) = expr_metrics(expr);
+        depth = depth.max(d);
+        ops += o;
+    }
+    match expr.node() {
+        Expression::Literal(_) | Expression::TypeOrIdentifier(_) => (1, 0),
+        Expression::Parenthesized(_) => (depth, ops),
+        Expression::NamedComponent(_)
+        | Expression::Indexing(_)
+        | Expression::Unary(_)
+        | Expression::Binary(_)
+        | Expression::FunctionCall(_) => (depth + 1, ops + 1),
+    }
+}
+
+fn stmt_metrics(stmt: &StatementNode, res: &mut UnitMetrics) {
+    match stmt.node() {
+        Statement::Void => return,
+        Statement::Compound(_) => res.statements += 1,
+        _ => {
+            res.statements += 1;
+            res.instructions += 1;
+        }
+    }
+    for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+        stmt_metrics(stmt, res);
+    }
+}
+
+pub(crate) fn metrics(wesl: &TranslationUnit) -> UnitMetrics {
+    let mut res = UnitMetrics::default();
+    for decl in &wesl.global_declarations {
+        match decl {
+            GlobalDeclaration::Void | GlobalDeclaration::ConstAssert(_) => continue,
+            GlobalDeclaration::Function(f) => {
+                res.functions += 1;
+                for stmt in &f.body.statements {
+                    stmt_metrics(stmt, &mut res);
+                }
+            }
+            _ => (),
+        }
+        res.declarations += 1;
+    }
+    for expr in Visit::<ExpressionNode>::visit(wesl) {
+        let (depth, ops) = expr_metrics(expr);
+        res.max_expression_depth = res.max_expression_depth.max(depth);
+        res.instructions += ops;
+    }
+    res
+}
+
+#[test]
+fn test_metrics() {
+    use crate::SyntaxUtil;
+    let source = "struct Light { color: vec3f }
+        const scale = 2.0;
+        fn shade(l: Light) -> f32 {
+            let x = l.color.x * scale + 1.0;
+            if x > 0.0 {
+                return (x);
+            }
+            return 0.0;
+        }";
+    let wesl = source.parse::<TranslationUnit>().unwrap();
+    let expected = UnitMetrics {
+        declarations: 3,
+        functions: 1,
+        // let, if, return, return.
+        statements: 4,
+        // `+(*(.x(.color(l)), scale), 1.0)`
+        max_expression_depth: 5,
+        // 4 operations in the let, 1 in the if condition, and 4 non-block statements.
+        instructions: 9,
+    };
+    assert_eq!(wesl.metrics(), expected);
+}
@@ -0,0 +1,196 @@
+use wgsl_parse::{
+    span::Span,
+    syntax::{ExpressionNode, GlobalDeclaration, TranslationUnit},
+};
+
+use itertools::Itertools;
+
+use crate::{visit::Visit, Diagnostic, Error};
+
+/// Error reported by naga on the compiled output. See [`crate::CompileOptions::naga`].
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum NagaError {
+    #[error("naga parse error: {0}")]
+    Parse(String),
+    #[error("naga validation error: {0}")]
+    Validation(String),
+}
+
+impl From<NagaError> for Diagnostic<Error> {
+    fn from(error: NagaError) -> Self {
+        Self::new(error.into())
+    }
+}
+
+/// An expression and all its sub-expressions, in syntax order.
+fn push_expressions<'a>(expr: &'a ExpressionNode, res: &mut Vec<&'a ExpressionNode>) {
+    res.push(expr);
+    for expr in Visit::<ExpressionNode>::visit(expr.node()) {
+        push_expressions(expr, res);
+    }
+}
+
+fn expressions(decl: &GlobalDeclaration) -> Vec<&ExpressionNode> {
+    let mut res = Vec::new();
+    for expr in Visit::<ExpressionNode>::visit(decl) {
+        push_expressions(expr, &mut res);
+    }
+    res
+}
+
+/// Find the declaration and the source span of a naga error span in the output.
+///
+/// The output is parsed again to get the spans of its expressions. The expressions of
+/// the output and of `wgsl` are the same, but those of `wgsl` have the span of their
+/// original module. The innermost expression containing the error is picked.
+fn locate(
+    wgsl: &TranslationUnit,
+    output: &str,
+    range: std::ops::Range<usize>,
+) -> (Option<String>, Option<Span>) {
+    let decls = wgsl
+        .global_declarations
+        .iter()
+        .filter(|decl| !matches!(decl, GlobalDeclaration::Void))
+        .collect_vec();
+
+    // declarations are displayed in order, find the one containing the error.
+    let mut pos = 0;
+    let mut found = None;
+    for (i, decl) in decls.iter().enumerate() {
+        let text = decl.to_string();
+        // e.g. root declarations emitted verbatim are not found.
+        let Some(start) = output[pos..].find(&text).map(|start| start + pos) else {
+            continue;
+        };
+        pos = start + text.len();
+        if (start..pos).contains(&range.start) {
+            found = Some(i);
+            break;
+        }
+    }
+    let Some(i) = found else {
+        return (None, None);
+    };
+    let name = decls[i].ident().map(|id| id.to_string());
+
+    let Ok(reparsed) = wgsl_parse::parse_str(output) else {
+        return (name, None);
+    };
+    // with a verbatim root, the output declarations are not those of `wgsl` in order.
+    let mut reparsed_decls = reparsed
+        .global_declarations
+        .iter()
+        .filter(|decl| !matches!(decl, GlobalDeclaration::Void));
+    let reparsed_decl = match &name {
+        Some(name) => {
+            reparsed_decls.find(|decl| decl.ident().is_some_and(|id| *id.name() == *name))
+        }
+        None => reparsed_decls.nth(i),
+    };
+    let Some(reparsed_decl) = reparsed_decl else {
+        return (name, None);
+    };
+    let exprs = expressions(decls[i]);
+    let reparsed_exprs = expressions(reparsed_decl);
+    if exprs.len() != reparsed_exprs.len() {
+        return (name, None);
+    }
+    let span = reparsed_exprs
+        .iter()
+        .zip(exprs)
+        .filter(|(e, _)| e.span().start <= range.start && range.end <= e.span().end)
+        .min_by_key(|(e, _)| e.span().len())
+        .map(|(_, expr)| expr.span().clone());
+    (name, span)
+}
+
+/// Parse and validate the output text with naga. `wgsl` is the syntax tree the output was
+/// displayed from. Errors have the declaration name (mangled) and the source span,
+/// resolve them with [`Diagnostic::with_sourcemap`].
+pub(crate) fn naga_validate(
+    wgsl: &TranslationUnit,
+    output: String,
+) -> Result<(), Diagnostic<Error>> {
+    let (error, span) = match naga::front::wgsl::parse_str(&output) {
+        Ok(module) => {
+            let mut validator = naga::valid::Validator::new(
+                naga::valid::ValidationFlags::all(),
+                naga::valid::Capabilities::all(),
+            );
+            match validator.validate(&module) {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    // the validation errors are nested, e.g. "function `f` is invalid:
+                    // the return type does not match".
+                    let mut msg = e.as_inner().to_string();
+                    let mut source = std::error::Error::source(e.as_inner());
+                    while let Some(err) = source {
+                        msg = format!("{msg}: {err}");
+                        source = err.source();
+                    }
+                    // e.g. the span of the function and of the invalid expression, keep the smallest.
+                    let span = e
+                        .spans()
+                        .filter_map(|(span, _)| span.to_range())
+                        .min_by_key(|range| range.len());
+                    (NagaError::Validation(msg), span)
+                }
+            }
+        }
+        Err(e) => {
+            let span = e.labels().next().and_then(|(span, _)| span.to_range());
+            (NagaError::Parse(e.message().to_string()), span)
+        }
+    };
+
+    let mut diagnostic = Diagnostic::from(error);
+    if let Some(range) = span {
+        (diagnostic.declaration, diagnostic.span) = locate(wgsl, &output, range);
+    }
+    Err(diagnostic.with_output(output))
+}
+
+#[test]
+fn test_naga_errors() {
+    use crate::{ModulePath, VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::scale;
+        @compute @workgroup_size(1) fn main() { let x = scale(2.0); }"
+            .into(),
+    );
+    let util = "fn scale(x: f32) -> f32 {
+            let factor = 2.0;
+            return x * factor + 1u;
+        }";
+    resolver.add_module("util", util.into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    assert!(compiler.compile("main").is_ok());
+
+    compiler.use_naga(true);
+    let Some(Error::Error(err)) = compiler.compile("main").err() else {
+        panic!("expected a diagnostic");
+    };
+    assert!(matches!(
+        *err.error,
+        Error::NagaError(NagaError::Validation(_))
+    ));
+    assert_eq!(err.module_path, Some(ModulePath::from_path("/util")));
+    assert_eq!(err.declaration.as_deref(), Some("scale"));
+    assert_eq!(err.source.as_deref(), Some(util));
+    let span = err.span.unwrap();
+    assert_eq!(&util[span.range()], "x * factor + 1u");
+    let line = util[..span.start].lines().count();
+    assert_eq!(line, 3);
+
+    // the emitted text is validated, after the transforms that change it.
+    compiler.hoist_consts(true).preserve_entry_verbatim(true);
+    let Some(Error::Error(err)) = compiler.compile("main").err() else {
+        panic!("expected a diagnostic");
+    };
+    assert_eq!(err.declaration.as_deref(), Some("scale"));
+    assert_eq!(&util[err.span.unwrap().range()], "x * factor + 1u");
+    assert!(err.output.unwrap().starts_with("        @compute"));
+}
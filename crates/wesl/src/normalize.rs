@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use wgsl_parse::{
+    span::Spanned,
+    syntax::{Expression, Ident, TemplateArg, TranslationUnit, TypeExpression},
+};
+
+use crate::syntax_util::for_each_type_mut;
+
+/// Spelling of vector and matrix types. Used in [`normalize_types`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TypeSpelling {
+    /// Predeclared aliases, e.g. `vec3f`, `mat4x4h`.
+    #[default]
+    Alias,
+    /// Template forms, e.g. `vec3<f32>`, `mat4x4<f16>`.
+    Template,
+}
+
+/// Component types of vectors and matrices with a predeclared alias suffix.
+const VEC_COMPONENTS: [(&str, &str); 4] = [("f", "f32"), ("h", "f16"), ("i", "i32"), ("u", "u32")];
+const MAT_COMPONENTS: [(&str, &str); 2] = [("f", "f32"), ("h", "f16")];
+
+fn is_vec(name: &str) -> bool {
+    matches!(name, "vec2" | "vec3" | "vec4")
+}
+
+fn is_mat(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    name.len() == 6
+        && name.starts_with("mat")
+        && matches!(bytes[3], b'2'..=b'4')
+        && bytes[4] == b'x'
+        && matches!(bytes[5], b'2'..=b'4')
+}
+
+/// `vec3f` => `(vec3, f32)`
+fn split_alias(name: &str) -> Option<(&str, &'static str)> {
+    let (base, suffix) = name.split_at_checked(name.len().checked_sub(1)?)?;
+    let components: &[_] = if is_vec(base) {
+        &VEC_COMPONENTS
+    } else if is_mat(base) {
+        &MAT_COMPONENTS
+    } else {
+        return None;
+    };
+    components
+        .iter()
+        .find(|(s, _)| *s == suffix)
+        .map(|(_, comp)| (base, *comp))
+}
+
+/// `(vec3, f32)` => `vec3f`
+fn join_alias(base: &str, component: &str) -> Option<String> {
+    let components: &[_] = if is_vec(base) {
+        &VEC_COMPONENTS
+    } else if is_mat(base) {
+        &MAT_COMPONENTS
+    } else {
+        return None;
+    };
+    components
+        .iter()
+        .find(|(_, comp)| *comp == component)
+        .map(|(suffix, _)| format!("{base}{suffix}"))
+}
+
+/// Canonicalize the spelling of vector and matrix types, e.g. `vec3<f32>` and `vec3f`.
+///
+/// Types referring to a user declaration (e.g. a shadowing `alias vec3f = ...`) are left
+/// untouched, and so are types whose new spelling would refer to one: `vec2<f32>` is not
+/// rewritten to `vec2f` if the module declares `vec2f`. Only vector and matrix types that
+/// have a predeclared alias are rewritten.
+/// The translation unit should have its identifiers retargeted with
+/// [`crate::SyntaxUtil::retarget_idents`].
+pub fn normalize_types(wesl: &mut TranslationUnit, spelling: TypeSpelling) {
+    fn normalize(
+        ty: &mut TypeExpression,
+        spelling: TypeSpelling,
+        decls: &HashSet<Ident>,
+        names: &HashSet<String>,
+    ) {
+        if ty.path.is_some() || decls.contains(&ty.ident) {
+            return;
+        }
+        match spelling {
+            TypeSpelling::Alias => {
+                let component = match ty.template_args.as_deref() {
+                    Some([arg]) => match arg.expression.node() {
+                        Expression::TypeOrIdentifier(comp)
+                            if comp.template_args.is_none()
+                                && comp.path.is_none()
+                                && !decls.contains(&comp.ident) =>
+                        {
+                            comp.ident.to_string()
+                        }
+                        _ => return,
+                    },
+                    _ => return,
+                };
+                let name = join_alias(&ty.ident.name(), &component);
+                if let Some(name) = name.filter(|name| !names.contains(name)) {
+                    ty.ident = Ident::new(name);
+                    ty.template_args = None;
+                }
+            }
+            TypeSpelling::Template => {
+                if ty.template_args.is_some() {
+                    return;
+                }
+                let split = split_alias(&ty.ident.name())
+                    .map(|(base, comp)| (base.to_string(), comp.to_string()))
+                    .filter(|(base, comp)| !names.contains(base) && !names.contains(comp));
+                if let Some((base, comp)) = split {
+                    let comp = TypeExpression::new(Ident::new(comp));
+                    ty.ident = Ident::new(base);
+                    ty.template_args = Some(vec![TemplateArg {
+                        expression: Spanned::new(
+                            Expression::TypeOrIdentifier(comp),
+                            Default::default(),
+                        ),
+                    }]);
+                }
+            }
+        }
+    }
+
+    let decls = wesl
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.ident().cloned())
+        .collect::<HashSet<_>>();
+    let names = decls
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<HashSet<_>>();
+
+    for_each_type_mut(wesl, &mut |ty| normalize(ty, spelling, &decls, &names));
+}
+
+#[test]
+fn test_normalize_types() {
+    use crate::SyntaxUtil;
+    let source = "alias vec2f = vec2<u32>;
+        fn f(a: vec3<f32>, b: array<vec4<i32>, 2>, c: mat2x3<f16>, d: vec2f) -> vec4u {
+            let x = vec3f(1.0);
+            let y = vec2<bool>(true);
+            return vec4<u32>(1u);
+        }";
+
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+    normalize_types(&mut wgsl, TypeSpelling::Alias);
+    let expected = "alias vec2f = vec2u;
+
+fn f(a: vec3f, b: array<vec4i, 2>, c: mat2x3h, d: vec2f) -> vec4u {
+    let x = vec3f(1.0);
+    let y = vec2<bool>(true);
+    return vec4u(1u);
+}
+";
+    assert_eq!(wgsl.to_string(), expected);
+
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+    normalize_types(&mut wgsl, TypeSpelling::Template);
+    let expected = "alias vec2f = vec2<u32>;
+
+fn f(a: vec3<f32>, b: array<vec4<i32>, 2>, c: mat2x3<f16>, d: vec2f) -> vec4<u32> {
+    let x = vec3<f32>(1.0);
+    let y = vec2<bool>(true);
+    return vec4<u32>(1u);
+}
+";
+    assert_eq!(wgsl.to_string(), expected);
+}
+
+#[test]
+fn test_normalize_types_shadowed_alias() {
+    use crate::SyntaxUtil;
+    // `vec2f` is a user declaration, `vec2<f32>` must not be rewritten to it.
+    let source = "alias vec2f = vec2<u32>;
+        fn f(a: vec2<f32>, b: vec3<f32>) -> vec2f { return vec2f(a); }";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+    normalize_types(&mut wgsl, TypeSpelling::Alias);
+    let expected = "alias vec2f = vec2u;
+
+fn f(a: vec2<f32>, b: vec3f) -> vec2f {
+    return vec2f(a);
+}
+";
+    assert_eq!(wgsl.to_string(), expected);
+}
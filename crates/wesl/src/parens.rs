@@ -0,0 +1,287 @@
+use wgsl_parse::syntax::{
+    BinaryOperator, Expression, ExpressionNode, LiteralExpression, TranslationUnit, TypeExpression,
+    UnaryOperator,
+};
+
+use crate::visit::Visit;
+
+/// Where an expression appears. It determines which expressions can appear there
+/// without parentheses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Position {
+    /// A whole expression: initializer, function argument, index, condition, ...
+    Whole,
+    /// The base of a component or indexing expression: `(a + b).x`.
+    Postfix,
+    /// The operand of a unary operator.
+    Unary(UnaryOperator),
+    /// The left operand of a binary operator.
+    Left(BinaryOperator),
+    /// The right operand of a binary operator.
+    Right(BinaryOperator),
+}
+
+fn binary_op(expr: &Expression) -> Option<BinaryOperator> {
+    match expr {
+        Expression::Binary(b) => Some(b.operator),
+        _ => None,
+    }
+}
+
+fn is_negative_literal(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(lit) => match lit {
+            LiteralExpression::AbstractInt(n) => *n < 0,
+            LiteralExpression::AbstractFloat(n) => n.is_sign_negative(),
+            LiteralExpression::I32(n) => *n < 0,
+            LiteralExpression::F32(n) | LiteralExpression::F16(n) => n.is_sign_negative(),
+            LiteralExpression::Bool(_) | LiteralExpression::U32(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// `primary_expression` and postfix expressions in the WGSL grammar.
+fn is_primary(expr: &Expression) -> bool {
+    !matches!(expr, Expression::Unary(_) | Expression::Binary(_)) && !is_negative_literal(expr)
+}
+
+/// `unary_expression` in the WGSL grammar.
+fn is_unary(expr: &Expression) -> bool {
+    !matches!(expr, Expression::Binary(_))
+}
+
+/// `multiplicative_expression` in the WGSL grammar.
+fn is_multiplicative(expr: &Expression) -> bool {
+    use BinaryOperator::*;
+    is_unary(expr) || matches!(binary_op(expr), Some(Multiplication | Division | Remainder))
+}
+
+/// `additive_expression` in the WGSL grammar.
+fn is_additive(expr: &Expression) -> bool {
+    use BinaryOperator::*;
+    is_multiplicative(expr) || matches!(binary_op(expr), Some(Addition | Subtraction))
+}
+
+/// `shift_expression` in the WGSL grammar.
+fn is_shift(expr: &Expression) -> bool {
+    use BinaryOperator::*;
+    is_additive(expr) || matches!(binary_op(expr), Some(ShiftLeft | ShiftRight))
+}
+
+/// `relational_expression` in the WGSL grammar.
+fn is_relational(expr: &Expression) -> bool {
+    use BinaryOperator::*;
+    is_shift(expr)
+        || matches!(
+            binary_op(expr),
+            Some(Equality | Inequality | LessThan | LessThanEqual | GreaterThan | GreaterThanEqual)
+        )
+}
+
+/// Whether the operator is written with a `<` or `>`.
+fn is_angle_bracket(op: BinaryOperator) -> bool {
+    use BinaryOperator::*;
+    matches!(
+        op,
+        LessThan | LessThanEqual | GreaterThan | GreaterThanEqual | ShiftLeft | ShiftRight
+    )
+}
+
+/// Whether the expression contains a `<` or `>` outside of brackets.
+///
+/// Removing the parentheses around it could make the parser mistake it for a template
+/// list, e.g. `f((a < b), (c > d))`.
+fn has_angle_bracket(expr: &Expression) -> bool {
+    match expr {
+        Expression::Unary(u) => has_angle_bracket(&u.operand),
+        Expression::Binary(b) => {
+            is_angle_bracket(b.operator)
+                || has_angle_bracket(&b.left)
+                || has_angle_bracket(&b.right)
+        }
+        Expression::NamedComponent(c) => has_angle_bracket(&c.base),
+        Expression::Indexing(i) => has_angle_bracket(&i.base),
+        _ => false,
+    }
+}
+
+/// Whether `expr` parses the same without parentheses at `pos`.
+///
+/// In template arguments, only unary expressions can be unwrapped, because `>`, `&&` and
+/// `||` interact with the template list discovery. For the same reason, the operands of
+/// `<` and `>` are never unwrapped: in `f((a) < b, c > (d))`, the parentheses are what
+/// prevent `a<b, c>` from being discovered as a template list.
+fn fits(expr: &Expression, pos: Position, in_template: bool) -> bool {
+    use BinaryOperator::*;
+    if has_angle_bracket(expr) || in_template && !is_unary(expr) {
+        return false;
+    }
+    match pos {
+        Position::Whole => true,
+        Position::Postfix => is_primary(expr),
+        Position::Unary(op) => {
+            // `-(-a)` and `&(&a)` would be lexed as `--` and `&&`.
+            let merges = match (op, expr) {
+                (UnaryOperator::Negation, _) if is_negative_literal(expr) => true,
+                (UnaryOperator::Negation | UnaryOperator::AddressOf, Expression::Unary(u)) => {
+                    u.operator == op
+                }
+                _ => false,
+            };
+            is_unary(expr) && !merges
+        }
+        Position::Left(op) | Position::Right(op) => {
+            let left = matches!(pos, Position::Left(_));
+            match op {
+                Multiplication | Division | Remainder => {
+                    is_unary(expr) || left && is_multiplicative(expr)
+                }
+                Addition | Subtraction => is_multiplicative(expr) || left && is_additive(expr),
+                ShiftLeft | ShiftRight | LessThan | LessThanEqual | GreaterThan
+                | GreaterThanEqual => false,
+                Equality | Inequality => is_shift(expr),
+                // bitwise and short-circuit operators cannot be mixed, but can be chained.
+                BitwiseAnd | BitwiseOr | BitwiseXor => {
+                    is_unary(expr) || left && binary_op(expr) == Some(op)
+                }
+                ShortCircuitAnd | ShortCircuitOr => {
+                    is_relational(expr) || left && binary_op(expr) == Some(op)
+                }
+            }
+        }
+    }
+}
+
+fn simplify_ty(ty: &mut TypeExpression) {
+    for arg in ty.template_args.iter_mut().flatten() {
+        simplify(&mut arg.expression, Position::Whole, true);
+    }
+}
+
+fn simplify(expr: &mut ExpressionNode, pos: Position, in_template: bool) {
+    // first, the recursive calls
+    match expr.node_mut() {
+        Expression::Literal(_) => {}
+        Expression::Parenthesized(p) => simplify(&mut p.expression, pos, in_template),
+        Expression::NamedComponent(c) => simplify(&mut c.base, Position::Postfix, in_template),
+        Expression::Indexing(i) => {
+            simplify(&mut i.base, Position::Postfix, in_template);
+            simplify(&mut i.index, Position::Whole, in_template);
+        }
+        Expression::Unary(u) => simplify(&mut u.operand, Position::Unary(u.operator), in_template),
+        Expression::Binary(b) => {
+            simplify(&mut b.left, Position::Left(b.operator), in_template);
+            simplify(&mut b.right, Position::Right(b.operator), in_template);
+        }
+        Expression::FunctionCall(call) => {
+            simplify_ty(&mut call.ty);
+            for arg in &mut call.arguments {
+                simplify(arg, Position::Whole, in_template);
+            }
+        }
+        Expression::TypeOrIdentifier(ty) => simplify_ty(ty),
+    }
+
+    if let Expression::Parenthesized(p) = expr.node() {
+        if fits(&p.expression, pos, in_template) {
+            *expr = p.expression.clone();
+        }
+    }
+}
+
+/// Remove parentheses that are not required by operator precedence, e.g.
+/// `(a * b) + (c)` becomes `a * b + c`.
+///
+/// The meaning of expressions is preserved: parentheses are only removed where the
+/// expression parses to the same tree without them. Parentheses around expressions
+/// containing `<` or `>`, and around the operands of `<` and `>`, are kept, to avoid
+/// ambiguities with template lists.
+pub fn remove_redundant_parens(wesl: &mut TranslationUnit) {
+    for expr in Visit::<ExpressionNode>::visit_mut(wesl) {
+        simplify(expr, Position::Whole, false);
+    }
+}
+
+#[test]
+fn test_remove_redundant_parens() {
+    let source = "fn f() {
+        let a = (1 + 2);
+        let b = (x * y) + (z);
+        let c = (x + y) * z;
+        let d = x - (y - z);
+        let e = (x - y) - z;
+        let f = -(x.y);
+        let g = (-x).y;
+        let h = -(-x);
+        let i = (a & b) & c;
+        let j = (a & b) | c;
+        let k = (a < b) && ((c) || d);
+        let l = (x << 1u) + 1u;
+        let m = g((x), (y + 1));
+        let n = arr[(i + 1)] + (*p).x;
+        let o = array<f32, (N)>((1.0), 2.0);
+        let p = f32((a && b));
+        let q = f((a) < b, c > (d));
+        let r = f((a) < b, (c) > d);
+        let s = vec2((x) < y, z > w);
+        if (x) { return; }
+    }";
+    let expected = "fn f() {
+    let a = 1 + 2;
+    let b = x * y + z;
+    let c = (x + y) * z;
+    let d = x - (y - z);
+    let e = x - y - z;
+    let f = -x.y;
+    let g = (-x).y;
+    let h = -(-x);
+    let i = a & b & c;
+    let j = (a & b) | c;
+    let k = (a < b) && (c || d);
+    let l = (x << 1u) + 1u;
+    let m = g(x, y + 1);
+    let n = arr[i + 1] + (*p).x;
+    let o = array<f32, N>(1.0, 2.0);
+    let p = f32(a && b);
+    let q = f((a) < b, c > (d));
+    let r = f((a) < b, (c) > d);
+    let s = vec2((x) < y, z > w);
+    if x {
+        return;
+    }
+}
+";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    remove_redundant_parens(&mut wgsl);
+    let output = wgsl.to_string();
+    assert_eq!(output, expected);
+    // the output parses to the same tree.
+    assert_eq!(wgsl_parse::parse_str(&output).unwrap().to_string(), output);
+}
+
+#[cfg(feature = "eval")]
+#[test]
+fn test_remove_redundant_parens_eval() {
+    use wgsl_parse::syntax::GlobalDeclaration;
+    let source = "const x = ((2 * 3) + (4 % 3)) - (8 / (2 * 2)) - (1 - 5);
+        const y = ((1u << 2u) | 1u) ^ ((6u & 3u) >> 1u);
+        const z = ((1 < 2) && !(true || false)) || (-(4) == -(2 * 2));";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    let original = wgsl.clone();
+    remove_redundant_parens(&mut wgsl);
+    let reparsed = wgsl_parse::parse_str(&wgsl.to_string()).unwrap();
+
+    let eval_decls = |wgsl: &TranslationUnit| {
+        wgsl.global_declarations
+            .iter()
+            .filter_map(|decl| match decl {
+                GlobalDeclaration::Declaration(decl) => decl.initializer.as_ref(),
+                _ => None,
+            })
+            .map(|expr| crate::eval(expr.node(), wgsl).0.unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(eval_decls(&original), eval_decls(&reparsed));
+    assert_ne!(original.to_string(), reparsed.to_string());
+}
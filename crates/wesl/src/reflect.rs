@@ -0,0 +1,229 @@
+use wgsl_parse::syntax::{
+    AddressSpace, DeclarationKind, GlobalDeclaration, Struct, TranslationUnit,
+};
+
+use crate::{
+    bindings::literal_group_binding,
+    eval::{round_up, ty_eval_ty, Context, EvalAttrs, SamplerType, TextureType, Type},
+    CompileResult,
+};
+
+/// Typed reflection of the resource bindings of a shader.
+///
+/// Obtained with [`CompileResult::reflect`]. It is the Rust counterpart of the JSON
+/// reflection of [`crate::ShaderBundle`], for engines that want to match on enums rather
+/// than parse strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReflectionInfo {
+    /// Module-scope variables with literal `@group` and `@binding` attributes.
+    pub bindings: Vec<BindingReflection>,
+}
+
+/// A resource binding of a [`ReflectionInfo`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindingReflection {
+    pub group: u32,
+    pub binding: u32,
+    pub name: String,
+    pub resource: ResourceKind,
+}
+
+/// The kind of resource bound by a [`BindingReflection`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// A `uniform` or `storage` buffer. The layout is `None` when the type has no size,
+    /// e.g. when it depends on an `override`.
+    Buffer {
+        address_space: AddressSpace,
+        layout: Option<Layout>,
+    },
+    Texture(TextureType),
+    Sampler(SamplerType),
+}
+
+/// The memory layout of a buffer type, per the WGSL alignment and size rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The size in bytes. For runtime-sized arrays (or structs ending with one) this is
+    /// the size with a single array element.
+    pub size: u32,
+    pub align: u32,
+    /// The members, if the type is a struct. Empty otherwise.
+    pub members: Vec<MemberLayout>,
+}
+
+/// A struct member of a [`Layout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberLayout {
+    pub name: String,
+    /// The offset in bytes from the start of the struct.
+    pub offset: u32,
+    pub size: u32,
+    pub align: u32,
+}
+
+fn struct_layout(decl: &Struct, ctx: &mut Context) -> Option<Layout> {
+    let mut members = Vec::new();
+    let mut offset = 0;
+    let mut struct_align = 0;
+    for m in &decl.members {
+        let ty = ty_eval_ty(&m.ty, ctx).ok()?;
+        let size = m
+            .attr_size(ctx)
+            .ok()
+            .flatten()
+            .or_else(|| ty.min_size_of(ctx))?;
+        let align = m
+            .attr_align(ctx)
+            .ok()
+            .flatten()
+            .or_else(|| ty.align_of(ctx))?;
+        offset = round_up(align, offset);
+        members.push(MemberLayout {
+            name: m.ident.to_string(),
+            offset,
+            size,
+            align,
+        });
+        offset += size;
+        struct_align = struct_align.max(align);
+    }
+    Some(Layout {
+        size: round_up(struct_align, offset),
+        align: struct_align,
+        members,
+    })
+}
+
+fn ty_layout(ty: &Type, wgsl: &TranslationUnit, ctx: &mut Context) -> Option<Layout> {
+    if let Type::Struct(name) = ty {
+        let decl = wgsl
+            .global_declarations
+            .iter()
+            .find_map(|decl| match decl {
+                GlobalDeclaration::Struct(s) if *s.ident.name() == *name => Some(s),
+                _ => None,
+            })?;
+        struct_layout(decl, ctx)
+    } else {
+        Some(Layout {
+            size: ty.min_size_of(ctx)?,
+            align: ty.align_of(ctx)?,
+            members: Vec::new(),
+        })
+    }
+}
+
+impl CompileResult {
+    /// Typed reflection of the resource bindings of the compiled shader.
+    ///
+    /// Like [`CompileResult::bundle`], bindings with `@group` or `@binding` attributes
+    /// that are not literals are omitted, and so are bindings whose type is not a valid
+    /// resource type.
+    pub fn reflect(&self) -> ReflectionInfo {
+        let wgsl = &self.syntax;
+        let mut ctx = Context::new(wgsl);
+        let mut bindings = Vec::new();
+
+        for decl in &wgsl.global_declarations {
+            let GlobalDeclaration::Declaration(d) = decl else {
+                continue;
+            };
+            let DeclarationKind::Var(space) = &d.kind else {
+                continue;
+            };
+            let (Some((group, binding)), Some(ty)) = (literal_group_binding(d), &d.ty) else {
+                continue;
+            };
+            let Ok(ty) = ty_eval_ty(ty, &mut ctx) else {
+                continue;
+            };
+            let resource = match (space, ty) {
+                (Some(space @ (AddressSpace::Uniform | AddressSpace::Storage(_))), ty) => {
+                    ResourceKind::Buffer {
+                        address_space: *space,
+                        layout: ty_layout(&ty, wgsl, &mut ctx),
+                    }
+                }
+                (None | Some(AddressSpace::Handle), Type::Texture(tex)) => {
+                    ResourceKind::Texture(tex)
+                }
+                (None | Some(AddressSpace::Handle), Type::Sampler(sampler)) => {
+                    ResourceKind::Sampler(sampler)
+                }
+                _ => continue,
+            };
+            bindings.push(BindingReflection {
+                group,
+                binding,
+                name: d.ident.to_string(),
+                resource,
+            });
+        }
+
+        ReflectionInfo { bindings }
+    }
+}
+
+#[test]
+fn test_reflect() {
+    use crate::{eval::SampledType, VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "struct Camera { view: mat4x4f, pos: vec3f, exposure: f32, @align(16) tint: vec2f }
+        @group(0) @binding(0) var<uniform> camera: Camera;
+        @group(0) @binding(1) var albedo: texture_2d<f32>;
+        @group(0) @binding(2) var samp: sampler;
+        @fragment fn main() -> @location(0) vec4f {
+            let c = textureSample(albedo, samp, camera.tint);
+            return c * camera.exposure;
+        }"
+        .into(),
+    );
+    let res = Wesl::new("")
+        .set_custom_resolver(resolver)
+        .compile("main")
+        .unwrap();
+    let member = |name: &str, offset, size, align| MemberLayout {
+        name: name.to_string(),
+        offset,
+        size,
+        align,
+    };
+    let expected = ReflectionInfo {
+        bindings: vec![
+            BindingReflection {
+                group: 0,
+                binding: 0,
+                name: "camera".to_string(),
+                resource: ResourceKind::Buffer {
+                    address_space: AddressSpace::Uniform,
+                    layout: Some(Layout {
+                        size: 96,
+                        align: 16,
+                        members: vec![
+                            member("view", 0, 64, 16),
+                            member("pos", 64, 12, 16),
+                            member("exposure", 76, 4, 4),
+                            member("tint", 80, 8, 16),
+                        ],
+                    }),
+                },
+            },
+            BindingReflection {
+                group: 0,
+                binding: 1,
+                name: "albedo".to_string(),
+                resource: ResourceKind::Texture(TextureType::Sampled2D(SampledType::F32)),
+            },
+            BindingReflection {
+                group: 0,
+                binding: 2,
+                name: "samp".to_string(),
+                resource: ResourceKind::Sampler(SamplerType::Sampler),
+            },
+        ],
+    };
+    assert_eq!(res.reflect(), expected);
+}
@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use wgsl_parse::syntax::{GlobalDeclaration, Statement, StatementNode, TranslationUnit};
+
+use crate::{
+    builtin::RESERVED_WORDS,
+    syntax_util::{all_types, flatten_imports},
+    visit::Visit,
+    SyntaxUtil,
+};
+
+/// Error produced by [`SyntaxUtil::rename_declaration`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RenameError {
+    #[error("no global declaration named `{0}`")]
+    NotFound(String),
+    #[error("cannot rename to `{0}`, the name is already declared or used")]
+    AlreadyExists(String),
+    #[error("`{0}` is not a valid identifier")]
+    InvalidIdent(String),
+}
+
+/// Whether `name` is a WGSL identifier: it must lex as an identifier token and not be a
+/// reserved word.
+fn is_valid_ident(name: &str) -> bool {
+    if name == "_" || name.starts_with("__") || RESERVED_WORDS.contains(&name) {
+        return false;
+    }
+    // keywords and invalid characters are rejected by the parser.
+    format!("alias {name} = i32;")
+        .parse::<GlobalDeclaration>()
+        .is_ok_and(|decl| decl.ident().is_some_and(|id| *id.name() == name))
+}
+
+/// All names declared or referred to in the unit: global declarations, imports,
+/// function parameters, local declarations and type or identifier references.
+/// Struct members are not included, they are always accessed through their struct.
+fn used_names(wesl: &TranslationUnit) -> HashSet<String> {
+    fn rec_stmt(stmt: &StatementNode, res: &mut HashSet<String>) {
+        match stmt.node() {
+            Statement::Declaration(decl) => {
+                res.insert(decl.ident.to_string());
+            }
+            Statement::Compound(block) => {
+                res.extend(flatten_imports(&block.imports).map(|id| id.to_string()));
+            }
+            _ => (),
+        }
+        for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+            rec_stmt(stmt, res);
+        }
+    }
+
+    let mut res = wesl
+        .global_declarations
+        .iter()
+        .filter_map(GlobalDeclaration::ident)
+        .map(|id| id.to_string())
+        .chain(flatten_imports(&wesl.imports).map(|id| id.to_string()))
+        .collect::<HashSet<_>>();
+    for decl in &wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            res.extend(f.parameters.iter().map(|p| p.ident.to_string()));
+            res.extend(flatten_imports(&f.body.imports).map(|id| id.to_string()));
+        }
+    }
+    for stmt in Visit::<StatementNode>::visit(wesl) {
+        rec_stmt(stmt, &mut res);
+    }
+    res.extend(all_types(wesl).map(|ty| ty.ident.to_string()));
+    res
+}
+
+pub(crate) fn rename_declaration(
+    wesl: &mut TranslationUnit,
+    old: &str,
+    new: &str,
+) -> Result<(), RenameError> {
+    if !is_valid_ident(new) {
+        return Err(RenameError::InvalidIdent(new.to_string()));
+    }
+    let exists = wesl
+        .global_declarations
+        .iter()
+        .filter_map(GlobalDeclaration::ident)
+        .any(|id| *id.name() == old);
+    if !exists {
+        return Err(RenameError::NotFound(old.to_string()));
+    }
+    if old == new {
+        return Ok(());
+    }
+    // a local declaration or import named `new` would capture the references, and a
+    // reference to another declaration named `new` would be captured by the rename.
+    if used_names(wesl).contains(new) {
+        return Err(RenameError::AlreadyExists(new.to_string()));
+    }
+
+    // references share the ident of the declaration once retargeted.
+    wesl.retarget_idents();
+    let ident = wesl
+        .global_declarations
+        .iter_mut()
+        .filter_map(GlobalDeclaration::ident_mut)
+        .find(|id| *id.name() == old)
+        .expect("the declaration exists");
+    ident.rename(new.to_string());
+    Ok(())
+}
+
+#[test]
+fn test_rename_declaration() {
+    let source = "struct Light { color: vec3f }
+        const scale = 2.0;
+        fn shade(l: Light) -> vec3f { return l.color * scale; }
+        fn twice(l: Light) -> vec3f { let shade_1 = 1.0; return shade(l) + shade(l) * shade_1; }
+        @fragment fn main() -> @location(0) vec4f {
+            let shade = 0.5;
+            return vec4f(twice(Light(vec3f(shade))), 1.0);
+        }";
+    let mut wesl = wgsl_parse::parse_str(source).unwrap();
+    wesl.rename_declaration("shade", "lighting").unwrap();
+    let output = wesl.to_string();
+    assert!(output.contains("fn lighting(l: Light) -> vec3f"));
+    assert!(output.contains("return lighting(l) + lighting(l) * shade_1;"));
+    // the local `shade` shadows the function and is not renamed.
+    assert!(output.contains("let shade = 0.5;"));
+    assert!(output.contains("twice(Light(vec3f(shade)))"));
+
+    // types are renamed too.
+    wesl.rename_declaration("Light", "PointLight").unwrap();
+    let output = wesl.to_string();
+    assert!(output.contains("struct PointLight {"));
+    assert!(output.contains("fn twice(l: PointLight) -> vec3f"));
+    assert!(output.contains("twice(PointLight(vec3f(shade)))"));
+
+    let err = |old, new| {
+        let mut wesl = wgsl_parse::parse_str(source).unwrap();
+        wesl.rename_declaration(old, new).unwrap_err()
+    };
+    assert_eq!(
+        err("shade", "twice"),
+        RenameError::AlreadyExists("twice".to_string())
+    );
+    // the local declaration in `twice` would capture the calls.
+    assert_eq!(
+        err("shade", "shade_1"),
+        RenameError::AlreadyExists("shade_1".to_string())
+    );
+    // a builtin that is referred to.
+    assert_eq!(
+        err("scale", "vec3f"),
+        RenameError::AlreadyExists("vec3f".to_string())
+    );
+    assert_eq!(
+        err("shade", "fn"),
+        RenameError::InvalidIdent("fn".to_string())
+    );
+    assert_eq!(
+        err("shade", "2d"),
+        RenameError::InvalidIdent("2d".to_string())
+    );
+    assert_eq!(
+        err("shade", "__x"),
+        RenameError::InvalidIdent("__x".to_string())
+    );
+    assert_eq!(
+        err("missing", "x"),
+        RenameError::NotFound("missing".to_string())
+    );
+}
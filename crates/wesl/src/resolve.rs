@@ -1,13 +1,22 @@
-use crate::{Diagnostic, Error};
+use crate::{
+    import::nested_blocks_mut, syntax_util::for_each_type_mut, visit::Visit, Diagnostic, Error,
+    ImportError, SyntaxUtil,
+};
 
 use itertools::Itertools;
-use wgsl_parse::syntax::{ModulePath, PathOrigin, TranslationUnit};
+use wgsl_parse::syntax::{
+    Expression, ExpressionNode, GlobalDeclaration, Ident, ImportContent, ImportStatement,
+    ModulePath, PathOrigin, Statement, StatementNode, TranslationUnit,
+};
 
 use std::{
     borrow::Cow,
+    cell::RefCell,
     collections::HashMap,
     fs,
+    hash::{DefaultHasher, Hash, Hasher},
     path::{Path, PathBuf},
+    sync::{Arc, RwLock},
 };
 
 /// Error produced by module resolution.
@@ -17,6 +26,10 @@ pub enum ResolveError {
     FileNotFound(PathBuf, String),
     #[error("module not found: `{0}` ({1})")]
     ModuleNotFound(ModulePath, String),
+    #[error("failed to preprocess `{0}`: {1}")]
+    Preprocess(PathBuf, String),
+    #[error("invalid data URI for module `{0}`: {1}")]
+    InvalidDataUri(ModulePath, String),
     #[error("{0}")]
     Error(#[from] Diagnostic<Error>),
 }
@@ -30,6 +43,12 @@ type E = ResolveError;
 ///
 /// Calls to `Resolver` functions must respect these preconditions:
 /// * the import path must not be relative.
+///
+/// Module identity: a module is identified by its absolute module path, not by the
+/// resolver that served it. Each path is resolved once per compilation, so a module
+/// reached through several imports (or through a [`Router`] or [`OverlayResolver`]) is
+/// included once. Conversely, the same source served under two paths, e.g. mounted twice
+/// in a [`Router`], is two distinct modules with distinct mangled names.
 pub trait Resolver {
     /// Try to resolve a source file identified by a module path.
     fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E>;
@@ -69,6 +88,21 @@ impl<T: Resolver + ?Sized> Resolver for Box<T> {
     }
 }
 
+impl<T: Resolver + ?Sized> Resolver for Arc<T> {
+    fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
+        (**self).resolve_source(path)
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        (**self).source_to_module(source, path)
+    }
+    fn resolve_module(&self, path: &ModulePath) -> Result<TranslationUnit, E> {
+        (**self).resolve_module(path)
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        (**self).display_name(path)
+    }
+}
+
 impl<T: Resolver> Resolver for &T {
     fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
         (**self).resolve_source(path)
@@ -102,12 +136,30 @@ impl Resolver for NoResolver {
 /// A resolver that looks for files in the filesystem.
 ///
 /// It simply translates module paths to file paths. This is the intended behavior.
+/// Each component of the module path is a directory, except the last which is the file:
+/// `import package::a::b::c::thing;` imports `thing` from `a/b/c.wesl`.
+///
+/// The file extension is usually omitted, the resolver tries the [configured
+/// extension][Self::set_extension], then `wgsl`. Import paths can also name the file
+/// explicitly, e.g. `import package::utils.wgsl::foo;`, when both `utils.wesl` and
+/// `utils.wgsl` exist.
+///
+/// Relative imports (`super::`) are resolved against the module path, i.e. the location
+/// of the file. A module can override it with a `@base("some/path");` directive at the
+/// top of the file: its relative imports are then resolved as if the file was located in
+/// `some/path`, relative to the package root. When they conflict, the directive wins.
+///
+/// Files in other formats can be converted to WGSL on the fly with
+/// [`Self::add_preprocessor`].
 #[derive(Default)]
 pub struct FileResolver {
     base: PathBuf,
     extension: &'static str,
+    preprocessors: Vec<(&'static str, Box<PreprocessFn>)>,
 }
 
+type PreprocessFn = dyn Fn(&str) -> Result<String, String> + Send + Sync;
+
 impl FileResolver {
     /// Create a new resolver.
     ///
@@ -116,6 +168,7 @@ impl FileResolver {
         Self {
             base: base.as_ref().to_path_buf(),
             extension: "wesl",
+            preprocessors: Vec::new(),
         }
     }
 
@@ -124,6 +177,27 @@ impl FileResolver {
         self.extension = extension;
     }
 
+    /// Also look for files that end with `extension` (e.g. `"wgsl.tmpl"`), and convert their
+    /// contents to WGSL with `preprocess` before parsing.
+    ///
+    /// Files with the regular extensions are preferred. An error returned by `preprocess`
+    /// is reported as [`ResolveError::Preprocess`].
+    pub fn add_preprocessor(
+        &mut self,
+        extension: &'static str,
+        preprocess: impl Fn(&str) -> Result<String, String> + Send + Sync + 'static,
+    ) {
+        self.preprocessors.push((extension, Box::new(preprocess)));
+    }
+
+    fn preprocessor(&self, fs_path: &Path) -> Option<&PreprocessFn> {
+        let name = fs_path.file_name()?.to_str()?;
+        self.preprocessors
+            .iter()
+            .find(|(ext, _)| name.ends_with(&format!(".{ext}")))
+            .map(|(_, f)| &**f)
+    }
+
     fn file_path(&self, path: &ModulePath) -> Result<PathBuf, E> {
         if path.origin.is_package() {
             return Err(E::ModuleNotFound(
@@ -134,17 +208,30 @@ impl FileResolver {
         }
         let mut fs_path = self.base.to_path_buf();
         fs_path.extend(&path.components);
-        fs_path.set_extension(self.extension);
-        if fs_path.exists() {
-            Ok(fs_path)
-        } else {
-            fs_path.set_extension("wgsl");
-            if fs_path.exists() {
+
+        // the import path has an explicit extension, e.g. `import package::utils.wgsl::foo;`
+        let explicit = fs_path
+            .extension()
+            .is_some_and(|ext| ext == self.extension || ext == "wesl" || ext == "wgsl");
+        if explicit {
+            return if fs_path.exists() {
                 Ok(fs_path)
             } else {
                 Err(E::FileNotFound(fs_path, "physical file".to_string()))
+            };
+        }
+
+        let extensions = [self.extension, "wgsl"]
+            .into_iter()
+            .chain(self.preprocessors.iter().map(|(ext, _)| *ext));
+        for extension in extensions {
+            fs_path.set_extension(extension);
+            if fs_path.exists() {
+                return Ok(fs_path);
             }
         }
+        fs_path.set_extension(self.extension);
+        Err(E::FileNotFound(fs_path, "physical file".to_string()))
     }
 }
 
@@ -152,9 +239,14 @@ impl Resolver for FileResolver {
     fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
         let fs_path = self.file_path(path)?;
         let source = fs::read_to_string(&fs_path)
-            .map_err(|_| E::FileNotFound(fs_path, "physical file".to_string()))?;
+            .map_err(|_| E::FileNotFound(fs_path.clone(), "physical file".to_string()))?;
 
-        Ok(source.into())
+        match self.preprocessor(&fs_path) {
+            Some(preprocess) => preprocess(&source)
+                .map(Cow::Owned)
+                .map_err(|e| E::Preprocess(fs_path, e)),
+            None => Ok(source.into()),
+        }
     }
     fn display_name(&self, path: &ModulePath) -> Option<String> {
         self.file_path(path)
@@ -209,6 +301,236 @@ impl Resolver for VirtualResolver<'_> {
     }
 }
 
+/// A resolver that overrides some modules of the inner resolver with in-memory sources.
+///
+/// Use-case: in an editor, unsaved buffers shadow the files on disk, so that the
+/// current edits are compiled. Modules not in the overlay are resolved by the inner
+/// resolver, and so are the display names of all modules.
+pub struct OverlayResolver<R: Resolver> {
+    pub resolver: R,
+    overlay: HashMap<ModulePath, String>,
+}
+
+impl<R: Resolver> OverlayResolver<R> {
+    /// Create a new resolver with an empty overlay on top of `resolver`.
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver,
+            overlay: HashMap::new(),
+        }
+    }
+
+    /// Shadow the module `path` of the inner resolver with `source`.
+    pub fn set_module(&mut self, path: impl Into<ModulePath>, source: String) {
+        let mut path = path.into();
+        path.origin = PathOrigin::Absolute; // we force absolute paths
+        self.overlay.insert(path, source);
+    }
+
+    /// Stop shadowing the module `path`. Returns the overlay source, if any.
+    pub fn remove_module(&mut self, path: &ModulePath) -> Option<String> {
+        let mut path = path.clone();
+        path.origin = PathOrigin::Absolute; // like set_module
+        self.overlay.remove(&path)
+    }
+
+    /// Iterate over the shadowed modules.
+    pub fn modules(&self) -> impl Iterator<Item = (&ModulePath, &str)> {
+        self.overlay
+            .iter()
+            .map(|(path, source)| (path, source.as_str()))
+    }
+}
+
+impl<R: Resolver> Resolver for OverlayResolver<R> {
+    fn resolve_source<'b>(&'b self, path: &ModulePath) -> Result<Cow<'b, str>, E> {
+        match self.overlay.get(path) {
+            Some(source) => Ok(source.into()),
+            None => self.resolver.resolve_source(path),
+        }
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        self.resolver.source_to_module(source, path)
+    }
+    fn resolve_module(&self, path: &ModulePath) -> Result<TranslationUnit, E> {
+        match self.overlay.get(path) {
+            Some(source) => self.resolver.source_to_module(source, path),
+            None => self.resolver.resolve_module(path),
+        }
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        self.resolver.display_name(path)
+    }
+}
+
+/// A resolver that decodes in-memory modules provided as data URIs.
+///
+/// Supported URIs are of the form `data:[<mediatype>][;base64],<data>`, e.g.
+/// `data:text/wgsl;base64,Zm4gZigpIHt9`. Use-cases are sandboxed (web) environments,
+/// where a host inlines shader modules instead of serving them.
+///
+/// The mapping function translates a module path to the data URI of its content, like
+/// the content key of [`ContentStoreResolver`]. It usually looks up a manifest provided
+/// by the host.
+pub struct DataUriResolver {
+    map: Box<MapFn>,
+}
+
+impl DataUriResolver {
+    /// Create a new resolver.
+    ///
+    /// `map` returns the data URI of a module, or `None` if the module is not provided.
+    pub fn new(map: impl Fn(&ModulePath) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self { map: Box::new(map) }
+    }
+
+    /// Get the data URI of a module.
+    pub fn get_uri(&self, path: &ModulePath) -> Result<String, E> {
+        (self.map)(path)
+            .ok_or_else(|| E::ModuleNotFound(path.clone(), "data URI module".to_string()))
+    }
+
+    /// Decode a data URI into a WGSL/WESL source string.
+    pub fn decode_uri(uri: &str) -> Option<String> {
+        Self::decode(uri).ok()
+    }
+
+    /// Like [`Self::decode_uri`], with the reason of the failure.
+    fn decode(uri: &str) -> Result<String, String> {
+        use base64::Engine;
+        let (header, data) = uri
+            .strip_prefix("data:")
+            .ok_or("missing `data:` scheme")?
+            .split_once(',')
+            .ok_or("missing `,` before the data")?;
+        let bytes = if header.ends_with(";base64") {
+            base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("invalid base64: {e}"))?
+        } else {
+            data.as_bytes().to_vec()
+        };
+        String::from_utf8(bytes).map_err(|_| "the data is not valid UTF-8".to_string())
+    }
+}
+
+impl Resolver for DataUriResolver {
+    fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
+        let uri = self.get_uri(path)?;
+        let source = Self::decode(&uri).map_err(|e| E::InvalidDataUri(path.clone(), e))?;
+        Ok(source.into())
+    }
+}
+
+/// A resolver that calls a closure with the module path and returns the source it gives.
+///
+/// The closure receives the path as a string, e.g. `package::util` for the module
+/// imported with `import package::util::...`. It is the simplest resolver, for scripts
+/// and tests.
+///
+/// # Example
+/// ```rust
+/// # use wesl::{FnResolver, ImportError, ResolveError, Wesl};
+/// let resolver = FnResolver::new(|path| match path {
+///     "package::main" => Ok("@compute @workgroup_size(1) fn main() {}".to_string()),
+///     _ => Err(ResolveError::ModuleNotFound(path.into(), "no such module".into()).into()),
+/// });
+/// Wesl::new("").set_custom_resolver(resolver).compile("main").unwrap();
+/// ```
+pub struct FnResolver<F: Fn(&str) -> Result<String, ImportError>> {
+    f: F,
+}
+
+impl<F: Fn(&str) -> Result<String, ImportError>> FnResolver<F> {
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F: Fn(&str) -> Result<String, ImportError>> Resolver for FnResolver<F> {
+    fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
+        match (self.f)(&path.to_string()) {
+            Ok(source) => Ok(source.into()),
+            Err(ImportError::ResolveError(e)) => Err(e),
+            Err(e) => Err(E::Error(e.into())),
+        }
+    }
+}
+
+type MapFn = dyn Fn(&ModulePath) -> Option<String> + Send + Sync;
+type LoadFn = dyn Fn(&Path) -> std::io::Result<String> + Send + Sync;
+
+/// A resolver that loads modules from a content-addressed store, e.g. a shader package
+/// cache.
+///
+/// The mapping function translates a module path to the key (hash) of its content. The
+/// object is read from `<root>/objects/<2 first chars>/<rest>`, like git loose objects:
+/// the key `abcdef` is stored at `objects/ab/cdef`. Keys must be alphanumeric, e.g. hex
+/// digests, and longer than 2 characters. The mapping function usually looks
+/// up a manifest of the package. Objects are read as UTF-8 text by default, use
+/// [`Self::with_loader`] for stores that compress their objects.
+pub struct ContentStoreResolver {
+    root: PathBuf,
+    map: Box<MapFn>,
+    load: Box<LoadFn>,
+}
+
+impl ContentStoreResolver {
+    /// Create a new resolver.
+    ///
+    /// `root` is the store directory, which contains the `objects` directory. `map`
+    /// returns the content key of a module, or `None` if the module is not in the store.
+    pub fn new(
+        root: impl AsRef<Path>,
+        map: impl Fn(&ModulePath) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            map: Box::new(map),
+            load: Box::new(|path| fs::read_to_string(path)),
+        }
+    }
+
+    /// Read objects with a custom function instead of [`fs::read_to_string`].
+    pub fn with_loader(
+        mut self,
+        load: impl Fn(&Path) -> std::io::Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.load = Box::new(load);
+        self
+    }
+
+    /// Get the location in the store of the module content.
+    pub fn object_path(&self, path: &ModulePath) -> Result<PathBuf, E> {
+        let key = (self.map)(path).ok_or_else(|| {
+            E::ModuleNotFound(path.clone(), "not in the content store".to_string())
+        })?;
+        // the key is a path component, e.g. `../x` must not escape the store.
+        if key.len() <= 2 || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(E::ModuleNotFound(
+                path.clone(),
+                format!("invalid content key `{key}`"),
+            ));
+        }
+        let (prefix, rest) = key.split_at(2);
+        Ok(self.root.join("objects").join(prefix).join(rest))
+    }
+}
+
+impl Resolver for ContentStoreResolver {
+    fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
+        let obj_path = self.object_path(path)?;
+        let source = (self.load)(&obj_path)
+            .map_err(|e| E::FileNotFound(obj_path, format!("content store object: {e}")))?;
+        Ok(source.into())
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        self.object_path(path)
+            .ok()
+            .map(|obj_path| obj_path.display().to_string())
+    }
+}
+
 // trait alias
 pub trait ResolveFn: Fn(&mut TranslationUnit) -> Result<(), Error> {}
 impl<T: Fn(&mut TranslationUnit) -> Result<(), Error>> ResolveFn for T {}
@@ -239,11 +561,7 @@ impl<R: Resolver, F: ResolveFn> Resolver for Preprocessor<R, F> {
         Ok(res)
     }
     fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
-        let mut wesl: TranslationUnit = source.parse().map_err(|e| {
-            Diagnostic::from(e)
-                .with_module_path(path.clone(), self.display_name(path))
-                .with_source(source.to_string())
-        })?;
+        let mut wesl = self.resolver.source_to_module(source, path)?;
         (self.preprocess)(&mut wesl).map_err(|e| {
             Diagnostic::from(e)
                 .with_module_path(path.clone(), self.display_name(path))
@@ -256,10 +574,324 @@ impl<R: Resolver, F: ResolveFn> Resolver for Preprocessor<R, F> {
     }
 }
 
+/// A callback invoked on each module after it is resolved and before it is linked to
+/// other modules. Used in [`crate::CompileOptions::on_resolved_unit`].
+///
+/// Use-case: per-module instrumentation, e.g. injecting counters in each function.
+/// Unlike [`Preprocessor`], the callback knows the module path and cannot fail.
+#[derive(Clone)]
+pub struct ResolvedUnitHook(Arc<HookFn>);
+
+type HookFn = dyn Fn(&ModulePath, &mut TranslationUnit) + Send + Sync;
+
+impl ResolvedUnitHook {
+    pub fn new(hook: impl Fn(&ModulePath, &mut TranslationUnit) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+}
+
+impl std::fmt::Debug for ResolvedUnitHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ResolvedUnitHook(..)")
+    }
+}
+
+/// Runs a [`ResolvedUnitHook`] on each module returned by the inner resolver.
+pub(crate) struct HookResolver<'a, R: Resolver> {
+    pub resolver: R,
+    pub hook: &'a ResolvedUnitHook,
+}
+
+impl<R: Resolver> Resolver for HookResolver<'_, R> {
+    fn resolve_source<'b>(&'b self, path: &ModulePath) -> Result<Cow<'b, str>, E> {
+        self.resolver.resolve_source(path)
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        self.resolver.source_to_module(source, path)
+    }
+    fn resolve_module(&self, path: &ModulePath) -> Result<TranslationUnit, E> {
+        let mut wesl = self.resolver.resolve_module(path)?;
+        (self.hook.0)(path, &mut wesl);
+        Ok(wesl)
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        self.resolver.display_name(path)
+    }
+}
+
+/// A resolver that logs each resolution of the inner resolver, for debugging.
+///
+/// Resolutions are logged with the [`log`] crate at the `debug` level, with the module
+/// path and the resolved resource ([`Resolver::display_name`]). Failures are logged at
+/// the `warn` level. Requires the `log` feature.
+#[cfg(feature = "log")]
+pub struct LoggingResolver<R: Resolver> {
+    pub resolver: R,
+}
+
+#[cfg(feature = "log")]
+impl<R: Resolver> LoggingResolver<R> {
+    /// Create a new resolver that logs the calls to `resolver`.
+    pub fn new(resolver: R) -> Self {
+        Self { resolver }
+    }
+
+    fn log<T>(&self, method: &str, path: &ModulePath, res: &Result<T, E>) {
+        let name = self
+            .display_name(path)
+            .unwrap_or_else(|| "unknown".to_string());
+        match res {
+            Ok(_) => log::debug!("{method}: `{path}` resolved to `{name}`"),
+            Err(e) => log::warn!("{method}: `{path}` failed to resolve to `{name}`: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl<R: Resolver> Resolver for LoggingResolver<R> {
+    fn resolve_source<'b>(&'b self, path: &ModulePath) -> Result<Cow<'b, str>, E> {
+        let res = self.resolver.resolve_source(path);
+        self.log("resolve_source", path, &res);
+        res
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        self.resolver.source_to_module(source, path)
+    }
+    fn resolve_module(&self, path: &ModulePath) -> Result<TranslationUnit, E> {
+        let res = self.resolver.resolve_module(path);
+        self.log("resolve_module", path, &res);
+        res
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        self.resolver.display_name(path)
+    }
+}
+
+/// Replace every identifier of a copy of a cached syntax tree with a new one.
+///
+/// Cloned syntax trees share their identifiers: renaming one in a copy (e.g. mangling)
+/// would rename it in the cache and in the other copies, and the use-counts would count
+/// the references of all copies. References are linked to the new declaration
+/// identifiers by `retarget_idents`.
+fn fresh_idents(wesl: &mut TranslationUnit) {
+    for_each_ident_mut(wesl, &mut |id| *id = Ident::new(id.to_string()));
+    wesl.retarget_idents();
+}
+
+/// Call `f` on each identifier of a syntax tree: declarations, references, struct
+/// members, imports and named components.
+pub(crate) fn for_each_ident_mut(wesl: &mut TranslationUnit, f: &mut impl FnMut(&mut Ident)) {
+    fn imports_mut(imports: &mut [ImportStatement], f: &mut impl FnMut(&mut Ident)) {
+        fn rec(content: &mut ImportContent, f: &mut impl FnMut(&mut Ident)) {
+            match content {
+                ImportContent::Item(item) => {
+                    f(&mut item.ident);
+                    item.rename.iter_mut().for_each(&mut *f);
+                }
+                ImportContent::Collection(coll) => coll
+                    .iter_mut()
+                    .for_each(|import| rec(&mut import.content, f)),
+            }
+        }
+        imports
+            .iter_mut()
+            .for_each(|import| rec(&mut import.content, f));
+    }
+    fn stmt_mut(stmt: &mut StatementNode, f: &mut impl FnMut(&mut Ident)) {
+        if let Statement::Declaration(decl) = stmt.node_mut() {
+            f(&mut decl.ident);
+        }
+        for block in nested_blocks_mut(stmt.node_mut()) {
+            imports_mut(&mut block.imports, f);
+        }
+        Visit::<StatementNode>::visit_mut(stmt.node_mut()).for_each(|stmt| stmt_mut(stmt, f));
+    }
+    fn expr_mut(expr: &mut ExpressionNode, f: &mut impl FnMut(&mut Ident)) {
+        if let Expression::NamedComponent(expr) = expr.node_mut() {
+            f(&mut expr.component);
+        }
+        Visit::<ExpressionNode>::visit_mut(expr.node_mut()).for_each(|expr| expr_mut(expr, f));
+    }
+
+    imports_mut(&mut wesl.imports, f);
+    for decl in &mut wesl.global_declarations {
+        if let Some(id) = decl.ident_mut() {
+            f(id);
+        }
+        match decl {
+            GlobalDeclaration::Struct(s) => {
+                s.members.iter_mut().for_each(|m| f(&mut m.ident));
+            }
+            GlobalDeclaration::Function(func) => {
+                func.parameters.iter_mut().for_each(|p| f(&mut p.ident));
+                imports_mut(&mut func.body.imports, f);
+            }
+            _ => (),
+        }
+    }
+    #[cfg(feature = "generics")]
+    for attrs in Visit::<wgsl_parse::syntax::Attributes>::visit_mut(wesl) {
+        for attr in attrs {
+            if let wgsl_parse::syntax::Attribute::Type(constraint) = attr {
+                f(&mut constraint.ident);
+            }
+        }
+    }
+    Visit::<StatementNode>::visit_mut(wesl).for_each(|stmt| stmt_mut(stmt, f));
+    for_each_type_mut(wesl, &mut |ty| f(&mut ty.ident));
+    Visit::<ExpressionNode>::visit_mut(wesl).for_each(|expr| expr_mut(expr, f));
+}
+
+/// Caches the syntax trees produced by the inner resolver, so that modules shared by
+/// several compilations are parsed once. Used in [`crate::Wesl::compile_project`].
+///
+/// Trees are keyed by module path and by a hash of the source, see [`source_hash`]: a
+/// module whose source changed is parsed again.
+pub(crate) struct CacheResolver<R: Resolver> {
+    pub resolver: R,
+    cache: RefCell<HashMap<ModulePath, (u64, TranslationUnit)>>,
+}
+
+/// The hash of a module source, used by the cache resolvers to detect changed sources.
+fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<R: Resolver> CacheResolver<R> {
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver,
+            cache: Default::default(),
+        }
+    }
+}
+
+impl<R: Resolver> Resolver for CacheResolver<R> {
+    fn resolve_source<'b>(&'b self, path: &ModulePath) -> Result<Cow<'b, str>, E> {
+        self.resolver.resolve_source(path)
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        let hash = source_hash(source);
+        let cached = self.cache.borrow().get(path).cloned();
+        let mut wesl = match cached {
+            Some((h, wesl)) if h == hash => wesl,
+            _ => {
+                let wesl = self.resolver.source_to_module(source, path)?;
+                let entry = (hash, wesl.clone());
+                self.cache.borrow_mut().insert(path.clone(), entry);
+                wesl
+            }
+        };
+        fresh_idents(&mut wesl);
+        Ok(wesl)
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        self.resolver.display_name(path)
+    }
+}
+
+/// Like [`CacheResolver`], but thread-safe: the cache can be shared behind an [`Arc`] by
+/// concurrent compilations, e.g. in a shader compilation server.
+///
+/// Concurrency contract:
+/// * The syntax trees are cached behind a [`RwLock`]. Lookups take a read lock, a cache
+///   miss parses the module without holding the lock, then inserts it with a write lock.
+///   Two threads that miss the same module concurrently both parse it, the first
+///   inserted tree is kept.
+/// * Each call returns a copy of the cached tree in which every identifier is new. The
+///   copy shares no state with the cache or with the copies of other threads, so
+///   compilations that mangle or rename declarations do not affect each other. The
+///   cached trees are never modified.
+/// * Trees are keyed by module path and by a hash of the source. A module whose source
+///   changed is parsed again and replaces the cached tree. Compilations in progress keep
+///   the copies they already got.
+///
+/// # Example
+/// ```rust
+/// # use std::sync::Arc;
+/// # use wesl::{SyncCacheResolver, VirtualResolver, Wesl};
+/// let mut resolver = VirtualResolver::new();
+/// resolver.add_module("main", "@compute @workgroup_size(1) fn main() {}".into());
+/// let cache = Arc::new(SyncCacheResolver::new(resolver));
+///
+/// std::thread::scope(|s| {
+///     for _ in 0..2 {
+///         let compiler = Wesl::new("").set_custom_resolver(cache.clone());
+///         s.spawn(move || compiler.compile("main").unwrap());
+///     }
+/// });
+/// assert_eq!(cache.len(), 1);
+/// ```
+pub struct SyncCacheResolver<R: Resolver> {
+    pub resolver: R,
+    cache: RwLock<HashMap<ModulePath, (u64, TranslationUnit)>>,
+}
+
+impl<R: Resolver> SyncCacheResolver<R> {
+    /// Create a new resolver that caches the syntax trees produced by `resolver`.
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver,
+            cache: Default::default(),
+        }
+    }
+
+    /// Number of cached modules.
+    pub fn len(&self) -> usize {
+        self.cache.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove the module from the cache, e.g. after it was deleted. Changed sources are
+    /// detected without it.
+    pub fn invalidate(&self, path: &ModulePath) {
+        self.cache.write().unwrap().remove(path);
+    }
+
+    pub fn clear(&self) {
+        self.cache.write().unwrap().clear();
+    }
+}
+
+impl<R: Resolver> Resolver for SyncCacheResolver<R> {
+    fn resolve_source<'b>(&'b self, path: &ModulePath) -> Result<Cow<'b, str>, E> {
+        self.resolver.resolve_source(path)
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        let hash = source_hash(source);
+        let cached = self.cache.read().unwrap().get(path).cloned();
+        let mut wesl = match cached {
+            Some((h, wesl)) if h == hash => wesl,
+            _ => {
+                let wesl = self.resolver.source_to_module(source, path)?;
+                let mut cache = self.cache.write().unwrap();
+                match cache.get(path) {
+                    Some((h, cached)) if *h == hash => cached.clone(),
+                    _ => {
+                        cache.insert(path.clone(), (hash, wesl.clone()));
+                        wesl
+                    }
+                }
+            }
+        };
+        fresh_idents(&mut wesl);
+        Ok(wesl)
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        self.resolver.display_name(path)
+    }
+}
+
 /// A resolver that can dispatch imports to several sub-resolvers based on the import
 /// path prefix.
 ///
-/// Add sub-resolvers with [`Self::mount_resolver`].
+/// Add sub-resolvers with [`Self::mount_resolver`]. Modules are identified by their full
+/// import path, see [`Resolver`].
 ///
 /// This resolver is not thread-safe (not [`Send`] or [`Sync`]).
 pub struct Router {
@@ -469,3 +1101,679 @@ impl Resolver for StandardResolver {
         }
     }
 }
+
+/// The modules of the standard library bundled with the crate, by name.
+const STD_MODULES: &[(&str, &str)] = &[
+    ("color", include_str!("std/color.wesl")),
+    ("noise", include_str!("std/noise.wesl")),
+    ("pbr", include_str!("std/pbr.wesl")),
+];
+
+/// A resolver that serves the standard library modules bundled with the crate.
+///
+/// Imports starting with `std::` resolve to the embedded modules (`std::color`,
+/// `std::noise` and `std::pbr`), all other paths are dispatched to the inner resolver.
+///
+/// # Example
+/// ```rust
+/// # use wesl::{StdResolver, VirtualResolver, Wesl};
+/// let mut resolver = VirtualResolver::new();
+/// resolver.add_module("main", "import std::color::luminance;
+///     @compute @workgroup_size(1) fn main() { let l = luminance(vec3(1.0)); }".into());
+/// let compiler = Wesl::new("").set_custom_resolver(StdResolver::new(resolver));
+/// assert!(compiler.compile("main").is_ok());
+/// ```
+pub struct StdResolver<R: Resolver> {
+    pub resolver: R,
+}
+
+impl<R: Resolver> StdResolver<R> {
+    /// Create a new resolver, serving the standard library ahead of `resolver`.
+    pub fn new(resolver: R) -> Self {
+        Self { resolver }
+    }
+
+    /// The names of the bundled modules.
+    pub fn modules() -> impl Iterator<Item = &'static str> {
+        STD_MODULES.iter().map(|(name, _)| *name)
+    }
+
+    fn is_std(path: &ModulePath) -> bool {
+        path.origin.is_package() && path.components.first().map(String::as_str) == Some("std")
+    }
+}
+
+impl<R: Resolver> Resolver for StdResolver<R> {
+    fn resolve_source<'a>(&'a self, path: &ModulePath) -> Result<Cow<'a, str>, E> {
+        if !Self::is_std(path) {
+            return self.resolver.resolve_source(path);
+        }
+        let name = match path.components.as_slice() {
+            [_, name] => name,
+            _ => {
+                return Err(E::ModuleNotFound(
+                    path.clone(),
+                    "standard library modules are not nested".to_string(),
+                ));
+            }
+        };
+        STD_MODULES
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, source)| Cow::Borrowed(*source))
+            .ok_or_else(|| {
+                E::ModuleNotFound(
+                    path.clone(),
+                    format!("no standard library module named `{name}`"),
+                )
+            })
+    }
+    fn source_to_module(&self, source: &str, path: &ModulePath) -> Result<TranslationUnit, E> {
+        if Self::is_std(path) {
+            source.parse().map_err(|e| {
+                Diagnostic::from(e)
+                    .with_module_path(path.clone(), self.display_name(path))
+                    .with_source(source.to_string())
+                    .into()
+            })
+        } else {
+            self.resolver.source_to_module(source, path)
+        }
+    }
+    fn resolve_module(&self, path: &ModulePath) -> Result<TranslationUnit, E> {
+        if Self::is_std(path) {
+            let source = self.resolve_source(path)?;
+            self.source_to_module(&source, path)
+        } else {
+            self.resolver.resolve_module(path)
+        }
+    }
+    fn display_name(&self, path: &ModulePath) -> Option<String> {
+        if Self::is_std(path) {
+            Some(path.to_string())
+        } else {
+            self.resolver.display_name(path)
+        }
+    }
+}
+
+#[test]
+fn test_data_uri_resolver() {
+    // the manifest of the host, from module paths to data URIs.
+    let uris = HashMap::from([
+        // base64 of `fn f() -> u32 { return 1u; }`
+        (
+            ModulePath::from_path("/util"),
+            "data:text/wgsl;base64,Zm4gZigpIC0+IHUzMiB7IHJldHVybiAxdTsgfQ==",
+        ),
+        (ModulePath::from_path("/bad"), "data:text/wgsl;base64,#!"),
+        (ModulePath::from_path("/no_scheme"), "text/wgsl,fn f() {}"),
+    ]);
+    let resolver = DataUriResolver::new(move |path| uris.get(path).map(|uri| uri.to_string()));
+
+    let wesl = resolver
+        .resolve_module(&ModulePath::from_path("/util"))
+        .unwrap();
+    assert_eq!(wesl.global_declarations.len(), 1);
+    assert_eq!(
+        wesl.global_declarations[0].ident().unwrap().to_string(),
+        "f"
+    );
+
+    assert!(matches!(
+        resolver.resolve_source(&ModulePath::from_path("/bad")),
+        Err(E::InvalidDataUri(_, _))
+    ));
+    assert!(matches!(
+        resolver.resolve_source(&ModulePath::from_path("/no_scheme")),
+        Err(E::InvalidDataUri(_, _))
+    ));
+    assert!(matches!(
+        resolver.resolve_source(&ModulePath::from_path("/missing")),
+        Err(E::ModuleNotFound(_, _))
+    ));
+}
+
+/// A temporary directory for the file resolver tests. It is removed on drop, also when the
+/// test panics.
+#[cfg(test)]
+struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("wesl_test_{name}_{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// Write a file, relative to the directory. Parent directories are created.
+    fn write(&self, file: &str, contents: &str) {
+        let path = self.0.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_file_resolver_explicit_extension() {
+    let base = TempDir::new("explicit_ext");
+    base.write(
+        "main.wesl",
+        "import package::utils.wgsl::foo;
+        @compute @workgroup_size(1) fn main() { let x = foo(); }",
+    );
+    base.write("utils.wesl", "fn foo() -> u32 { return 1u; }");
+    base.write("utils.wgsl", "fn foo() -> u32 { return 2u; }");
+
+    let resolver = FileResolver::new(&*base);
+    let path = ModulePath::new(PathOrigin::Absolute, vec!["utils.wgsl".to_string()]);
+    assert_eq!(resolver.file_path(&path).unwrap(), base.join("utils.wgsl"));
+    let path = ModulePath::new(PathOrigin::Absolute, vec!["utils".to_string()]);
+    assert_eq!(resolver.file_path(&path).unwrap(), base.join("utils.wesl"));
+
+    let res = crate::Wesl::new(&*base).compile("main");
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    assert!(wgsl.contains("return 2u;"));
+    assert!(!wgsl.contains("return 1u;"));
+}
+
+#[test]
+fn test_explicit_extension_mangling() {
+    let base = TempDir::new("ext_mangle");
+    base.write(
+        "main.wesl",
+        "import package::utils.wgsl::foo;
+        import package::utils.wesl::foo as bar;
+        import package::utils::wgsl::foo as baz;
+        @compute @workgroup_size(1) fn main() { let x = foo() + bar() + baz(); }",
+    );
+    base.write("utils.wesl", "fn foo() -> u32 { return 1u; }");
+    base.write("utils.wgsl", "fn foo() -> u32 { return 2u; }");
+    base.write("utils/wgsl.wesl", "fn foo() -> u32 { return 3u; }");
+
+    let res = crate::Wesl::new(&*base).compile("main");
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    // the extension is part of the mangled name, the three `foo`s do not collide.
+    assert!(wgsl.contains("fn package__3__utils_dwgsl_foo() -> u32 {\n    return 2u;"));
+    assert!(wgsl.contains("fn package__3__utils_dwesl_foo() -> u32 {\n    return 1u;"));
+    assert!(wgsl.contains("fn package_utils_wgsl_foo() -> u32 {\n    return 3u;"));
+    assert!(wgsl.contains(
+        "package__3__utils_dwgsl_foo() + package__3__utils_dwesl_foo() + package_utils_wgsl_foo()"
+    ));
+}
+
+#[test]
+fn test_file_resolver_preprocessor() {
+    let base = TempDir::new("preprocess");
+    base.write(
+        "main.wesl",
+        "import package::gen::thing::value;
+        @compute @workgroup_size(1) fn main() { let x = value(); }",
+    );
+    base.write(
+        "gen/thing.wgsl.tmpl",
+        "const THING = 7u; fn value() -> u32 { return $thing; }",
+    );
+    base.write("gen/broken.wgsl.tmpl", "fn broken() {}");
+
+    let mut resolver = FileResolver::new(&*base);
+    // the `$name` marker expands to `NAME`.
+    resolver.add_preprocessor("wgsl.tmpl", |source| match source.split_once('$') {
+        Some((before, after)) => {
+            let end = after
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(after.len());
+            let (name, after) = after.split_at(end);
+            Ok(format!("{before}{}{after}", name.to_uppercase()))
+        }
+        None => Err("missing `$` marker".to_string()),
+    });
+    let res = crate::Wesl::new(&*base)
+        .set_custom_resolver(&resolver)
+        .compile("main");
+    let broken = resolver.resolve_source(&ModulePath::from_path("/gen/broken"));
+
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    assert!(wgsl.contains("return package_gen_thing_THING;"));
+    assert!(matches!(broken, Err(ResolveError::Preprocess(path, msg))
+        if path.ends_with("gen/broken.wgsl.tmpl") && msg.contains("marker")));
+}
+
+#[test]
+fn test_file_resolver_nested_path() {
+    use wgsl_parse::syntax::ImportContent;
+    let source = "import package::a::b::c::thing;
+        @compute @workgroup_size(1) fn main() { let x = thing(); }";
+    let wesl = wgsl_parse::parse_str(source).unwrap();
+    let import = &wesl.imports[0];
+    assert_eq!(import.path.to_string(), "package::a::b::c");
+    let ImportContent::Item(item) = &import.content else {
+        panic!("expected an item")
+    };
+    assert_eq!(item.ident.name().as_str(), "thing");
+
+    let base = TempDir::new("nested");
+    base.write("main.wesl", source);
+    base.write("a/b/c.wesl", "fn thing() -> u32 { return 3u; }");
+
+    let resolver = FileResolver::new(&*base);
+    assert_eq!(
+        resolver.file_path(&import.path).unwrap(),
+        base.join("a/b/c.wesl")
+    );
+    let res = crate::Wesl::new(&*base).compile("main");
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    assert!(wgsl.contains("return 3u;"));
+}
+
+#[test]
+fn test_overlay_resolver() {
+    let base = TempDir::new("overlay");
+    base.write(
+        "main.wesl",
+        "import package::util::value;
+        @compute @workgroup_size(1) fn main() { let x = value(); }",
+    );
+    base.write("util.wesl", "fn value() -> u32 { return 1u; }");
+
+    let mut resolver = OverlayResolver::new(FileResolver::new(&*base));
+    resolver.set_module(
+        "util",
+        "fn value() -> u32 { return helper(); }
+        fn helper() -> u32 { return 2u; }"
+            .to_string(),
+    );
+    let res = crate::Wesl::new("")
+        .set_custom_resolver(&resolver)
+        .compile("main");
+    let util = ModulePath::from_path("/util");
+    let display_name = resolver.display_name(&util);
+    // the overlay is keyed by absolute paths, whatever the origin of the argument.
+    let package_util = ModulePath::new(PathOrigin::Package, vec!["util".to_string()]);
+    assert!(resolver.remove_module(&package_util).is_some());
+    let disk = crate::Wesl::new("")
+        .set_custom_resolver(&resolver)
+        .compile("main");
+
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    assert!(wgsl.contains("return 2u;"));
+    assert!(wgsl.contains("fn package_util_helper()"));
+    assert!(!wgsl.contains("return 1u;"));
+    assert_eq!(
+        display_name,
+        Some(base.join("util.wesl").display().to_string())
+    );
+    let wgsl = disk.unwrap_or_else(|e| panic!("{e}")).to_string();
+    assert!(wgsl.contains("return 1u;"));
+}
+
+#[test]
+fn test_base_directive() {
+    use crate::{CompileOptions, EscapeMangler};
+    let base = TempDir::new("base");
+    base.write(
+        "gen/out/main.wesl",
+        "@base(\"shaders/lib.v2\");
+        import super::common::value;
+        @compute @workgroup_size(1) fn main() { let x = value(); }",
+    );
+    base.write("gen/out/common.wesl", "fn value() -> u32 { return 1u; }");
+    base.write(
+        "shaders/lib.v2/common.wesl",
+        "fn value() -> u32 { return 2u; }",
+    );
+
+    let resolver = FileResolver::new(&*base);
+    let root = ModulePath::from_path("/gen/out/main");
+    let res = crate::compile(&root, &resolver, &EscapeMangler, &CompileOptions::default());
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    // the directive wins over the physical location of the file.
+    assert!(wgsl.contains("return 2u;"));
+    assert!(!wgsl.contains("return 1u;"));
+    assert!(!wgsl.contains("@base"));
+}
+
+#[test]
+fn test_on_resolved_unit() {
+    use crate::{CompileOptions, EscapeMangler};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::helper;
+        @compute @workgroup_size(1) fn main() { helper(); }"
+            .into(),
+    );
+    resolver.add_module("util", "fn helper() {}".into());
+    let options = CompileOptions {
+        strip: false,
+        on_resolved_unit: Some(ResolvedUnitHook::new(|path, wesl| {
+            let decl = format!("const depth = {}u;", path.components.len());
+            wesl.global_declarations.push(decl.parse().unwrap());
+        })),
+        ..Default::default()
+    };
+    let wgsl = crate::compile(
+        &ModulePath::from_path("/main"),
+        &resolver,
+        &EscapeMangler,
+        &options,
+    )
+    .unwrap()
+    .to_string();
+    assert!(wgsl.contains("const depth = 1u;"));
+    assert!(wgsl.contains("const package_util_depth = 1u;"));
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn test_logging_resolver() {
+    use std::sync::Mutex;
+
+    static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    struct Capture;
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+        fn log(&self, record: &log::Record) {
+            // dependencies log too, e.g. naga in tests running concurrently.
+            if record.target() == module_path!() {
+                let msg = format!("{} {}", record.level(), record.args());
+                RECORDS.lock().unwrap().push(msg);
+            }
+        }
+        fn flush(&self) {}
+    }
+    log::set_logger(&Capture).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let mut inner = VirtualResolver::new();
+    inner.add_module("main", "fn main() {}".into());
+    let resolver = LoggingResolver::new(inner);
+
+    let main = ModulePath::from_path("/main");
+    let missing = ModulePath::from_path("/missing");
+    assert_eq!(
+        resolver.resolve_module(&main).unwrap().to_string(),
+        resolver.resolver.resolve_module(&main).unwrap().to_string()
+    );
+    assert!(resolver.resolve_source(&missing).is_err());
+    assert_eq!(
+        resolver.display_name(&main),
+        resolver.resolver.display_name(&main)
+    );
+
+    let records = RECORDS.lock().unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records[0].starts_with(&format!("DEBUG resolve_module: `{main}` resolved to")));
+    assert!(records[1].starts_with(&format!("WARN resolve_source: `{missing}` failed")));
+}
+
+#[test]
+fn test_content_store_resolver() {
+    let root = TempDir::new("store");
+    let object = |key: &str, source: &str| {
+        root.write(&format!("objects/{}/{}", &key[..2], &key[2..]), source);
+    };
+    object(
+        "3f2a91",
+        "import package::noise::hash;
+        @compute @workgroup_size(1) fn main() { let x = hash(1u); }",
+    );
+    object(
+        "c0ffee",
+        "fn hash(x: u32) -> u32 { return x * 747796405u; }",
+    );
+
+    let manifest = HashMap::from([
+        (ModulePath::from_path("/main"), "3f2a91".to_string()),
+        (ModulePath::from_path("/noise"), "c0ffee".to_string()),
+        (ModulePath::from_path("/escape"), "../../x".to_string()),
+        (ModulePath::from_path("/dotted"), "ab.cd".to_string()),
+    ]);
+    let resolver = ContentStoreResolver::new(&*root, move |path| manifest.get(path).cloned());
+    let res = crate::Wesl::new("")
+        .set_custom_resolver(&resolver)
+        .compile("main");
+    let missing = resolver.resolve_source(&ModulePath::from_path("/other"));
+
+    let wgsl = res.unwrap_or_else(|e| panic!("{e}")).to_string();
+    assert!(wgsl.contains("fn package_noise_hash(x: u32) -> u32"));
+    assert_eq!(
+        resolver.display_name(&ModulePath::from_path("/noise")),
+        Some(root.join("objects/c0/ffee").display().to_string())
+    );
+    assert!(matches!(missing, Err(ResolveError::ModuleNotFound(..))));
+    for invalid in ["/escape", "/dotted"] {
+        let err = resolver.object_path(&ModulePath::from_path(invalid));
+        let invalid_key = matches!(&err, Err(ResolveError::ModuleNotFound(_, msg))
+            if msg.contains("invalid content key"));
+        assert!(invalid_key, "{err:?}");
+    }
+}
+
+#[test]
+fn test_sync_cache_resolver() {
+    let mut resolver = VirtualResolver::new();
+    for (name, entry) in [("a", "fa"), ("b", "fb"), ("c", "fc")] {
+        resolver.add_module(
+            name,
+            format!(
+                "import package::shared::{{util, Light}};
+                @compute @workgroup_size(1) fn main() {{ let x = util(1.0); let l = {entry}(); }}
+                fn {entry}() -> Light {{ return Light(util(2.0)); }}"
+            )
+            .into(),
+        );
+    }
+    resolver.add_module(
+        "shared",
+        "import package::math::sq;
+        struct Light { power: f32 }
+        fn util(x: f32) -> f32 { return sq(x); }"
+            .into(),
+    );
+    resolver.add_module("math", "fn sq(x: f32) -> f32 { return x * x; }".into());
+
+    let expected = ["a", "b", "c"].map(|root| {
+        crate::Wesl::new("")
+            .set_custom_resolver(&resolver)
+            .compile(root)
+            .unwrap()
+            .to_string()
+    });
+
+    let cache = Arc::new(SyncCacheResolver::new(resolver));
+    std::thread::scope(|s| {
+        let handles = (0..12)
+            .map(|i| {
+                let compiler = crate::Wesl::new("").set_custom_resolver(cache.clone());
+                let root = ["a", "b", "c"][i % 3];
+                s.spawn(move || (i % 3, compiler.compile(root).unwrap().to_string()))
+            })
+            .collect_vec();
+        for handle in handles {
+            let (i, wgsl) = handle.join().unwrap();
+            assert_eq!(wgsl, expected[i]);
+        }
+    });
+    assert_eq!(cache.len(), 5);
+
+    cache.invalidate(&ModulePath::from_path("/math"));
+    assert_eq!(cache.len(), 4);
+}
+
+#[test]
+fn test_cache_resolver_changed_source() {
+    let resolver = VirtualResolver::new();
+    let main = ModulePath::from_path("/main");
+    let name = |res: Result<TranslationUnit, E>| {
+        let wesl = res.unwrap();
+        wesl.global_declarations[0].ident().unwrap().to_string()
+    };
+    let (old, new) = ("fn old() {}", "fn new() {}");
+
+    // the cached tree of a path is not returned for another source.
+    let cache = CacheResolver::new(&resolver);
+    assert_eq!(name(cache.source_to_module(old, &main)), "old");
+    assert_eq!(name(cache.source_to_module(new, &main)), "new");
+    assert_eq!(name(cache.source_to_module(new, &main)), "new");
+    let cache = SyncCacheResolver::new(&resolver);
+    assert_eq!(name(cache.source_to_module(old, &main)), "old");
+    assert_eq!(name(cache.source_to_module(new, &main)), "new");
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn test_cache_resolver_errors() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("util", "fn scale(x: f32) -> f32 { return x * 2.0; }".into());
+    resolver.add_module(
+        "undefined",
+        "import package::util::scale;
+        @compute @workgroup_size(1) fn main() { let x = scale(1.0) + bias; }"
+            .into(),
+    );
+    resolver.add_module(
+        "scoped",
+        "@compute @workgroup_size(1) fn main() {
+            { import package::util::scale; let x = scale(1.0); }
+            let y = scale(2.0);
+        }"
+        .into(),
+    );
+    let roots = ["undefined", "scoped", "undefined", "scoped"];
+    fn compile(resolver: impl Resolver, root: &str) -> String {
+        crate::Wesl::new("")
+            .set_custom_resolver(resolver)
+            .compile(root)
+            .err()
+            .expect("the compilation fails")
+            .to_string()
+    }
+    let expected = roots.map(|root| compile(&resolver, root));
+    assert!(expected[0].contains("cannot find declaration of `bias`"));
+    assert!(expected[1].contains("scale"));
+
+    // the second compilation of each root gets its modules from the cache.
+    let cache = CacheResolver::new(&resolver);
+    assert_eq!(roots.map(|root| compile(&cache, root)), expected);
+    let cache = SyncCacheResolver::new(&resolver);
+    assert_eq!(roots.map(|root| compile(&cache, root)), expected);
+}
+
+#[test]
+fn test_std_resolver() {
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import std::color::srgb_to_linear;
+        @fragment fn main() -> @location(0) vec4<f32> {
+            return vec4(srgb_to_linear(vec3(0.5)), 1.0);
+        }"
+        .into(),
+    );
+    let resolver = StdResolver::new(resolver);
+    let wgsl = crate::Wesl::new("")
+        .set_custom_resolver(&resolver)
+        .set_mangler(crate::ManglerKind::Escape)
+        .compile("main")
+        .unwrap_or_else(|e| panic!("{e}"))
+        .to_string();
+    assert!(wgsl.contains("fn std_color__2srgb_to_linear(color: vec3<f32>) -> vec3<f32>"));
+    assert!(!wgsl.contains("linear_to_srgb"));
+
+    // all bundled modules parse and validate.
+    for name in StdResolver::<NoResolver>::modules() {
+        let path = ModulePath::new(
+            PathOrigin::Package,
+            vec!["std".to_string(), name.to_string()],
+        );
+        let mut wesl = resolver.resolve_module(&path).unwrap();
+        crate::SyntaxUtil::retarget_idents(&mut wesl);
+        crate::validate_wesl(&wesl).unwrap();
+    }
+    let missing = ModulePath::new(PathOrigin::Package, vec!["std".into(), "other".into()]);
+    assert!(matches!(
+        resolver.resolve_source(&missing),
+        Err(ResolveError::ModuleNotFound(..))
+    ));
+}
+
+#[test]
+fn test_fn_resolver() {
+    use crate::Wesl;
+    let resolver = FnResolver::new(|path| match path {
+        "package::main" => Ok("import package::shapes::area;
+            @compute @workgroup_size(1) fn main() { let a = area(2.0); }"
+            .to_string()),
+        "package::shapes" => Ok("import package::consts::PI;
+            fn area(r: f32) -> f32 { return PI * r * r; }"
+            .to_string()),
+        "package::consts" => Ok("const PI = 3.14;".to_string()),
+        _ => Err(E::ModuleNotFound(ModulePath::from_path(path), "no such module".into()).into()),
+    });
+    let compiler = Wesl::new("").set_custom_resolver(resolver);
+    let res = compiler.compile("main").unwrap();
+    assert_eq!(res.syntax.global_declarations.len(), 3);
+
+    let Err(err) = compiler.compile("missing") else {
+        panic!("expected a missing module error")
+    };
+    assert!(err.to_string().contains("no such module"));
+}
+
+#[test]
+fn test_router_module_identity() {
+    let gen = || {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("util", "fn f() -> f32 { return 1.0; }".into());
+        resolver
+    };
+    let mut files = VirtualResolver::new();
+    files.add_module(
+        "main",
+        "import package::gen::util::f;
+        import package::lib::g;
+        @compute @workgroup_size(1) fn main() {
+            let x = f() + g() + package::gen::util::f() + package::copy::util::f();
+        }"
+        .into(),
+    );
+    // the same logical module, reached from a module served by another backend.
+    files.add_module(
+        "lib",
+        "import super::gen::util::f;
+        fn g() -> f32 { return f(); }"
+            .into(),
+    );
+    let mut router = Router::new();
+    router.mount_resolver(ModulePath::from_path("/gen"), gen());
+    router.mount_resolver(ModulePath::from_path("/copy"), gen());
+    router.mount_fallback_resolver(files);
+
+    let wgsl = crate::Wesl::new("")
+        .set_custom_resolver(router)
+        .compile("main")
+        .unwrap()
+        .to_string();
+    assert_eq!(wgsl.matches("fn package_gen_util_f()").count(), 1);
+    // the same source mounted under another path is a distinct module.
+    assert_eq!(wgsl.matches("fn package_copy_util_f()").count(), 1);
+}
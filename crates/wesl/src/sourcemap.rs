@@ -19,11 +19,57 @@ pub trait SourceMap {
     }
 }
 
+/// How columns are counted by [`LineIndex::position`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Count unicode characters. This is the default.
+    #[default]
+    Char,
+    /// Count UTF-16 code units, as in the Language Server Protocol.
+    Utf16,
+}
+
+/// The offsets of line starts in a source, to convert byte offsets to line and column.
+#[derive(Clone, Debug, Default)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { line_starts }
+    }
+
+    /// Get the zero-based line and column of a byte offset in `source`, which must be the
+    /// source this index was built from.
+    ///
+    /// Returns `None` if the offset is out of bounds or not at a character boundary.
+    pub fn position(
+        &self,
+        source: &str,
+        offset: usize,
+        unit: ColumnUnit,
+    ) -> Option<(usize, usize)> {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let text = source.get(self.line_starts[line]..offset)?;
+        let col = match unit {
+            ColumnUnit::Char => text.chars().count(),
+            ColumnUnit::Utf16 => text.encode_utf16().count(),
+        };
+        Some((line, col))
+    }
+}
+
 /// Basic implementation of [`SourceMap`].
 #[derive(Clone, Debug, Default)]
 pub struct BasicSourceMap {
     mappings: HashMap<String, (ModulePath, String)>,
     sources: HashMap<ModulePath, (Option<String>, String)>, // res -> (display_name, source)
+    lines: HashMap<ModulePath, LineIndex>,
+    column_unit: ColumnUnit,
     default_source: Option<String>,
 }
 
@@ -35,11 +81,25 @@ impl BasicSourceMap {
         self.mappings.insert(decl, (path, item));
     }
     pub fn add_source(&mut self, file: ModulePath, name: Option<String>, source: String) {
+        self.lines.insert(file.clone(), LineIndex::new(&source));
         self.sources.insert(file, (name, source));
     }
     pub fn set_default_source(&mut self, source: String) {
         self.default_source = Some(source);
     }
+    /// Count columns returned by [`Self::position`] in this unit. Default: characters.
+    pub fn set_column_unit(&mut self, unit: ColumnUnit) {
+        self.column_unit = unit;
+    }
+    /// Get the zero-based line and column of a byte offset (e.g. a span start) in a module.
+    ///
+    /// Line starts are indexed once when the module is resolved, lookups are cheap.
+    pub fn position(&self, path: &ModulePath, offset: usize) -> Option<(usize, usize)> {
+        let source = self.get_source(path)?;
+        self.lines
+            .get(path)?
+            .position(source, offset, self.column_unit)
+    }
 }
 
 impl SourceMap for BasicSourceMap {
@@ -166,3 +226,34 @@ impl Mangler for SourceMapper<'_> {
         self.mangler.mangle_types(item, variant, types)
     }
 }
+
+#[test]
+fn test_position() {
+    let path = ModulePath::from_path("/main");
+    let mut sourcemap = BasicSourceMap::new();
+    sourcemap.add_source(
+        path.clone(),
+        None,
+        "fn f() {}\nconst x = 1;\n// é𝄞 ok\n".to_string(),
+    );
+
+    // ascii
+    assert_eq!(sourcemap.position(&path, 0), Some((0, 0)));
+    assert_eq!(sourcemap.position(&path, 3), Some((0, 3)));
+    assert_eq!(sourcemap.position(&path, 9), Some((0, 9)));
+    assert_eq!(sourcemap.position(&path, 16), Some((1, 6)));
+
+    // multibyte: `é` is 2 bytes, 1 UTF-16 unit; `𝄞` is 4 bytes, 2 UTF-16 units.
+    let ok = 23 + 3 + 2 + 4 + 1;
+    assert_eq!(sourcemap.position(&path, ok), Some((2, 6)));
+    assert_eq!(sourcemap.position(&path, ok - 2), None);
+    sourcemap.set_column_unit(ColumnUnit::Utf16);
+    assert_eq!(sourcemap.position(&path, ok), Some((2, 7)));
+
+    assert_eq!(sourcemap.position(&path, ok + 3), Some((3, 0)));
+    assert_eq!(sourcemap.position(&path, ok + 4), None);
+    assert_eq!(
+        sourcemap.position(&ModulePath::from_path("/other"), 0),
+        None
+    );
+}
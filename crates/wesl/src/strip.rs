@@ -1,6 +1,12 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use wgsl_parse::syntax::{Ident, TranslationUnit};
+use itertools::Itertools;
+use wgsl_parse::syntax::{Attribute, GlobalDeclaration, Ident, TranslationUnit};
+
+use crate::{bindings::literal_group_binding, syntax_util::all_types, CompileResult};
 
 /// Remove unused declarations.
 pub(crate) fn strip_except(wgsl: &mut TranslationUnit, keep: &HashSet<Ident>) {
@@ -12,3 +18,502 @@ pub(crate) fn strip_except(wgsl: &mut TranslationUnit, keep: &HashSet<Ident>) {
         }
     });
 }
+
+/// Keep only the declarations matching the predicate and the declarations they
+/// depend on, transitively.
+///
+/// Use-case: extract a subset of a shader, e.g. all `@compute` entry points, from an
+/// assembled translation unit such as [`crate::CompileResult::syntax`]. Identifiers must
+/// be retargeted, see [`crate::SyntaxUtil::retarget_idents`].
+pub fn assemble_filtered(wesl: &mut TranslationUnit, pred: impl Fn(&GlobalDeclaration) -> bool) {
+    let decls = wesl
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.ident().map(|id| (id, decl)))
+        .collect::<HashMap<_, _>>();
+
+    let mut next = wesl
+        .global_declarations
+        .iter()
+        .filter(|decl| pred(decl))
+        .collect::<Vec<_>>();
+    let mut keep = next
+        .iter()
+        .filter_map(|decl| decl.ident().cloned())
+        .collect::<HashSet<_>>();
+    while let Some(decl) = next.pop() {
+        for ty in all_types(decl) {
+            if let Some(dep) = decls.get(&ty.ident) {
+                if keep.insert(ty.ident.clone()) {
+                    next.push(dep);
+                }
+            }
+        }
+    }
+
+    wesl.global_declarations.retain(|decl| {
+        decl.ident()
+            .map_or_else(|| pred(decl), |id| keep.contains(id))
+    });
+}
+
+/// Keep only the code of the pipeline that uses the resource `bindings`, given as
+/// `(group, binding)` pairs.
+///
+/// Resource variables with other bindings are removed, with all declarations that refer
+/// to them, transitively. The entry points that remain are kept with the declarations
+/// they depend on, other declarations are removed. Use-case: specialize a shader with
+/// many materials for the pipeline layout of one material. Variables whose `@group` or
+/// `@binding` is not a literal are not trimmed. Identifiers must be retargeted, see
+/// [`crate::SyntaxUtil::retarget_idents`].
+pub fn trim_to_bindings(wgsl: &mut TranslationUnit, bindings: &[(u32, u32)]) {
+    let decls = wgsl
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.ident().map(|id| (id, decl)))
+        .collect::<HashMap<_, _>>();
+
+    // declarations that refer to a removed binding, directly or transitively.
+    fn is_tainted<'a>(
+        decl: &'a GlobalDeclaration,
+        decls: &HashMap<&Ident, &'a GlobalDeclaration>,
+        bindings: &[(u32, u32)],
+        memo: &mut HashMap<&'a Ident, bool>,
+    ) -> bool {
+        let id = decl.ident();
+        if let Some(tainted) = id.and_then(|id| memo.get(id)) {
+            return *tainted;
+        }
+        if let Some(id) = id {
+            memo.insert(id, false); // guard against cycles
+        }
+        let binding = match decl {
+            GlobalDeclaration::Declaration(decl) => literal_group_binding(decl),
+            _ => None,
+        };
+        let tainted = binding.is_some_and(|b| !bindings.contains(&b))
+            || all_types(decl)
+                .filter_map(|ty| decls.get(&ty.ident))
+                .any(|dep| is_tainted(dep, decls, bindings, memo));
+        if let Some(id) = id {
+            memo.insert(id, tainted);
+        }
+        tainted
+    }
+    let mut memo = HashMap::new();
+    let tainted = wgsl
+        .global_declarations
+        .iter()
+        .filter(|decl| is_tainted(decl, &decls, bindings, &mut memo))
+        .filter_map(|decl| decl.ident().cloned())
+        .collect::<HashSet<_>>();
+
+    assemble_filtered(wgsl, |decl| match (decl.ident(), decl) {
+        (Some(id), _) if tainted.contains(id) => false,
+        (Some(_), GlobalDeclaration::Declaration(decl)) => {
+            literal_group_binding(decl).is_some_and(|b| bindings.contains(&b))
+        }
+        (Some(_), _) => ShaderStage::of(decl).is_some(),
+        (None, _) => true,
+    });
+}
+
+/// A shader stage of an entry point function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    pub(crate) fn of(decl: &GlobalDeclaration) -> Option<Self> {
+        let GlobalDeclaration::Function(f) = decl else {
+            return None;
+        };
+        f.attributes.iter().find_map(|attr| match attr {
+            Attribute::Vertex => Some(ShaderStage::Vertex),
+            Attribute::Fragment => Some(ShaderStage::Fragment),
+            Attribute::Compute => Some(ShaderStage::Compute),
+            _ => None,
+        })
+    }
+}
+
+/// A predicate selecting the entry points to keep, over their name, stage and
+/// attributes. Used in [`crate::CompileOptions::keep_filter`].
+///
+/// Use-case: mega-shaders with many entry points, e.g. keep all `@compute` entries.
+#[derive(Clone)]
+pub struct EntryPointFilter(Arc<FilterFn>);
+
+type FilterFn = dyn Fn(&str, ShaderStage, &[Attribute]) -> bool + Send + Sync;
+
+impl EntryPointFilter {
+    pub fn new(
+        filter: impl Fn(&str, ShaderStage, &[Attribute]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    /// Whether the declaration is an entry point selected by the filter.
+    pub(crate) fn matches(&self, decl: &GlobalDeclaration) -> bool {
+        match (decl, ShaderStage::of(decl)) {
+            (GlobalDeclaration::Function(f), Some(stage)) => {
+                (self.0)(&f.ident.name(), stage, &f.attributes)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for EntryPointFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EntryPointFilter(..)")
+    }
+}
+
+/// The compiled WGSL split in a shared chunk and one chunk per shader stage. See
+/// [`CompileResult::emit_by_stage`].
+#[derive(Clone, Debug)]
+pub struct StageChunks {
+    /// Directives and declarations that are not specific to one stage.
+    pub shared: TranslationUnit,
+    /// For each stage, its entry points and the declarations only they use, in the order
+    /// of the entry points.
+    pub stages: Vec<(ShaderStage, TranslationUnit)>,
+}
+
+impl CompileResult {
+    /// Split the output in a shared chunk and one chunk per shader stage.
+    ///
+    /// A declaration goes to a stage chunk if it is only used by the entry points of that
+    /// stage, otherwise it goes to the shared chunk. Declarations not used by any entry
+    /// point are shared. A module-scope `const_assert` goes to the chunk of the
+    /// declarations it refers to. If they are used by several stages, they are shared and
+    /// so is the assertion. The chunks are split after mangling, so names are consistent
+    /// across chunks.
+    ///
+    /// Concatenating the shared chunk with any number of stage chunks forms a valid
+    /// WGSL module, e.g. `format!("{}{}", chunks.shared, vertex_chunk)`. Only the shared
+    /// chunk contains directives.
+    pub fn emit_by_stage(&self) -> StageChunks {
+        let wgsl = &self.syntax;
+        let decls = wgsl
+            .global_declarations
+            .iter()
+            .filter_map(|decl| decl.ident().map(|id| (id, decl)))
+            .collect::<HashMap<_, _>>();
+
+        // the stages using each declaration. `None` stands for the shared chunk.
+        let mut users = HashMap::<&Ident, HashSet<Option<ShaderStage>>>::new();
+        fn mark<'a>(
+            root: &'a GlobalDeclaration,
+            stage: Option<ShaderStage>,
+            decls: &HashMap<&Ident, &'a GlobalDeclaration>,
+            users: &mut HashMap<&'a Ident, HashSet<Option<ShaderStage>>>,
+        ) {
+            let mut next = vec![root];
+            while let Some(decl) = next.pop() {
+                let id = decl.ident().expect("roots and dependencies have an ident");
+                if users.entry(id).or_default().insert(stage) {
+                    next.extend(all_types(decl).filter_map(|ty| decls.get(&ty.ident)));
+                }
+            }
+        }
+
+        let mut stages = Vec::new();
+        for decl in &wgsl.global_declarations {
+            if let Some(stage) = ShaderStage::of(decl) {
+                mark(decl, Some(stage), &decls, &mut users);
+                if !stages.contains(&stage) {
+                    stages.push(stage);
+                }
+            }
+        }
+        let unused = wgsl
+            .global_declarations
+            .iter()
+            .filter(|decl| decl.ident().is_some_and(|id| !users.contains_key(id)))
+            .collect::<Vec<_>>();
+        for decl in unused {
+            mark(decl, None, &decls, &mut users);
+        }
+
+        // const_asserts have no ident, they follow their dependencies, by index.
+        let mut assert_stages = HashMap::new();
+        for (i, decl) in wgsl.global_declarations.iter().enumerate() {
+            if !decl.is_const_assert() {
+                continue;
+            }
+            let deps = all_types(decl)
+                .filter_map(|ty| decls.get(&ty.ident).copied())
+                .collect::<Vec<_>>();
+            let deps_users = deps
+                .iter()
+                .flat_map(|dep| &users[dep.ident().unwrap()])
+                .copied()
+                .collect::<HashSet<_>>();
+            match deps_users.into_iter().exactly_one() {
+                Ok(Some(stage)) => {
+                    assert_stages.insert(i, stage);
+                }
+                _ => {
+                    for dep in deps {
+                        mark(dep, None, &decls, &mut users);
+                    }
+                }
+            }
+        }
+
+        let stage_of = |i: usize, decl: &GlobalDeclaration| {
+            let Some(id) = decl.ident() else {
+                return assert_stages.get(&i).copied();
+            };
+            match users[id].iter().exactly_one() {
+                Ok(stage) => *stage,
+                Err(_) => None,
+            }
+        };
+        let chunk = |stage: Option<ShaderStage>| TranslationUnit {
+            global_declarations: wgsl
+                .global_declarations
+                .iter()
+                .enumerate()
+                .filter(|(i, decl)| stage_of(*i, decl) == stage)
+                .map(|(_, decl)| decl.clone())
+                .collect(),
+            ..Default::default()
+        };
+
+        StageChunks {
+            shared: TranslationUnit {
+                global_directives: wgsl.global_directives.clone(),
+                ..chunk(None)
+            },
+            stages: stages
+                .into_iter()
+                .map(|stage| (stage, chunk(Some(stage))))
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn test_assemble_filtered() {
+    use crate::SyntaxUtil;
+    use wgsl_parse::{syntax::Attribute, Decorated};
+    let source = "struct Particle { pos: vec3f, vel: Velocity }
+        struct Velocity { v: vec3f }
+        const DT = 0.1;
+        const SCALE = 2.0;
+        @group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+        @group(0) @binding(1) var<uniform> color: vec4f;
+        fn step(p: Particle) -> Particle { return Particle(p.pos + p.vel.v * DT, p.vel); }
+        fn shade() -> vec4f { return color * SCALE; }
+        @compute @workgroup_size(64) fn simulate(@builtin(global_invocation_id) id: vec3u) {
+            particles[id.x] = step(particles[id.x]);
+        }
+        @compute @workgroup_size(1) fn reset() { particles[0].pos = vec3f(); }
+        @fragment fn fs() -> @location(0) vec4f { return shade(); }";
+    let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+    wgsl.retarget_idents();
+
+    assemble_filtered(&mut wgsl, |decl| {
+        decl.attributes().contains(&Attribute::Compute)
+    });
+    let names = wgsl
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.ident().map(|id| id.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "Particle",
+            "Velocity",
+            "DT",
+            "particles",
+            "step",
+            "simulate",
+            "reset"
+        ]
+    );
+}
+
+#[test]
+fn test_trim_to_bindings() {
+    use crate::SyntaxUtil;
+    let source = "struct Material { tint: vec4f }
+        @group(0) @binding(0) var<uniform> camera: mat4x4f;
+        @group(1) @binding(0) var<uniform> metal: Material;
+        @group(1) @binding(1) var<uniform> cloth: Material;
+        @group(1) @binding(2) var cloth_tex: texture_2d<f32>;
+        fn project(p: vec4f) -> vec4f { return camera * p; }
+        fn shade_metal() -> vec4f { return metal.tint; }
+        fn shade_cloth() -> vec4f { return cloth.tint * textureLoad(cloth_tex, vec2u(), 0); }
+        @vertex fn vs(@location(0) p: vec4f) -> @builtin(position) vec4f { return project(p); }
+        @fragment fn fs_metal() -> @location(0) vec4f { return shade_metal(); }
+        @fragment fn fs_cloth() -> @location(0) vec4f { return shade_cloth(); }";
+    let trim = |bindings: &[(u32, u32)]| {
+        let mut wgsl = wgsl_parse::parse_str(source).unwrap();
+        wgsl.retarget_idents();
+        trim_to_bindings(&mut wgsl, bindings);
+        wgsl.global_declarations
+            .iter()
+            .filter_map(|decl| decl.ident().map(|id| id.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        trim(&[(0, 0), (1, 0)]),
+        [
+            "Material",
+            "camera",
+            "metal",
+            "project",
+            "shade_metal",
+            "vs",
+            "fs_metal"
+        ]
+    );
+    // the cloth pipeline does not bind the camera: the vertex entry point is removed.
+    assert_eq!(
+        trim(&[(1, 1), (1, 2)]),
+        ["Material", "cloth", "cloth_tex", "shade_cloth", "fs_cloth"]
+    );
+}
+
+#[test]
+fn test_emit_by_stage() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{tint, Vertex};
+        enable f16;
+        const SCALE = 2.0;
+        fn offset() -> vec4f { return vec4f(SCALE); }
+        @vertex fn vs(v: Vertex) -> @builtin(position) vec4f { return v.pos + offset(); }
+        @fragment fn fs() -> @location(0) vec4f { return tint(vec4f(SCALE)); }"
+            .into(),
+    );
+    resolver.add_module(
+        "util",
+        "struct Vertex { @location(0) pos: vec4f }
+        fn tint(c: vec4f) -> vec4f { return c * 0.5; }"
+            .into(),
+    );
+    let result = Wesl::new("")
+        .set_custom_resolver(resolver)
+        .compile("main")
+        .unwrap();
+    let chunks = result.emit_by_stage();
+
+    let names = |wgsl: &TranslationUnit| {
+        wgsl.global_declarations
+            .iter()
+            .filter_map(|decl| decl.ident().map(|id| id.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&chunks.shared), ["SCALE"]);
+    assert_eq!(chunks.shared.global_directives.len(), 1);
+    let [(ShaderStage::Vertex, vertex), (ShaderStage::Fragment, fragment)] = &chunks.stages[..]
+    else {
+        panic!("expected a vertex and a fragment chunk")
+    };
+    assert_eq!(names(vertex), ["offset", "vs", "package_util_Vertex"]);
+    assert_eq!(names(fragment), ["fs", "package_util_tint"]);
+
+    // the shared chunk and a stage chunk form a valid module.
+    let source = format!("{}{}", chunks.shared, fragment);
+    let mut wgsl = wgsl_parse::parse_str(&source).unwrap();
+    crate::SyntaxUtil::retarget_idents(&mut wgsl);
+    crate::validate_wgsl(&wgsl).unwrap();
+}
+
+#[test]
+fn test_emit_by_stage_const_assert() {
+    use crate::{VirtualResolver, Wesl};
+    let chunks = |source: &str| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", source.to_string().into());
+        let result = Wesl::new("")
+            .set_custom_resolver(resolver)
+            .compile("main")
+            .unwrap();
+        result.emit_by_stage()
+    };
+    let stages = "@vertex fn vs() -> @builtin(position) vec4f { return vec4f(f32(VERT_N)); }
+        @fragment fn fs() -> @location(0) vec4f { return vec4f(f32(FRAG_N)); }";
+    let is_valid = |chunks: &[&TranslationUnit]| {
+        let source = chunks.iter().map(ToString::to_string).collect::<String>();
+        let mut wgsl = wgsl_parse::parse_str(&source).unwrap();
+        crate::SyntaxUtil::retarget_idents(&mut wgsl);
+        crate::validate_wgsl(&wgsl).is_ok()
+    };
+
+    // the assertion goes with the declaration it refers to.
+    let c = chunks(&format!(
+        "const VERT_N = 2u; const FRAG_N = 4u; const_assert FRAG_N > 0u; {stages}"
+    ));
+    let [(_, vertex), (_, fragment)] = &c.stages[..] else {
+        panic!("expected a vertex and a fragment chunk")
+    };
+    assert!(c.shared.global_declarations.is_empty());
+    assert!(fragment.to_string().contains("const_assert"));
+    assert!(is_valid(&[&c.shared, vertex]));
+    assert!(is_valid(&[&c.shared, fragment]));
+    assert!(is_valid(&[&c.shared, vertex, fragment]));
+
+    // if it refers to declarations of several stages, they are all shared.
+    let c = chunks(&format!(
+        "const VERT_N = 2u; const FRAG_N = 4u; const_assert FRAG_N > VERT_N; {stages}"
+    ));
+    let [(_, vertex), (_, fragment)] = &c.stages[..] else {
+        panic!("expected a vertex and a fragment chunk")
+    };
+    assert_eq!(c.shared.global_declarations.len(), 3);
+    assert!(is_valid(&[&c.shared, vertex]));
+    assert!(is_valid(&[&c.shared, fragment]));
+}
+
+#[test]
+fn test_keep_entrypoints_where() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "fn step(x: u32) -> u32 { return x + 1u; }
+        fn tint(c: vec4f) -> vec4f { return c * 0.5; }
+        @compute @workgroup_size(64) fn blur() { let x = step(0u); }
+        @compute @workgroup_size(8) fn reduce() { let x = step(1u); }
+        @vertex fn vs() -> @builtin(position) vec4f { return vec4f(0.0); }
+        @fragment fn fs() -> @location(0) vec4f { return tint(vec4f(1.0)); }"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.keep_entrypoints_where(|_, stage, _| stage == ShaderStage::Compute);
+    let names = |compiler: &Wesl<VirtualResolver>| {
+        compiler
+            .compile("main")
+            .unwrap()
+            .syntax
+            .global_declarations
+            .iter()
+            .filter_map(|decl| decl.ident().map(|id| id.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&compiler), ["step", "blur", "reduce"]);
+
+    // the filter sees the attributes, and names in `keep` are kept too.
+    compiler
+        .keep_entrypoints(vec!["fs".to_string()])
+        .keep_entrypoints_where(|name, _, attrs| {
+            name != "blur"
+                && attrs
+                    .iter()
+                    .any(|attr| matches!(attr, Attribute::WorkgroupSize(_)))
+        });
+    assert_eq!(names(&compiler), ["step", "tint", "reduce", "fs"]);
+}
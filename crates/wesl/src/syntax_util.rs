@@ -1,6 +1,10 @@
-use std::{borrow::Cow, collections::HashMap, iter::Iterator};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    iter::Iterator,
+};
 
-use crate::visit::Visit;
+use crate::{feature::Feature, visit::Visit, RenameError, UnitMetrics};
 use wesl_macros::query_mut;
 use wgsl_parse::syntax::*;
 
@@ -23,9 +27,68 @@ impl<T: Iterator> IteratorExt for T {
     }
 }
 
+/// The identifiers introduced by imports in the scope, after renaming.
+pub(crate) fn flatten_imports(imports: &[ImportStatement]) -> impl Iterator<Item = Ident> + '_ {
+    fn rec(content: &ImportContent) -> impl Iterator<Item = Ident> + '_ {
+        match &content {
+            ImportContent::Item(item) => {
+                std::iter::once(item.rename.as_ref().unwrap_or(&item.ident).clone()).boxed()
+            }
+            ImportContent::Collection(coll) => {
+                coll.iter().flat_map(|import| rec(&import.content)).boxed()
+            }
+        }
+    }
+    imports.iter().flat_map(|import| rec(&import.content))
+}
+
+/// The type expressions in a syntax node, including the ones nested in template arguments,
+/// e.g. `f32` in `array<f32, 4>`. Nested type expressions come first.
+pub(crate) fn all_types<T: Visit<TypeExpression>>(
+    node: &T,
+) -> impl Iterator<Item = &TypeExpression> {
+    fn rec(ty: &TypeExpression) -> BoxedIterator<'_, &TypeExpression> {
+        Visit::<TypeExpression>::visit(ty)
+            .flat_map(rec)
+            .chain(std::iter::once(ty))
+            .boxed()
+    }
+    Visit::<TypeExpression>::visit(node).flat_map(rec)
+}
+
+/// Call `f` on the type expressions in a syntax node, like [`all_types`].
+pub(crate) fn for_each_type_mut<T: Visit<TypeExpression>>(
+    node: &mut T,
+    f: &mut impl FnMut(&mut TypeExpression),
+) {
+    fn rec(ty: &mut TypeExpression, f: &mut impl FnMut(&mut TypeExpression)) {
+        for ty in Visit::<TypeExpression>::visit_mut(ty) {
+            rec(ty, f);
+        }
+        f(ty);
+    }
+    for ty in Visit::<TypeExpression>::visit_mut(node) {
+        rec(ty, f);
+    }
+}
+
 pub trait SyntaxUtil {
     fn entry_points(&self) -> impl Iterator<Item = &Ident>;
     fn retarget_idents(&mut self);
+    /// The WGSL features used by the translation unit, that must be declared with
+    /// `enable` or `requires`. Only the common features are detected, see [`Feature`].
+    fn used_features(&self) -> HashSet<Feature>;
+    /// Size and complexity metrics: declarations, statements, expression depth and an
+    /// estimated instruction count. Best computed on the assembled output.
+    fn metrics(&self) -> UnitMetrics;
+    /// Rename the global declaration `old` and all references to it: types, function
+    /// calls and variable references. Local declarations that shadow `old` are not
+    /// renamed.
+    ///
+    /// Fails if `new` is not a valid identifier, or if the name `new` is already declared
+    /// or used in the unit, including by local declarations and references to builtins.
+    /// This is the general-purpose counterpart of the renaming done by import mangling.
+    fn rename_declaration(&mut self, old: &str, new: &str) -> Result<(), RenameError>;
 }
 
 impl SyntaxUtil for TranslationUnit {
@@ -47,6 +110,18 @@ impl SyntaxUtil for TranslationUnit {
             })
     }
 
+    fn used_features(&self) -> HashSet<Feature> {
+        crate::feature::used_features(self)
+    }
+
+    fn metrics(&self) -> UnitMetrics {
+        crate::metrics::metrics(self)
+    }
+
+    fn rename_declaration(&mut self, old: &str, new: &str) -> Result<(), RenameError> {
+        crate::rename::rename_declaration(self, old, new)
+    }
+
     /// make all identifiers that point to the same declaration refer to the same string.
     ///
     /// retarget local references to the local declaration ident and global
@@ -56,20 +131,6 @@ impl SyntaxUtil for TranslationUnit {
         // keep track of declarations in a scope.
         type Scope<'a> = Cow<'a, HashMap<String, Ident>>;
 
-        fn flatten_imports(imports: &[ImportStatement]) -> impl Iterator<Item = Ident> + '_ {
-            fn rec(content: &ImportContent) -> impl Iterator<Item = Ident> + '_ {
-                match &content {
-                    ImportContent::Item(item) => {
-                        std::iter::once(item.rename.as_ref().unwrap_or(&item.ident).clone()).boxed()
-                    }
-                    ImportContent::Collection(coll) => {
-                        coll.iter().flat_map(|import| rec(&import.content)).boxed()
-                    }
-                }
-            }
-            imports.iter().flat_map(|import| rec(&import.content))
-        }
-
         let scope: Scope = Cow::Owned(
             self.global_declarations
                 .iter()
@@ -90,6 +151,17 @@ impl SyntaxUtil for TranslationUnit {
                 .for_each(|ty| retarget_ty(ty, scope));
         }
 
+        // block-scoped imports are visible in the block, like local declarations.
+        fn retarget_block<'a>(block: &'a mut CompoundStatement, scope: &Scope<'a>) -> Scope<'a> {
+            let mut scope = scope.clone();
+            if !block.imports.is_empty() {
+                scope
+                    .to_mut()
+                    .extend(flatten_imports(&block.imports).map(|id| (id.to_string(), id)));
+            }
+            retarget_stats(&mut block.statements, scope)
+        }
+
         // retarget local references to the local declaration ident and global
         // references to the global declaration ident. It does this by keeping track of the
         // local declarations scope.
@@ -102,7 +174,7 @@ impl SyntaxUtil for TranslationUnit {
                 Statement::Compound(s) => {
                     query_mut!(s.attributes.[].(x => x.visit_mut()))
                         .for_each(|ty| retarget_ty(ty, &scope));
-                    retarget_stats(&mut s.statements, scope.clone());
+                    retarget_block(s, &scope);
                 }
                 Statement::Assignment(s) => {
                     query_mut!(s.{
@@ -151,12 +223,12 @@ impl SyntaxUtil for TranslationUnit {
                         },
                     })
                     .for_each(|ty| retarget_ty(ty, &scope));
-                    retarget_stats(&mut s.if_clause.body.statements, scope.clone());
+                    retarget_block(&mut s.if_clause.body, &scope);
                     for clause in &mut s.else_if_clauses {
-                        retarget_stats(&mut clause.body.statements, scope.clone());
+                        retarget_block(&mut clause.body, &scope);
                     }
                     if let Some(clause) = &mut s.else_clause {
-                        retarget_stats(&mut clause.body.statements, scope.clone());
+                        retarget_block(&mut clause.body, &scope);
                     }
                 }
                 Statement::Switch(s) => {
@@ -176,7 +248,7 @@ impl SyntaxUtil for TranslationUnit {
                     })
                     .for_each(|ty| retarget_ty(ty, &scope));
                     for clause in &mut s.clauses {
-                        retarget_stats(&mut clause.body.statements, scope.clone());
+                        retarget_block(&mut clause.body, &scope);
                     }
                 }
                 Statement::Loop(s) => {
@@ -186,7 +258,7 @@ impl SyntaxUtil for TranslationUnit {
                         body.attributes.[].(x => x.visit_mut()),
                     })
                     .for_each(|ty| retarget_ty(ty, &scope));
-                    let scope = retarget_stats(&mut s.body.statements, scope.clone());
+                    let scope = retarget_block(&mut s.body, &scope);
                     // continuing, if present, must be the last statement of the loop body
                     // and therefore has access to the scope at the end of the body.
                     if let Some(s) = &mut s.continuing {
@@ -224,7 +296,7 @@ impl SyntaxUtil for TranslationUnit {
                     if let Some(update) = &mut s.update {
                         retarget_stats([update], scope.clone());
                     }
-                    retarget_stats(&mut s.body.statements, scope);
+                    retarget_block(&mut s.body, &scope);
                 }
                 Statement::While(s) => {
                     let s2 = &mut *s; // COMBAK: not sure why this is needed?
@@ -234,7 +306,7 @@ impl SyntaxUtil for TranslationUnit {
                         body.attributes.[].(x => x.visit_mut()),
                     })
                     .for_each(|ty| retarget_ty(ty, &scope));
-                    retarget_stats(&mut s.body.statements, scope.clone());
+                    retarget_block(&mut s.body, &scope);
                 }
                 Statement::Break(s) => {
                     query_mut!(s.attributes.[].(x => x.visit_mut()))
@@ -328,7 +400,7 @@ impl SyntaxUtil for TranslationUnit {
                             .iter()
                             .map(|param| (param.ident.to_string(), param.ident.clone())),
                     );
-                    retarget_stats(&mut d.body.statements, scope);
+                    retarget_block(&mut d.body, &scope);
                 }
                 GlobalDeclaration::ConstAssert(d) => {
                     Visit::<TypeExpression>::visit_mut(d).for_each(|ty| retarget_ty(ty, &scope))
@@ -0,0 +1,102 @@
+use wgsl_parse::syntax::{CompoundStatement, GlobalDeclaration, Statement, TranslationUnit};
+
+use crate::{import::nested_blocks, Diagnostic, Warning};
+
+/// Whether control never flows past the statement: it is a `return`, `break`,
+/// `continue` or `discard`, or a block or an `if` with an `else` in which every branch
+/// ends that way.
+fn diverges(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Return(_)
+        | Statement::Break(_)
+        | Statement::Continue(_)
+        | Statement::Discard(_) => true,
+        Statement::Compound(block) => block_diverges(block),
+        Statement::If(if_stmt) => {
+            if_stmt.else_clause.is_some() && nested_blocks(stmt).all(block_diverges)
+        }
+        _ => false,
+    }
+}
+
+fn block_diverges(block: &CompoundStatement) -> bool {
+    block.statements.iter().any(|stmt| diverges(stmt.node()))
+}
+
+fn check_block(block: &CompoundStatement, decl: &str, res: &mut Vec<Diagnostic<Warning>>) {
+    let mut dead = false;
+    for stmt in &block.statements {
+        if matches!(stmt.node(), Statement::Void) {
+            continue;
+        }
+        // only the first unreachable statement of a block is reported.
+        if dead {
+            let warning = Diagnostic::from(Warning::UnreachableCode)
+                .with_span(stmt.span().clone())
+                .with_declaration(decl.to_string());
+            res.push(warning);
+            return;
+        }
+        for block in nested_blocks(stmt.node()) {
+            check_block(block, decl, res);
+        }
+        dead = diverges(stmt.node());
+    }
+}
+
+/// Statements that come after an unconditional `return`, `break`, `continue` or
+/// `discard` in the same block. One warning is reported per block, with the span of the
+/// first unreachable statement.
+pub fn unreachable_code(wesl: &TranslationUnit) -> Vec<Diagnostic<Warning>> {
+    let mut res = Vec::new();
+    for decl in &wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            check_block(&f.body, &f.ident.name(), &mut res);
+        }
+    }
+    res
+}
+
+#[test]
+fn test_unreachable_code() {
+    let source = "fn f(x: u32) -> u32 {
+            if x > 0u {
+                return 1u;
+            } else {
+                let y = x;
+            }
+            loop {
+                if x == 1u { break; }
+                continue;
+                let z = 2u;
+            }
+            return 0u;
+            let w = 3u;
+        }";
+    let wesl = source.parse::<TranslationUnit>().unwrap();
+    let warnings = unreachable_code(&wesl);
+    let spans = warnings
+        .iter()
+        .map(|w| &source[w.span.clone().unwrap().range()])
+        .collect::<Vec<_>>();
+    // the branches of the `if` and the `break` in a nested block are not flagged.
+    assert_eq!(spans, ["let z = 2u;", "let w = 3u;"]);
+    assert_eq!(warnings[0].declaration.as_deref(), Some("f"));
+}
+
+#[test]
+fn test_unreachable_code_opt_in() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "@compute @workgroup_size(1) fn main() { return; let x = 1u; }".into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    assert!(compiler.compile("main").unwrap().warnings.is_empty());
+
+    compiler.warn_unreachable_code(true);
+    let warnings = compiler.compile("main").unwrap().warnings;
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(*warnings[0].error, Warning::UnreachableCode));
+}
@@ -2,13 +2,16 @@ use std::collections::HashSet;
 
 use wesl_macros::query;
 use wgsl_parse::syntax::{
+    AddressSpace, Attribute, Attributes, CompoundStatement, CustomAttribute, DeclarationKind,
     Expression, ExpressionNode, FunctionCall, GlobalDeclaration, Ident, Statement, StatementNode,
-    TranslationUnit, TypeExpression,
+    TranslationUnit, TypeExpression, WorkgroupSizeAttribute,
 };
 
 use crate::builtin::{BUILTIN_FUNCTIONS, BUILTIN_NAMES, RESERVED_WORDS};
+use crate::import::nested_blocks;
+use crate::syntax_util::{all_types, flatten_imports, for_each_type_mut};
 use crate::visit::Visit;
-use crate::{Diagnostic, Error};
+use crate::{Diagnostic, Error, SyntaxUtil};
 
 /// WESL or WGSL Validation error.
 #[derive(Clone, Debug, thiserror::Error)]
@@ -25,46 +28,125 @@ pub enum ValidateError {
     Cycle(String, String),
     #[error("use of reserved word `{0}`")]
     ReservedWord(String),
+    #[error("function `{0}` is marked `@const` but is not const-evaluable")]
+    NotConst(String),
+    #[error("no entry point: expected a `@vertex`, `@fragment` or `@compute` function")]
+    NoEntryPoint,
+    #[error("the element count of `{0}` refers to override `{1}`, only `var<workgroup>` arrays can have an override element count")]
+    OverrideArrayLength(String, String),
+    #[error("unknown attribute `@{0}`")]
+    UnknownAttribute(String),
+    #[error("workgroup size `{1}` of `{0}` is {2}, the maximum is {3}")]
+    WorkgroupSize(String, char, u32, u32),
+    #[error("workgroup of `{0}` has {1} invocations, the maximum is {2}")]
+    WorkgroupInvocations(String, u64, u32),
+    #[error("member `{1}` of struct `{0}` is a runtime-sized array, only the last member can be")]
+    RuntimeArrayNotLast(String, String),
+    #[error("member `{1}` of struct `{0}` has a type with no size or alignment")]
+    NoLayout(String, String),
+    #[error("`{1}` and `{2}` are both mangled to `{0}`")]
+    MangleCollision(String, String, String),
 }
 
 type E = ValidateError;
 
+/// A name is a builtin if it is a WGSL builtin or one of the user-provided builtins.
+fn is_builtin(name: &str, builtins: &HashSet<String>) -> bool {
+    BUILTIN_NAMES.contains(&name) || builtins.contains(name)
+}
+
+/// The identifiers of the declarations of the unit: global and local declarations,
+/// function parameters, imports and generic type parameters.
+fn declared_idents(wesl: &TranslationUnit) -> HashSet<Ident> {
+    fn rec_stmt(stmt: &StatementNode, res: &mut HashSet<Ident>) {
+        if let Statement::Declaration(decl) = stmt.node() {
+            res.insert(decl.ident.clone());
+        }
+        for block in nested_blocks(stmt.node()) {
+            res.extend(flatten_imports(&block.imports));
+        }
+        for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+            rec_stmt(stmt, res);
+        }
+    }
+
+    let mut res = wesl
+        .global_declarations
+        .iter()
+        .filter_map(GlobalDeclaration::ident)
+        .cloned()
+        .chain(flatten_imports(&wesl.imports))
+        .collect::<HashSet<_>>();
+    for decl in &wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            res.extend(f.parameters.iter().map(|p| p.ident.clone()));
+            res.extend(flatten_imports(&f.body.imports));
+            #[cfg(feature = "generics")]
+            res.extend(f.attributes.iter().filter_map(|attr| match attr {
+                Attribute::Type(constraint) => Some(constraint.ident.clone()),
+                _ => None,
+            }));
+        }
+    }
+    for stmt in Visit::<StatementNode>::visit(wesl) {
+        rec_stmt(stmt, &mut res);
+    }
+    res
+}
+
 /// An identifier is linked to a declaration if:
-/// * its use-count is greater than 1
+/// * it is the identifier of a declaration of the unit, see [`declared_idents`]
+/// * OR it is the identifier of one of the `external` declarations
 /// * OR it is a built-in name
 ///
+/// Identifiers must be retargeted. Qualified references (e.g. `package::foo::bar`) are
+/// checked when imports are resolved.
+///
 /// Note that this function could be simplified if we didn't care about the diagnostics metadata (declaration and expression)
-fn check_defined_symbols(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
-    fn check_ty(ty: &TypeExpression) -> Result<(), Diagnostic<Error>> {
-        if ty.ident.use_count() == 1 && !BUILTIN_NAMES.contains(&ty.ident.name().as_str()) {
+fn check_defined_symbols(
+    wesl: &TranslationUnit,
+    builtins: &HashSet<String>,
+    external: &HashSet<Ident>,
+) -> Result<(), Diagnostic<Error>> {
+    struct Names<'a> {
+        declared: HashSet<Ident>,
+        external: &'a HashSet<Ident>,
+        builtins: &'a HashSet<String>,
+    }
+    fn check_ty(ty: &TypeExpression, names: &Names) -> Result<(), Diagnostic<Error>> {
+        if ty.path.is_none()
+            && !names.declared.contains(&ty.ident)
+            && !names.external.contains(&ty.ident)
+            && !is_builtin(&ty.ident.name(), names.builtins)
+        {
             Err(E::UndefinedSymbol(ty.ident.to_string()).into())
         } else {
             for arg in ty.template_args.iter().flatten() {
-                check_expr(&arg.expression)?;
+                check_expr(&arg.expression, names)?;
             }
             Ok(())
         }
     }
-    fn check_expr(expr: &ExpressionNode) -> Result<(), Diagnostic<Error>> {
+    fn check_expr(expr: &ExpressionNode, names: &Names) -> Result<(), Diagnostic<Error>> {
         if let Expression::TypeOrIdentifier(ty) = expr.node() {
-            check_ty(ty).map_err(|d| d.with_span(expr.span().clone()))
+            check_ty(ty, names).map_err(|d| d.with_span(expr.span().clone()))
         } else if let Expression::FunctionCall(call) = expr.node() {
-            check_ty(&call.ty).map_err(|d| d.with_span(expr.span().clone()))?;
+            check_ty(&call.ty, names).map_err(|d| d.with_span(expr.span().clone()))?;
             for expr in &call.arguments {
-                check_expr(expr)?;
+                check_expr(expr, names)?;
             }
             Ok(())
         } else {
             for expr in Visit::<ExpressionNode>::visit(expr.node()) {
-                check_expr(expr)?;
+                check_expr(expr, names)?;
             }
             Ok(())
         }
     }
-    fn check_decl(decl: &GlobalDeclaration) -> Result<(), Diagnostic<Error>> {
+    fn check_decl(decl: &GlobalDeclaration, names: &Names) -> Result<(), Diagnostic<Error>> {
         let decl_name = decl.ident().map(|ident| ident.name().to_string());
         for expr in Visit::<ExpressionNode>::visit(decl) {
-            check_expr(expr).map_err(|mut d| {
+            check_expr(expr, names).map_err(|mut d| {
                 d.declaration = decl_name.clone();
                 d
             })?;
@@ -78,7 +160,7 @@ fn check_defined_symbols(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>
             GlobalDeclaration::Struct.members.[].ty,
             GlobalDeclaration::Function.{ parameters.[].ty, return_type.[] }
         }) {
-            check_ty(ty).map_err(|mut d| {
+            check_ty(ty, names).map_err(|mut d| {
                 d.declaration = decl_name.clone();
                 d
             })?;
@@ -86,14 +168,27 @@ fn check_defined_symbols(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>
         Ok(())
     }
 
+    let names = Names {
+        declared: declared_idents(wesl),
+        external,
+        builtins,
+    };
     for decl in &wesl.global_declarations {
-        check_decl(decl)?;
+        check_decl(decl, &names)?;
     }
     Ok(())
 }
 
-fn check_function_calls(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
-    fn check_call(call: &FunctionCall, ident: &Ident, wesl: &TranslationUnit) -> Result<(), E> {
+fn check_function_calls(
+    wesl: &TranslationUnit,
+    builtins: &HashSet<String>,
+) -> Result<(), Diagnostic<Error>> {
+    fn check_call(
+        call: &FunctionCall,
+        ident: &Ident,
+        wesl: &TranslationUnit,
+        builtins: &HashSet<String>,
+    ) -> Result<(), E> {
         let decl = wesl
             .global_declarations
             .iter()
@@ -122,12 +217,14 @@ fn check_function_calls(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>>
                 if decl.ty.template_args.is_some() {
                     return Err(E::NotCallable(ident.to_string()));
                 } else {
-                    check_call(call, &decl.ty.ident, wesl)?;
+                    check_call(call, &decl.ty.ident, wesl, builtins)?;
                 }
             }
             Some(_) => return Err(E::NotCallable(ident.to_string())),
             None => {
-                if BUILTIN_FUNCTIONS.iter().any(|name| name == &*ident.name()) {
+                if BUILTIN_FUNCTIONS.iter().any(|name| name == &*ident.name())
+                    || builtins.contains(&*ident.name())
+                {
                     // TODO: check args for builtin functions
                 } else {
                     // the ident is not a global declaration, it must be a local variable.
@@ -137,15 +234,19 @@ fn check_function_calls(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>>
         };
         Ok(())
     }
-    fn check_expr(expr: &Expression, wesl: &TranslationUnit) -> Result<(), E> {
+    fn check_expr(
+        expr: &Expression,
+        wesl: &TranslationUnit,
+        builtins: &HashSet<String>,
+    ) -> Result<(), E> {
         if let Expression::FunctionCall(call) = expr {
-            check_call(call, &call.ty.ident, wesl)?;
+            check_call(call, &call.ty.ident, wesl, builtins)?;
         }
         Ok(())
     }
     for decl in &wesl.global_declarations {
         for expr in Visit::<ExpressionNode>::visit(decl) {
-            check_expr(expr, wesl).map_err(|e| {
+            check_expr(expr, wesl, builtins).map_err(|e| {
                 let mut err = Diagnostic::from(e);
                 err.span = Some(expr.span().clone());
                 err.declaration = decl.ident().map(|id| id.name().to_string());
@@ -170,6 +271,36 @@ fn check_duplicate_decl(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>>
     Ok(())
 }
 
+/// Locals cannot be redeclared in the same block. Parameters are in the scope of the
+/// function body. Locals in nested blocks can shadow enclosing locals and parameters.
+fn check_local_decls(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    fn check_block(
+        block: &CompoundStatement,
+        mut names: HashSet<String>,
+    ) -> Result<(), Diagnostic<Error>> {
+        for stmt in &block.statements {
+            if let Statement::Declaration(decl) = stmt.node() {
+                if !names.insert(decl.ident.to_string()) {
+                    return Err(Diagnostic::from(E::Duplicate(decl.ident.to_string()))
+                        .with_span(stmt.span().clone()));
+                }
+            }
+            for block in nested_blocks(stmt) {
+                check_block(block, HashSet::new())?;
+            }
+        }
+        Ok(())
+    }
+
+    for decl in &wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            let params = f.parameters.iter().map(|p| p.ident.to_string()).collect();
+            check_block(&f.body, params).map_err(|d| d.with_declaration(f.ident.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
 fn check_cycles(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
     fn check_decl(
         id: &Ident,
@@ -253,6 +384,325 @@ fn check_reserved_words(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>>
     Ok(())
 }
 
+/// Fixed-size array element counts must be const-expressions, except in the store type of
+/// `var<workgroup>` declarations, where override-expressions are allowed.
+fn check_array_lengths(wgsl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    fn find_override<'a>(expr: &'a Expression, overrides: &HashSet<&Ident>) -> Option<&'a Ident> {
+        all_types(expr)
+            .map(|ty| &ty.ident)
+            .find(|id| overrides.contains(id))
+    }
+    fn check_ty(
+        ty: &TypeExpression,
+        exempt: Option<&TypeExpression>,
+        overrides: &HashSet<&Ident>,
+    ) -> Result<(), Diagnostic<Error>> {
+        if let Some([_, count]) = ty.template_args.as_deref() {
+            let is_exempt = exempt.is_some_and(|exempt| std::ptr::eq(ty, exempt));
+            if *ty.ident.name() == "array" && !is_exempt {
+                if let Some(id) = find_override(&count.expression, overrides) {
+                    return Err(Diagnostic::from(E::OverrideArrayLength(
+                        ty.to_string(),
+                        id.to_string(),
+                    ))
+                    .with_span(count.expression.span().clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let overrides = wgsl
+        .global_declarations
+        .iter()
+        .filter_map(|decl| match decl {
+            GlobalDeclaration::Declaration(d) if d.kind == DeclarationKind::Override => {
+                Some(&d.ident)
+            }
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    if overrides.is_empty() {
+        return Ok(());
+    }
+
+    for decl in &wgsl.global_declarations {
+        let exempt = match decl {
+            GlobalDeclaration::Declaration(d)
+                if d.kind == DeclarationKind::Var(Some(AddressSpace::Workgroup)) =>
+            {
+                d.ty.as_ref()
+            }
+            _ => None,
+        };
+        for ty in all_types(decl) {
+            check_ty(ty, exempt, &overrides).map_err(|mut d| {
+                d.declaration = decl.ident().map(Ident::to_string);
+                d
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// `@const` functions must only use const-evaluable constructs: no pointers, no calls to
+/// non-const functions, etc.
+#[cfg(feature = "eval")]
+fn check_const_functions(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    use wgsl_parse::syntax::Attribute;
+    for decl in &wesl.global_declarations {
+        if let GlobalDeclaration::Function(decl) = decl {
+            if decl.attributes.contains(&Attribute::Const) {
+                crate::eval::check_function_const(decl, wesl).map_err(|span| {
+                    let mut err = Diagnostic::from(E::NotConst(decl.ident.to_string()))
+                        .with_declaration(decl.ident.to_string());
+                    err.span = span;
+                    err
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Statically-evaluable `const_assert`s must be true. Assertions that cannot be evaluated,
+/// e.g. those referring to function-scope consts or unsupported builtins, are left to the
+/// backend.
+///
+/// Only the declarations an assertion refers to are evaluated, on demand.
+#[cfg(feature = "eval")]
+fn check_const_asserts(wgsl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    use crate::eval::{Context, Eval, EvalError, Instance, LiteralInstance, ScopeKind};
+
+    fn collect_asserts<'a>(
+        stmt: &'a StatementNode,
+        asserts: &mut Vec<&'a ExpressionNode>,
+        locals: &mut HashSet<&'a Ident>,
+    ) {
+        match stmt.node() {
+            Statement::ConstAssert(s) => asserts.push(&s.expression),
+            Statement::Declaration(s) => {
+                locals.insert(&s.ident);
+            }
+            _ => (),
+        }
+        for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+            collect_asserts(stmt, asserts, locals);
+        }
+    }
+
+    let mut ctx = Context::new(wgsl);
+    // module-scope declarations are executed when first referred to.
+    ctx.kind = ScopeKind::Module;
+    let mut check = |expr: &ExpressionNode, decl: Option<&Ident>| match expr.eval_value(&mut ctx) {
+        Ok(Instance::Literal(LiteralInstance::Bool(false))) => {
+            let mut err = Diagnostic::from(EvalError::ConstAssertFailure(expr.clone()))
+                .with_span(expr.span().clone());
+            err.declaration = decl.map(Ident::to_string);
+            Err(err)
+        }
+        _ => Ok(()),
+    };
+
+    for decl in &wgsl.global_declarations {
+        match decl {
+            GlobalDeclaration::ConstAssert(a) => check(&a.expression, None)?,
+            GlobalDeclaration::Function(f) => {
+                let mut asserts = Vec::new();
+                let mut locals = f.parameters.iter().map(|p| &p.ident).collect();
+                for stmt in &f.body.statements {
+                    collect_asserts(stmt, &mut asserts, &mut locals);
+                }
+                for expr in asserts {
+                    let local = all_types(expr.node()).any(|ty| locals.contains(&ty.ident));
+                    if !local {
+                        check(expr, Some(&f.ident))?;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Attributes that are not part of WGSL but are consumed by the WESL compiler.
+const WESL_ATTRIBUTES: &[&str] = &["no_mangle", "generic"];
+
+/// Validate that all attributes are either standard WGSL attributes, attributes consumed
+/// by the WESL compiler (`@no_mangle`, `@generic`), or in `known`.
+///
+/// This catches typos like `@vertx`, which are otherwise parsed as custom attributes and
+/// left in the output. See [`crate::CompileOptions::known_attributes`].
+pub fn validate_attributes(
+    wesl: &TranslationUnit,
+    known: &HashSet<String>,
+) -> Result<(), Diagnostic<Error>> {
+    for attrs in Visit::<Attributes>::visit(wesl) {
+        for attr in attrs {
+            if let Attribute::Custom(CustomAttribute { name, .. }) = attr {
+                if !WESL_ATTRIBUTES.contains(&name.as_str()) && !known.contains(name) {
+                    return Err(E::UnknownAttribute(name.clone()).into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Maximum compute workgroup sizes, usually the limits of the target device. Used in
+/// [`validate_workgroup_size`].
+///
+/// The defaults are the WebGPU default limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkgroupLimits {
+    pub max_size_x: u32,
+    pub max_size_y: u32,
+    pub max_size_z: u32,
+    /// Maximum product of the 3 dimensions.
+    pub max_invocations: u32,
+}
+
+impl Default for WorkgroupLimits {
+    fn default() -> Self {
+        Self {
+            max_size_x: 256,
+            max_size_y: 256,
+            max_size_z: 64,
+            max_invocations: 256,
+        }
+    }
+}
+
+/// The `@workgroup_size` of a compute entry point, missing dimensions are 1. `None` for
+/// dimensions that are not const-evaluable, e.g. overrides.
+fn workgroup_size(
+    attr: &WorkgroupSizeAttribute,
+    #[cfg(feature = "eval")] ctx: &mut Option<crate::eval::Context>,
+) -> [Option<u32>; 3] {
+    let dims = [Some(&attr.x), attr.y.as_ref(), attr.z.as_ref()];
+    dims.map(|dim| match dim {
+        None => Some(1),
+        Some(expr) => {
+            #[cfg(feature = "eval")]
+            if let Some(ctx) = ctx {
+                use crate::eval::{Eval, Instance, LiteralInstance};
+                return match expr.eval_value(ctx).ok()? {
+                    Instance::Literal(LiteralInstance::AbstractInt(n)) => u32::try_from(n).ok(),
+                    Instance::Literal(LiteralInstance::I32(n)) => u32::try_from(n).ok(),
+                    Instance::Literal(LiteralInstance::U32(n)) => Some(n),
+                    _ => None,
+                };
+            }
+            crate::bindings::literal_u32(expr)
+        }
+    })
+}
+
+/// Validate that the `@workgroup_size` of compute entry points is within `limits`.
+///
+/// Sizes are const-evaluated with the `eval` feature, otherwise only literal sizes are
+/// checked. Sizes that depend on overrides are not checked. See
+/// [`crate::CompileOptions::workgroup_limits`].
+pub fn validate_workgroup_size(
+    wgsl: &TranslationUnit,
+    limits: &WorkgroupLimits,
+) -> Result<(), Diagnostic<Error>> {
+    #[cfg(feature = "eval")]
+    let mut ctx = {
+        use crate::eval::{Context, Exec};
+        let mut ctx = Context::new(wgsl);
+        wgsl.exec(&mut ctx).ok().map(|_| ctx)
+    };
+    for decl in &wgsl.global_declarations {
+        let GlobalDeclaration::Function(decl) = decl else {
+            continue;
+        };
+        let Some(attr) = decl.attributes.iter().find_map(|attr| match attr {
+            Attribute::WorkgroupSize(attr) => Some(attr),
+            _ => None,
+        }) else {
+            continue;
+        };
+        let size = workgroup_size(
+            attr,
+            #[cfg(feature = "eval")]
+            &mut ctx,
+        );
+        let maxima = [limits.max_size_x, limits.max_size_y, limits.max_size_z];
+        for ((dim, size), max) in ['x', 'y', 'z'].into_iter().zip(size).zip(maxima) {
+            if let Some(size) = size.filter(|size| *size > max) {
+                let err = E::WorkgroupSize(decl.ident.to_string(), dim, size, max);
+                return Err(Diagnostic::from(err).with_declaration(decl.ident.to_string()));
+            }
+        }
+        if let [Some(x), Some(y), Some(z)] = size {
+            let invocations = x as u64 * y as u64 * z as u64;
+            if invocations > limits.max_invocations as u64 {
+                let err = E::WorkgroupInvocations(
+                    decl.ident.to_string(),
+                    invocations,
+                    limits.max_invocations,
+                );
+                return Err(Diagnostic::from(err).with_declaration(decl.ident.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validate that all functions marked `@const` are const-evaluable.
+///
+/// This is best run on the final output, when all imported declarations are available.
+/// It is part of [`validate_wgsl`].
+#[cfg(feature = "eval")]
+pub fn validate_const_functions(wgsl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    check_const_functions(wgsl)
+}
+
+/// Validate that every struct has a well-defined memory layout: each member has a size
+/// and an alignment per the WGSL rules, and only the last member is a runtime-sized
+/// array. See [`crate::CompileOptions::validate_layouts`].
+///
+/// Sizes are computed like [`crate::eval`] does, `@size` and `@align` attributes
+/// included. Use-case: host-side code that mirrors the structs in buffers.
+#[cfg(feature = "eval")]
+pub fn validate_struct_layouts(wgsl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    use crate::eval::{ty_eval_ty, Context, EvalAttrs, Type};
+
+    let mut ctx = Context::new(wgsl);
+    for decl in &wgsl.global_declarations {
+        let GlobalDeclaration::Struct(s) = decl else {
+            continue;
+        };
+        let err = |err: E| Diagnostic::from(err).with_declaration(s.ident.to_string());
+        for (i, m) in s.members.iter().enumerate() {
+            let no_layout = || err(E::NoLayout(s.ident.to_string(), m.ident.to_string()));
+            let ty = ty_eval_ty(&m.ty, &mut ctx).map_err(|_| no_layout())?;
+            let is_last = i + 1 == s.members.len();
+            let layout = match &ty {
+                Type::Array(_, None) if !is_last => {
+                    return Err(err(E::RuntimeArrayNotLast(
+                        s.ident.to_string(),
+                        m.ident.to_string(),
+                    )));
+                }
+                Type::Array(_, None) => ty.min_size_of(&mut ctx).zip(ty.align_of(&mut ctx)),
+                _ => {
+                    let size = m.attr_size(&mut ctx).ok().flatten();
+                    let align = m.attr_align(&mut ctx).ok().flatten();
+                    size.or_else(|| ty.size_of(&mut ctx))
+                        .zip(align.or_else(|| ty.align_of(&mut ctx)))
+                }
+            };
+            if layout.is_none() {
+                return Err(no_layout());
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Validate an intermediate WESL module.
 ///
 /// This function only checks that a WESL module is valid on its own, without looking at
@@ -263,11 +713,33 @@ fn check_reserved_words(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>>
 /// * Defined declarations: all identifiers refer to a user declaration, import or
 ///   built-in name.
 /// * Duplicate declarations: declarations in the same scope cannot have the same name.
+///   Function parameters are in the scope of the function body.
 /// * Cyclic declarations: no cycles are allowed in declarations.
 pub fn validate_wesl(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    validate_wesl_with_builtins(wesl, &HashSet::new())
+}
+
+/// Like [`validate_wesl`], but `builtins` are additional names that are treated as
+/// built-in declarations, e.g. builtins injected by a preprocessor or a custom WGSL
+/// implementation.
+pub fn validate_wesl_with_builtins(
+    wesl: &TranslationUnit,
+    builtins: &HashSet<String>,
+) -> Result<(), Diagnostic<Error>> {
+    validate_module(wesl, builtins, &HashSet::new())
+}
+
+/// Like [`validate_wesl_with_builtins`], for a module whose references to imported
+/// declarations are linked to the `external` identifiers of the other modules.
+pub(crate) fn validate_module(
+    wesl: &TranslationUnit,
+    builtins: &HashSet<String>,
+    external: &HashSet<Ident>,
+) -> Result<(), Diagnostic<Error>> {
     check_reserved_words(wesl)?;
-    check_defined_symbols(wesl)?;
+    check_defined_symbols(wesl, builtins, external)?;
     check_duplicate_decl(wesl)?;
+    check_local_decls(wesl)?;
     check_cycles(wesl)?;
     Ok(())
 }
@@ -278,14 +750,336 @@ pub fn validate_wesl(wesl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
 /// * Reserved words: no declaration identifier is a reserved name.
 /// * Defined declarations: all identifiers refer to a user declaration or built-in name.
 /// * Duplicate declarations: declarations in the same scope cannot have the same name.
+///   Function parameters are in the scope of the function body.
 /// * Cyclic declarations: no cycles are allowed in declarations.
 /// * Function calls: call expressions must refer to a function or a type constructor.
 ///   Check the number of arguments but not their type.
+/// * Array lengths: array element counts do not refer to overrides, except in
+///   `var<workgroup>` declarations.
+/// * Const functions: functions marked `@const` are const-evaluable (requires the `eval`
+///   feature).
+/// * Const assertions: `const_assert`s that can be evaluated are true (requires the `eval`
+///   feature).
 pub fn validate_wgsl(wgsl: &TranslationUnit) -> Result<(), Diagnostic<Error>> {
+    validate_wgsl_with_builtins(wgsl, &HashSet::new())
+}
+
+/// Like [`validate_wgsl`], but `builtins` are additional names that are treated as
+/// built-in declarations. See [`validate_wesl_with_builtins`].
+pub fn validate_wgsl_with_builtins(
+    wgsl: &TranslationUnit,
+    builtins: &HashSet<String>,
+) -> Result<(), Diagnostic<Error>> {
     check_reserved_words(wgsl)?;
-    check_defined_symbols(wgsl)?;
+    check_defined_symbols(wgsl, builtins, &HashSet::new())?;
     check_duplicate_decl(wgsl)?;
+    check_local_decls(wgsl)?;
     check_cycles(wgsl)?;
-    check_function_calls(wgsl)?;
+    check_function_calls(wgsl, builtins)?;
+    check_array_lengths(wgsl)?;
+    #[cfg(feature = "eval")]
+    check_const_functions(wgsl)?;
+    #[cfg(feature = "eval")]
+    check_const_asserts(wgsl)?;
+    Ok(())
+}
+
+/// The names in scope of a declaration validated with [`validate_declaration`].
+#[derive(Clone, Debug, Default)]
+pub struct ValidationContext {
+    /// Names of the other global declarations of the module.
+    pub globals: HashSet<String>,
+    /// Names introduced by the imports of the module, after renaming.
+    pub imports: HashSet<String>,
+    /// Additional built-in names. See [`validate_wesl_with_builtins`].
+    pub builtins: HashSet<String>,
+}
+
+impl ValidationContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The context of the declarations of a module: its global declarations and imports.
+    pub fn from_unit(wesl: &TranslationUnit) -> Self {
+        Self {
+            globals: wesl
+                .global_declarations
+                .iter()
+                .filter_map(|decl| decl.ident().map(Ident::to_string))
+                .collect(),
+            imports: flatten_imports(&wesl.imports)
+                .map(|id| id.to_string())
+                .collect(),
+            builtins: HashSet::new(),
+        }
+    }
+}
+
+/// Validate a single declaration, e.g. the function edited in an editor, without
+/// re-validating the whole module.
+///
+/// References to names that are not declared in `decl` must be provided by `context`.
+/// The declaration can be part of a parsed module, its identifiers are not required to
+/// be linked. It runs the checks of [`validate_wesl`] that apply to one declaration:
+/// reserved words, defined symbols, duplicate locals and cycles.
+pub fn validate_declaration(
+    decl: &GlobalDeclaration,
+    context: &ValidationContext,
+) -> Result<(), Diagnostic<Error>> {
+    // references get fresh identifiers: they may be linked to declarations of the module
+    // that are not part of the context.
+    let mut decl = decl.clone();
+    for_each_type_mut(&mut decl, &mut |ty| {
+        ty.ident = Ident::new(ty.ident.to_string())
+    });
+    let mut wesl = TranslationUnit {
+        global_declarations: vec![decl],
+        ..Default::default()
+    };
+    wesl.retarget_idents();
+
+    let names = context
+        .globals
+        .iter()
+        .chain(&context.imports)
+        .chain(&context.builtins)
+        .cloned()
+        .collect();
+    check_reserved_words(&wesl)?;
+    check_defined_symbols(&wesl, &names, &HashSet::new())?;
+    check_local_decls(&wesl)?;
+    check_cycles(&wesl)?;
     Ok(())
 }
+
+#[test]
+fn test_extra_builtins() {
+    use crate::SyntaxUtil;
+    let mut wgsl: TranslationUnit = "fn f() -> f32 { let x = HOST_BIAS; return host_scale(x); }"
+        .parse()
+        .unwrap();
+    wgsl.retarget_idents();
+    let err = validate_wgsl(&wgsl).unwrap_err();
+    assert!(matches!(
+        &*err.error,
+        Error::ValidateError(E::UndefinedSymbol(_))
+    ));
+
+    let builtins = HashSet::from_iter(["host_scale".to_string(), "HOST_BIAS".to_string()]);
+    validate_wgsl_with_builtins(&wgsl, &builtins).unwrap();
+}
+
+#[cfg(feature = "eval")]
+#[test]
+fn test_const_functions() {
+    let valid: TranslationUnit = "@const fn sq(x: f32) -> f32 { let y = x * x; return y; }"
+        .parse()
+        .unwrap();
+    assert!(validate_const_functions(&valid).is_ok());
+
+    let invalid: TranslationUnit = "@const fn bad(p: ptr<function, f32>) -> f32 { return *p; }"
+        .parse()
+        .unwrap();
+    let err = validate_const_functions(&invalid).unwrap_err();
+    assert!(matches!(&*err.error, Error::ValidateError(E::NotConst(name)) if name == "bad"));
+
+    let invalid: TranslationUnit = "var<private> counter: u32;
+        fn bump() -> u32 { counter += 1u; return counter; }
+        @const fn also_bad() -> u32 { return bump(); }"
+        .parse()
+        .unwrap();
+    let err = validate_const_functions(&invalid).unwrap_err();
+    assert!(err.span.is_some());
+}
+
+#[test]
+fn test_override_array_length() {
+    use crate::{CompileOptions, EscapeMangler, ModulePath, VirtualResolver};
+    let compile = |main: &str| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", main.into());
+        resolver.add_module(
+            "sizes",
+            "override count: u32 = 64; const fixed = 16u;".into(),
+        );
+        let options = CompileOptions {
+            validate: true,
+            ..Default::default()
+        };
+        let root = ModulePath::from_path("/main");
+        crate::compile(&root, &resolver, &EscapeMangler, &options)
+    };
+
+    compile(
+        "import package::sizes::{count, fixed};
+        var<workgroup> tile: array<f32, count * 2>;
+        var<private> local: array<f32, fixed>;
+        @compute @workgroup_size(1) fn main() { tile[0] = local[0]; }",
+    )
+    .unwrap();
+
+    let err = compile(
+        "import package::sizes::count;
+        var<private> data: array<f32, count>;
+        @compute @workgroup_size(1) fn main() { data[0] = 1.0; }",
+    )
+    .unwrap_err();
+    let crate::Error::ValidateError(E::OverrideArrayLength(ty, name)) = &*err.error else {
+        panic!("unexpected error: {err}")
+    };
+    assert_eq!(ty, "array<f32, count>");
+    assert_eq!(name, "count");
+    assert_eq!(err.declaration.as_deref(), Some("data"));
+    assert!(err.span.is_some());
+}
+
+#[test]
+fn test_local_redeclaration() {
+    use crate::SyntaxUtil;
+    let validate = |source: &str| {
+        let mut wgsl: TranslationUnit = source.parse().unwrap();
+        wgsl.retarget_idents();
+        validate_wgsl(&wgsl)
+    };
+
+    // shadowing in nested blocks is allowed.
+    validate(
+        "fn f(x: f32) -> f32 {
+            let y = x;
+            {
+                let x = 2.0;
+                let y = x;
+            }
+            for (var i = 0; i < 2; i++) { let i = 1; }
+            if true { let y = 1.0; } else { let y = 2.0; }
+            return y;
+        }",
+    )
+    .unwrap();
+
+    let err = validate("fn f(x: f32) -> f32 { let x = 2.0; return x; }").unwrap_err();
+    assert!(matches!(&*err.error, Error::ValidateError(E::Duplicate(name)) if name == "x"));
+    assert_eq!(err.declaration.as_deref(), Some("f"));
+    assert!(err.span.is_some());
+
+    let err = validate("fn f() { if true { var a = 1; let a = 2; } }").unwrap_err();
+    assert!(matches!(&*err.error, Error::ValidateError(E::Duplicate(name)) if name == "a"));
+}
+
+#[test]
+fn test_workgroup_size() {
+    let validate = |source: &str| {
+        let wgsl: TranslationUnit = source.parse().unwrap();
+        validate_workgroup_size(&wgsl, &WorkgroupLimits::default())
+    };
+
+    validate("@compute @workgroup_size(64, 2, 2) fn main() {}").unwrap();
+    validate("@compute @workgroup_size(256) fn main() {}").unwrap();
+
+    let err = validate("@compute @workgroup_size(8, 8, 128) fn main() {}").unwrap_err();
+    assert!(matches!(
+        &*err.error,
+        Error::ValidateError(E::WorkgroupSize(name, 'z', 128, 64)) if name == "main"
+    ));
+    assert_eq!(err.declaration.as_deref(), Some("main"));
+
+    let err = validate("@compute @workgroup_size(16, 32) fn main() {}").unwrap_err();
+    assert!(matches!(
+        &*err.error,
+        Error::ValidateError(E::WorkgroupInvocations(_, 512, 256))
+    ));
+
+    // sizes that depend on overrides are only known at pipeline creation.
+    validate("override n = 512; @compute @workgroup_size(n) fn main() {}").unwrap();
+    #[cfg(feature = "eval")]
+    {
+        let err = validate("const n = 512; @compute @workgroup_size(n / 2 + 1) fn main() {}")
+            .unwrap_err();
+        assert!(matches!(
+            &*err.error,
+            Error::ValidateError(E::WorkgroupSize(_, 'x', 257, 256))
+        ));
+    }
+}
+
+#[test]
+fn test_validate_declaration() {
+    let mut wesl: TranslationUnit = "import package::util::{scale, Light as L};
+        const BIAS = 1.0;
+        fn shade(light: L) -> f32 { return scale(light.power) + BIAS; }
+        fn broken() -> f32 { return missing(1.0); }"
+        .parse()
+        .unwrap();
+    wesl.retarget_idents();
+    let context = ValidationContext::from_unit(&wesl);
+    assert!(context.imports.contains("L") && context.globals.contains("BIAS"));
+    validate_declaration(&wesl.global_declarations[1], &context).unwrap();
+
+    let err = validate_declaration(&wesl.global_declarations[2], &context).unwrap_err();
+    assert!(matches!(
+        &*err.error,
+        Error::ValidateError(E::UndefinedSymbol(name)) if name == "missing"
+    ));
+    assert_eq!(err.declaration.as_deref(), Some("broken"));
+
+    // the context is all that is known, the other declarations are not in scope.
+    let err =
+        validate_declaration(&wesl.global_declarations[1], &ValidationContext::new()).unwrap_err();
+    assert!(matches!(
+        &*err.error,
+        Error::ValidateError(E::UndefinedSymbol(_))
+    ));
+}
+
+#[cfg(feature = "eval")]
+#[test]
+fn test_const_asserts() {
+    use crate::eval::EvalError;
+    use crate::SyntaxUtil;
+    let validate = |source: &str| {
+        let mut wgsl: TranslationUnit = source.parse().unwrap();
+        wgsl.retarget_idents();
+        validate_wgsl(&wgsl)
+    };
+    let err = validate("const N = 4u; const_assert N * 2u == 6u;").unwrap_err();
+    assert!(matches!(
+        &*err.error,
+        Error::EvalError(EvalError::ConstAssertFailure(expr)) if expr.to_string() == "N * 2u == 6u"
+    ));
+    assert!(err.span.is_some());
+
+    let err = validate("const N = 4u; fn f() { if true { const_assert N < 2u; } }").unwrap_err();
+    assert_eq!(err.declaration.as_deref(), Some("f"));
+
+    // true assertions, and those that cannot be evaluated here, pass.
+    validate("const N = 4u; const_assert N == 4u;").unwrap();
+    validate("const_assert M == 8u; const M = N * 2u; const N = 4u;").unwrap();
+    validate("const_assert M == 9u; const M = N * 2u; const N = 4u;").unwrap_err();
+    validate("const N = 4u; fn f() { const N = 1u; const_assert N == 1u; }").unwrap();
+    validate("override O = 1u; fn f() { const_assert O == 2u; }").unwrap();
+}
+
+#[cfg(feature = "eval")]
+#[test]
+fn test_validate_struct_layouts() {
+    let layout_err = |source: &str| {
+        let wgsl = source.parse::<TranslationUnit>().unwrap();
+        validate_struct_layouts(&wgsl).map_err(|e| match *e.error {
+            Error::ValidateError(e) => e,
+            e => panic!("unexpected error: {e}"),
+        })
+    };
+
+    layout_err(
+        "struct Light { color: vec3f, intensity: f32, @size(16) dir: vec3f }
+        struct Lights { count: u32, lights: array<Light> }",
+    )
+    .unwrap();
+
+    let err = layout_err("struct Bad { a: f32, rest: array<f32>, b: u32 }").unwrap_err();
+    assert!(matches!(err, E::RuntimeArrayNotLast(s, m) if s == "Bad" && m == "rest"));
+
+    let err = layout_err("struct Bad { t: texture_2d<f32> }").unwrap_err();
+    assert!(matches!(err, E::NoLayout(s, m) if s == "Bad" && m == "t"));
+}
@@ -0,0 +1,403 @@
+use std::{collections::HashSet, ops::Range};
+
+use wgsl_parse::{
+    syntax::{
+        Attribute, Attributes, GlobalDeclaration, ModulePath, Statement, StatementNode,
+        TranslationUnit, TypeExpression,
+    },
+    SyntaxSpans,
+};
+
+use crate::{
+    import::{flatten_imports, nested_blocks, resolve_inline_path},
+    visit::Visit,
+    ImportError, MangleMap,
+};
+
+/// Whether the root module has `@if`, `@elif` or `@else` attributes.
+fn has_conditions(wesl: &TranslationUnit) -> bool {
+    Visit::<Attributes>::visit(wesl).any(|attrs| {
+        attrs.iter().any(|attr| {
+            matches!(
+                attr,
+                Attribute::If(_) | Attribute::Elif(_) | Attribute::Else
+            )
+        })
+    })
+}
+
+/// Whether the root module has import statements in function bodies or blocks.
+fn has_block_imports(wesl: &TranslationUnit) -> bool {
+    fn rec(stmt: &StatementNode) -> bool {
+        nested_blocks(stmt.node()).any(|block| !block.imports.is_empty())
+            || Visit::<StatementNode>::visit(stmt.node()).any(rec)
+    }
+
+    wesl.global_declarations.iter().any(|decl| match decl {
+        GlobalDeclaration::Function(f) => !f.body.imports.is_empty(),
+        _ => false,
+    }) || Visit::<StatementNode>::visit(wesl).any(rec)
+}
+
+/// All type expressions of the unit, including those in template arguments.
+fn type_expressions(wesl: &TranslationUnit) -> Vec<&TypeExpression> {
+    fn rec<'a>(ty: &'a TypeExpression, res: &mut Vec<&'a TypeExpression>) {
+        res.push(ty);
+        for arg in ty.template_args.iter().flatten() {
+            for ty in Visit::<TypeExpression>::visit(arg.expression.node()) {
+                rec(ty, res);
+            }
+        }
+    }
+
+    let mut res = Vec::new();
+    for ty in Visit::<TypeExpression>::visit(wesl) {
+        rec(ty, &mut res);
+    }
+    res
+}
+
+/// Names declared in functions: parameters and local declarations.
+fn local_names(wesl: &TranslationUnit) -> HashSet<String> {
+    fn rec(stmt: &StatementNode, res: &mut HashSet<String>) {
+        if let Statement::Declaration(decl) = stmt.node() {
+            res.insert(decl.ident.to_string());
+        }
+        for stmt in Visit::<StatementNode>::visit(stmt.node()) {
+            rec(stmt, res);
+        }
+    }
+
+    let mut res = HashSet::new();
+    for decl in &wesl.global_declarations {
+        if let GlobalDeclaration::Function(f) = decl {
+            res.extend(f.parameters.iter().map(|p| p.ident.to_string()));
+        }
+    }
+    for stmt in Visit::<StatementNode>::visit(wesl) {
+        rec(stmt, &mut res);
+    }
+    res
+}
+
+/// The root module source, with its import statements removed and its references to
+/// imported declarations replaced with their mangled names, followed by the imported
+/// declarations of `wgsl`. See [`crate::CompileOptions::preserve_entry_verbatim`].
+///
+/// * `root` is the root module parsed with [`wgsl_parse::parse_str_with_spans`], `wgsl`
+///   the compiled output.
+/// * The root module cannot have `@if`, `@elif` or `@else` attributes, nor import
+///   statements in function bodies or blocks, and its declarations cannot be mangled.
+/// * Imported declarations are recognized by their name: they are the declarations of
+///   `wgsl` not declared in `root`. Module-scope `const_assert`s of imported modules are
+///   not emitted, they were checked during compilation.
+pub(crate) fn splice_entry(
+    source: &str,
+    spans: &SyntaxSpans,
+    root_path: &ModulePath,
+    root: &TranslationUnit,
+    wgsl: &TranslationUnit,
+    mangle_map: &MangleMap,
+) -> Result<String, ImportError> {
+    if has_conditions(root) {
+        return Err(ImportError::VerbatimUnsupported("conditional attributes"));
+    }
+    if has_block_imports(root) {
+        return Err(ImportError::VerbatimUnsupported("imports in blocks"));
+    }
+    let root_names = root
+        .global_declarations
+        .iter()
+        .filter_map(|decl| decl.ident())
+        .map(|id| id.to_string())
+        .collect::<HashSet<_>>();
+    // the root source is kept as-is, so its declarations must keep their names.
+    if root_names
+        .iter()
+        .any(|name| mangle_map.get(root_path, name).is_some_and(|m| m != name))
+    {
+        return Err(ImportError::VerbatimUnsupported("mangled declarations"));
+    }
+    let imports = flatten_imports(&root.imports, root_path)?;
+    let locals = local_names(root);
+    let mangled = |path: &ModulePath, item: &str| mangle_map.get(path, item).map(str::to_string);
+    let lookup = |name: &str| {
+        imports.iter().find_map(|(local, (path, item))| {
+            (*local.name() == name)
+                .then(|| mangled(path, &item.name()))
+                .flatten()
+        })
+    };
+    if let Some(name) = imports
+        .keys()
+        .map(|id| id.to_string())
+        .find(|name| locals.contains(name) && lookup(name).is_some())
+    {
+        return Err(ImportError::ShadowedImport(name));
+    }
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for span in &spans.imports {
+        // also remove the rest of the line, if it is blank.
+        let mut end = span.end;
+        let rest = &source[end..];
+        let blank = rest.len() - rest.trim_start_matches([' ', '\t', '\r']).len();
+        if rest[blank..].starts_with('\n') {
+            end += blank + 1;
+        }
+        edits.push((span.start..end, String::new()));
+    }
+    for ty in type_expressions(root) {
+        let Some(span) = spans.types.get(&ty.ident) else {
+            continue;
+        };
+        let replacement = match &ty.path {
+            Some(path) => {
+                let path = resolve_inline_path(path, root_path, &imports);
+                mangled(&path, &ty.ident.name())
+            }
+            None => lookup(&ty.ident.name()),
+        };
+        if let Some(name) = replacement {
+            edits.push((span.range(), name));
+        }
+    }
+    edits.sort_by_key(|(range, _)| range.start);
+
+    let mut body = String::with_capacity(source.len());
+    let mut pos = 0;
+    for (range, name) in edits {
+        body.push_str(&source[pos..range.start]);
+        body.push_str(&name);
+        pos = range.end;
+    }
+    body.push_str(&source[pos..]);
+
+    let mut res = String::new();
+    let root_directives = root
+        .global_directives
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<HashSet<_>>();
+    for directive in &wgsl.global_directives {
+        let directive = directive.to_string();
+        if !root_directives.contains(&directive) {
+            res.push_str(&directive);
+            res.push('\n');
+        }
+    }
+    res.push_str(&body);
+
+    for decl in &wgsl.global_declarations {
+        let Some(ident) = decl.ident() else {
+            continue;
+        };
+        if !root_names.contains(&*ident.name()) {
+            if !res.ends_with('\n') {
+                res.push('\n');
+            }
+            res.push('\n');
+            res.push_str(&decl.to_string());
+            res.push('\n');
+        }
+    }
+    Ok(res)
+}
+
+#[test]
+fn test_preserve_entry_verbatim() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{scale, Light};
+
+// the formatting of the root module is kept.
+fn shade(  light : Light ) -> f32 {
+    return light.color.x;   /* scale: not renamed */
+}
+
+@fragment
+fn main() -> @location(0) vec4f {
+    let s = scale( shade(Light(vec3f(1.0))) );
+    return vec4f(package::util::tint * s, 1.0);
+}
+"
+        .into(),
+    );
+    resolver.add_module(
+        "util",
+        "struct Light { color: vec3f }
+        fn scale(x: f32) -> f32 { return x * 2.0; }
+        const tint = vec3f(0.5);"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.preserve_entry_verbatim(true);
+    let res = compiler.compile("main").unwrap();
+    let expected = "
+// the formatting of the root module is kept.
+fn shade(  light : package_util_Light ) -> f32 {
+    return light.color.x;   /* scale: not renamed */
+}
+
+@fragment
+fn main() -> @location(0) vec4f {
+    let s = package_util_scale( shade(package_util_Light(vec3f(1.0))) );
+    return vec4f(package_util_tint * s, 1.0);
+}
+
+struct package_util_Light {
+    color: vec3f
+}
+
+fn package_util_scale(x: f32) -> f32 {
+    return x * 2.0;
+}
+
+const package_util_tint = vec3f(0.5);
+";
+    let output = res.to_string();
+    assert_eq!(output, expected);
+    output.parse::<TranslationUnit>().unwrap();
+}
+
+#[test]
+fn test_preserve_entry_verbatim_unsupported() {
+    use crate::{Error, VirtualResolver, Wesl};
+    let compile = |main: &str| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", main.into());
+        resolver.add_module("util", "fn scale(x: f32) -> f32 { return x; }".into());
+        let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+        compiler.preserve_entry_verbatim(true);
+        compiler
+            .compile("main")
+            .err()
+            .expect("compilation should fail")
+    };
+    let err = compile(
+        "import package::util::scale;
+        @if(false) const x = 1.0;
+        fn main() -> f32 { return scale(2.0); }",
+    );
+    assert!(
+        matches!(
+            err,
+            Error::ImportError(ImportError::VerbatimUnsupported("conditional attributes"))
+        ),
+        "{err}"
+    );
+    let err = compile(
+        "fn main() -> f32 {
+            import package::util::scale;
+            return scale(2.0);
+        }",
+    );
+    assert!(
+        matches!(
+            err,
+            Error::ImportError(ImportError::VerbatimUnsupported("imports in blocks"))
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_preserve_entry_verbatim_mangled() {
+    use crate::{Error, VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::scale;
+        fn helper() -> f32 { return 2.0; }
+        fn main() -> f32 { return scale(helper()); }"
+            .into(),
+    );
+    resolver.add_module("util", "fn scale(x: f32) -> f32 { return x; }".into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler
+        .preserve_entry_verbatim(true)
+        .mangle_entry_locals(true);
+    let err = compiler
+        .compile("main")
+        .err()
+        .expect("compilation should fail");
+    assert!(
+        matches!(
+            err,
+            Error::ImportError(ImportError::VerbatimUnsupported("mangled declarations"))
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_preserve_entry_verbatim_struct_member() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::scale;
+struct S { scale: f32 }
+@fragment
+fn main() -> @location(0) vec4f { return vec4f(scale(S(1.0).scale)); }
+"
+        .into(),
+    );
+    resolver.add_module("util", "fn scale(x: f32) -> f32 { return x; }".into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.preserve_entry_verbatim(true);
+    let output = compiler.compile("main").unwrap().to_string();
+    assert!(
+        output.contains("return vec4f(package_util_scale(S(1.0).scale));"),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_preserve_entry_verbatim_positions() {
+    use crate::{VirtualResolver, Wesl};
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module(
+        "main",
+        "import package::util::{N, T, f};
+alias A = array<T, N>;
+struct S { a: A, t: T }
+const_assert N > 1;
+var<workgroup> w: array<u32, N>;
+@compute @workgroup_size(N)
+fn main() {
+    var s: S;
+    let t: T = s.t;
+    f();
+    w[0] = u32(N);
+}
+"
+        .into(),
+    );
+    resolver.add_module(
+        "util",
+        "const N = 4u;
+        alias T = f32;
+        fn f() {}"
+            .into(),
+    );
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.preserve_entry_verbatim(true);
+    let output = compiler.compile("main").unwrap().to_string();
+    let body = "alias A = array<package_util_T, package_util_N>;
+struct S { a: A, t: package_util_T }
+const_assert package_util_N > 1;
+var<workgroup> w: array<u32, package_util_N>;
+@compute @workgroup_size(package_util_N)
+fn main() {
+    var s: S;
+    let t: package_util_T = s.t;
+    package_util_f();
+    w[0] = u32(package_util_N);
+}
+";
+    assert!(output.starts_with(body), "{output}");
+    output.parse::<TranslationUnit>().unwrap();
+}
@@ -56,18 +56,12 @@ macro_rules! impl_visit {
 
 impl_visit! { Expression => ExpressionNode,
     {
-        Expression::Parenthesized.expression.(x => recurse(x)),
-        Expression::NamedComponent.base.(x => recurse(x)),
-        Expression::Indexing.{
-            base.(x => recurse(x)),
-            index.(x => recurse(x)),
-        },
-        Expression::Unary.operand.(x => recurse(x)),
-        Expression::Binary.{
-            left.(x => recurse(x)),
-            right.(x => recurse(x)),
-        },
-        Expression::FunctionCall.arguments.[].(x => recurse(x)),
+        Expression::Parenthesized.expression,
+        Expression::NamedComponent.base,
+        Expression::Indexing.{ base, index },
+        Expression::Unary.operand,
+        Expression::Binary.{ left, right },
+        Expression::FunctionCall.arguments.[],
     }
 }
 
@@ -97,8 +91,15 @@ impl_visit! { Statement => Attributes,
         Statement::Compound.{ attributes, statements.[].(x => recurse(x)) },
         Statement::If.{
             attributes,
-            else_if_clauses.[].body.statements.[].(x => recurse(x)),
-            else_clause.[].body.statements.[].(x => recurse(x)),
+            if_clause.body.statements.[].(x => recurse(x)),
+            else_if_clauses.[].{
+                attributes,
+                body.statements.[].(x => recurse(x)),
+            },
+            else_clause.[].{
+                attributes,
+                body.statements.[].(x => recurse(x)),
+            },
         },
         Statement::Switch.{
             attributes,
@@ -273,7 +274,8 @@ impl_visit! { Statement => ExpressionNode,
             else_if_clauses.[].{
                 expression,
                 body.statements.[].(x => recurse(x)),
-            }
+            },
+            else_clause.[].body.statements.[].(x => recurse(x)),
         },
         Statement::Switch.{
             expression,
@@ -312,6 +314,7 @@ impl_visit! { Statement => StatementNode,
         Statement::If.{
             if_clause.body.statements.[],
             else_if_clauses.[].body.statements.[],
+            else_clause.[].body.statements.[],
         },
         Statement::Switch.clauses.[].body.statements.[],
         Statement::Loop.{
@@ -388,6 +391,7 @@ impl_visit! { TranslationUnit => Attributes,
             GlobalDeclaration::Function.{
                 attributes,
                 parameters.[].attributes,
+                return_attributes,
                 body.{ attributes, statements.[].(x => visit::<Statement, Attributes>(x)) }
             },
             GlobalDeclaration::ConstAssert.attributes,
@@ -454,3 +458,78 @@ impl_visit! { ConstAssert => TypeExpression,
         expression.(x => visit::<Expression, TypeExpression>(x)),
     }
 }
+
+#[test]
+fn test_visit_else_clause() {
+    let wesl = wgsl_parse::parse_str(
+        "fn f() { if true { let a = 1; } else if false { let b = 2; } else { let c = 3; } }",
+    )
+    .unwrap();
+    let stmt = Visit::<StatementNode>::visit(&wesl).next().unwrap();
+    let names = Visit::<StatementNode>::visit(stmt.node())
+        .filter_map(|stmt| match stmt.node() {
+            Statement::Declaration(decl) => Some(decl.ident.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b", "c"]);
+    let exprs = Visit::<ExpressionNode>::visit(stmt.node())
+        .map(|expr| expr.to_string())
+        .collect::<Vec<_>>();
+    assert!(exprs.contains(&"3".to_string()));
+}
+
+#[test]
+fn test_visit_return_attributes() {
+    let wesl = wgsl_parse::parse_str(
+        "@fragment fn f(@location(1) x: f32) -> @location(0) vec4f { return vec4f(x); }",
+    )
+    .unwrap();
+    let locations = Visit::<Attributes>::visit(&wesl)
+        .flatten()
+        .filter_map(|attr| match attr {
+            Attribute::Location(expr) => Some(expr.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(locations, ["1", "0"]);
+}
+
+#[test]
+fn test_visit_sub_expressions() {
+    let expr = "f(a + b * c, -(d))".parse::<Expression>().unwrap();
+    let to_strings = |expr: &Expression| {
+        Visit::<ExpressionNode>::visit(expr)
+            .map(|expr| expr.to_string())
+            .collect::<Vec<_>>()
+    };
+    // only the direct sub-expressions are visited, callers recurse themselves.
+    assert_eq!(to_strings(&expr), ["a + b * c", "-(d)"]);
+    let Expression::FunctionCall(call) = &expr else {
+        panic!("expected a function call")
+    };
+    assert_eq!(to_strings(&call.arguments[0]), ["a", "b * c"]);
+    assert_eq!(to_strings(&call.arguments[1]), ["(d)"]);
+}
+
+#[test]
+fn test_visit_if_attributes() {
+    let wesl = wgsl_parse::parse_str(
+        "fn f() {
+            if true { @if(a) let a = 1; }
+            else if false { @if(b) let b = 2; }
+            else { @if(c) let c = 3; }
+        }",
+    )
+    .unwrap();
+    let count = Visit::<Attributes>::visit(&wesl)
+        .flatten()
+        .filter(|attr| {
+            matches!(
+                attr,
+                Attribute::If(_) | Attribute::Elif(_) | Attribute::Else
+            )
+        })
+        .count();
+    assert_eq!(count, 3);
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use wgsl_parse::syntax::ModulePath;
+
+use crate::{Diagnostic, Error, Feature};
+
+/// WESL compilation warning.
+///
+/// Warnings do not abort compilation, unless they are escalated to errors with
+/// [`crate::CompileOptions::deny_warnings`].
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Warning {
+    /// Only emitted with [`crate::CompileOptions::warn_unused_imports`].
+    #[error("unused import `{0}`")]
+    UnusedImport(String),
+    #[error(
+        "use of deprecated `{name}`{}",
+        .message.as_ref().map(|m| format!(": {m}")).unwrap_or_default()
+    )]
+    Deprecated {
+        name: String,
+        message: Option<String>,
+    },
+    #[error("`{first}` and `{second}` are both mangled to `{name}`, one will shadow the other")]
+    MangleCollision {
+        name: String,
+        first: String,
+        second: String,
+    },
+    #[error(
+        "the `{}` feature is used but not declared, add `{} {};`",
+        .0, .0.directive(), .0
+    )]
+    MissingFeature(Feature),
+    /// Only emitted with [`crate::CompileOptions::warn_unreachable_code`].
+    #[error("unreachable statement, it comes after a `return`, `break`, `continue` or `discard`")]
+    UnreachableCode,
+}
+
+impl From<Warning> for Diagnostic<Warning> {
+    fn from(warning: Warning) -> Self {
+        Self::new(warning)
+    }
+}
+
+/// Displays a warning diagnostic. See [`Diagnostic::display_warning`].
+pub struct DisplayWarning<'a>(&'a Diagnostic<Warning>);
+
+impl std::fmt::Display for DisplayWarning<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.render(annotate_snippets::Level::Warning, f)
+    }
+}
+
+impl Diagnostic<Warning> {
+    /// Display the diagnostic with the `warning` level. The `Display` implementation of
+    /// [`Diagnostic`] always uses the `error` level.
+    pub fn display_warning(&self) -> DisplayWarning<'_> {
+        DisplayWarning(self)
+    }
+
+    /// Turn a warning into an error, keeping the diagnostic metadata.
+    pub fn into_error(self) -> Diagnostic<Error> {
+        Diagnostic {
+            error: Box::new(Error::Warning(*self.error)),
+            source: self.source,
+            output: self.output,
+            module_path: self.module_path,
+            display_name: self.display_name,
+            declaration: self.declaration,
+            span: self.span,
+        }
+    }
+}
+
+/// Which warnings are escalated to errors. Used in [`crate::CompileOptions::deny_warnings`].
+#[derive(Clone, Default)]
+pub enum DenyWarnings {
+    /// Warnings are never escalated. This is the default.
+    #[default]
+    None,
+    /// All warnings are escalated to errors.
+    All,
+    /// Warnings are escalated to errors only in modules selected by the predicate.
+    ///
+    /// Use-case: be strict with first-party modules, but not with vendored dependencies.
+    Modules(Arc<dyn Fn(&ModulePath) -> bool + Send + Sync>),
+}
+
+impl DenyWarnings {
+    /// Create a [`DenyWarnings::Modules`] from a predicate.
+    pub fn modules(pred: impl Fn(&ModulePath) -> bool + Send + Sync + 'static) -> Self {
+        Self::Modules(Arc::new(pred))
+    }
+
+    /// Whether warnings emitted in the module `path` are escalated to errors.
+    fn is_denied(&self, path: Option<&ModulePath>) -> bool {
+        match self {
+            DenyWarnings::None => false,
+            DenyWarnings::All => true,
+            DenyWarnings::Modules(pred) => path.is_some_and(|path| pred(path)),
+        }
+    }
+
+    /// Escalate the warning to an error if it is denied, otherwise push it to `warnings`.
+    pub(crate) fn emit(
+        &self,
+        warning: Diagnostic<Warning>,
+        warnings: &mut Vec<Diagnostic<Warning>>,
+    ) -> Result<(), Diagnostic<Error>> {
+        if self.is_denied(warning.module_path.as_ref()) {
+            Err(warning.into_error())
+        } else {
+            warnings.push(warning);
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Debug for DenyWarnings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::All => write!(f, "All"),
+            Self::Modules(_) => write!(f, "Modules(..)"),
+        }
+    }
+}
+
+#[test]
+fn test_deny_warnings_modules() {
+    use crate::{VirtualResolver, Wesl};
+    let compile = |main: &str, warn_unused_imports: bool| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module("main", main.to_string().into());
+        resolver.add_module(
+            "vendor/util",
+            "import package::vendor::other::stuff;
+            fn helper() {}
+            fn unused() {}"
+                .into(),
+        );
+        resolver.add_module("vendor/other", "fn stuff() {}".into());
+        let vendor = ModulePath::from_path("/vendor");
+        let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+        compiler
+            .warn_unused_imports(warn_unused_imports)
+            .deny_warnings(DenyWarnings::modules(move |path| {
+                !path.starts_with(&vendor)
+            }));
+        compiler.compile("main")
+    };
+
+    let main = "import package::vendor::util::{helper, unused};
+        @compute @workgroup_size(1) fn main() { helper(); }";
+    // the unused imports lint is opt-in.
+    assert!(compile(main, false).unwrap().warnings.is_empty());
+
+    // the unused import in the first-party module is escalated.
+    let Err(Error::Error(diagnostic)) = compile(main, true) else {
+        panic!("expected the first-party warning to be escalated")
+    };
+    assert!(
+        matches!(*diagnostic.error, Error::Warning(Warning::UnusedImport(ref id)) if id == "unused")
+    );
+
+    // the same warning in the vendored module is not.
+    let Ok(res) = compile(
+        "import package::vendor::util::helper;
+        @compute @workgroup_size(1) fn main() { helper(); }",
+        true,
+    ) else {
+        panic!("expected the vendored warning not to be escalated")
+    };
+    assert_eq!(res.warnings.len(), 1);
+    assert_eq!(
+        res.warnings[0].module_path,
+        Some(ModulePath::from_path("/vendor/util"))
+    );
+}
+
+#[test]
+fn test_display_warning() {
+    let warning = Diagnostic::from(Warning::UnusedImport("helper".to_string()));
+    assert!(warning.display_warning().to_string().contains("warning"));
+    assert!(warning.to_string().contains("error"));
+}
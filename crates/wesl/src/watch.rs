@@ -0,0 +1,615 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use itertools::Itertools;
+use wgsl_parse::{
+    display_indented,
+    syntax::{
+        Attributes, GlobalDeclaration, Ident, ModulePath, PathOrigin, TranslationUnit,
+        TypeExpression,
+    },
+};
+
+use crate::{
+    compile_post_assembly, compile_resolver, feature,
+    import::{is_deprecated_attr, remove_block_imports, Module, Resolutions},
+    keep_idents,
+    resolve::for_each_ident_mut,
+    syntax_util::{all_types, for_each_type_mut},
+    unreachable_code, validate, validate_attributes,
+    visit::Visit,
+    CompileOutputs, CompileResult, Emitter, Error, FragmentCache, ImportGraph, Indent, Resolver,
+    SourceMap, SourceMapper, SyncCacheResolver, SyntaxUtil, Wesl,
+};
+
+/// Emits declarations with the indentation of the compile options.
+struct IndentEmitter(Indent);
+
+impl Emitter for IndentEmitter {
+    fn emit(&mut self, decl: &GlobalDeclaration) -> String {
+        display_indented(decl, &self.0.to_string()).to_string()
+    }
+}
+
+/// Recompiles a shader after a module changed, for watch mode.
+///
+/// [`Self::on_change`] splices the changed module into the assembled syntax tree of the
+/// previous compilation:
+/// * only the changed module is parsed, resolved and validated again. Its references to
+///   other modules are linked to their declarations in the previous assembly,
+/// * its declarations keep their mangled names, and replace the previous ones in the
+///   assembly. The references of its importers are linked to the new declarations,
+/// * the passes that run on the assembled shader (stripping, lowering, validation...)
+///   run again, then only the declarations of the changed module and its importers are
+///   emitted again, the text of the others is spliced from a [`FragmentCache`].
+///
+/// The changed module must keep its interface: the same imports, exports, directives and
+/// declaration names, and each declaration refers to the same declarations of the module
+/// and of the other modules. Otherwise, or if the compilation emitted warnings, the
+/// shader is compiled again, with the mangle map of the previous compilation (see
+/// [`crate::CompileOptions::mangle_map`]). The output is byte-identical to a full
+/// compilation with the same options.
+///
+/// # Example
+/// ```rust
+/// # use wesl::{ModulePath, VirtualResolver, WatchCompiler, Wesl};
+/// let mut resolver = VirtualResolver::new();
+/// resolver.add_module("main", "@compute @workgroup_size(1) fn main() {}".into());
+/// let compiler = Wesl::new("").set_custom_resolver(resolver);
+///
+/// let mut watch = WatchCompiler::new(compiler, "main").unwrap();
+/// watch.resolver_mut().add_module(
+///     "main",
+///     "@compute @workgroup_size(2) fn main() {}".into(),
+/// );
+/// watch.on_change(ModulePath::from_path("/main")).unwrap();
+/// assert!(watch.output().contains("@workgroup_size(2)"));
+/// assert!(watch.spliced());
+/// ```
+pub struct WatchCompiler<R: Resolver> {
+    compiler: Wesl<SyncCacheResolver<R>>,
+    root: ModulePath,
+    graph: ImportGraph,
+    fragments: FragmentCache<IndentEmitter>,
+    result: CompileResult,
+    output: String,
+    assembly: Option<Assembly>,
+    spliced: bool,
+}
+
+impl<R: Resolver> WatchCompiler<R> {
+    /// Compile `root` a first time. The resolver of `compiler` is wrapped in a
+    /// [`SyncCacheResolver`].
+    pub fn new(compiler: Wesl<R>, root: impl Into<ModulePath>) -> Result<Self, Error> {
+        let mut root = root.into();
+        root.origin = PathOrigin::Absolute;
+        let Wesl {
+            options,
+            use_sourcemap,
+            resolver,
+            mangler,
+        } = compiler;
+        let compiler = Wesl {
+            options,
+            use_sourcemap,
+            resolver: SyncCacheResolver::new(resolver),
+            mangler,
+        };
+        let graph = ImportGraph::new(root.clone(), &compiler.resolver)?;
+        let (result, assembly) = compile(&compiler, &root)?;
+        let hashes = graph.semantic_hashes(&compiler.resolver)?;
+        let mut fragments = FragmentCache::with_emitter(IndentEmitter(compiler.options.indent));
+        let output = render(&result, &mut fragments, &hashes);
+        Ok(Self {
+            compiler,
+            root,
+            graph,
+            fragments,
+            result,
+            output,
+            assembly,
+            spliced: false,
+        })
+    }
+
+    /// The output of the last compilation. It is equal to the `Display` of
+    /// [`Self::result`].
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// The result of the last compilation.
+    pub fn result(&self) -> &CompileResult {
+        &self.result
+    }
+
+    /// Whether the last [`Self::on_change`] spliced the changed module into the previous
+    /// compilation, rather than compiling the shader again.
+    pub fn spliced(&self) -> bool {
+        self.spliced
+    }
+
+    /// The wrapped resolver, e.g. to update the sources of a [`crate::VirtualResolver`].
+    pub fn resolver_mut(&mut self) -> &mut R {
+        &mut self.compiler.resolver.resolver
+    }
+
+    /// Notify that the source of the module `path` changed, and recompile the shader.
+    ///
+    /// Returns the modules affected by the change: the changed module and its importers,
+    /// transitively. If the module is not part of the shader, nothing is recompiled and
+    /// the set is empty. On error, the previous output is kept.
+    pub fn on_change(&mut self, path: impl Into<ModulePath>) -> Result<HashSet<ModulePath>, Error> {
+        let path = path.into();
+        self.compiler.resolver.invalidate(&path);
+        let affected = self.graph.on_change(&path, &self.compiler.resolver)?;
+        if affected.is_empty() {
+            return Ok(affected);
+        }
+        let result = match self.splice(&path, &affected) {
+            Some(result) => {
+                self.spliced = true;
+                result
+            }
+            None => {
+                self.spliced = false;
+                // the assembly may be partially spliced.
+                self.assembly = None;
+                self.compiler.options.mangle_map = self.result.mangle_map.clone();
+                let (result, assembly) = compile(&self.compiler, &self.root)?;
+                self.assembly = assembly;
+                result
+            }
+        };
+        let hashes = self.graph.semantic_hashes(&self.compiler.resolver)?;
+        self.output = render(&result, &mut self.fragments, &hashes);
+        self.result = result;
+        Ok(affected)
+    }
+
+    /// Splice the changed module `path` into the previous assembly and run the
+    /// post-assembly passes. Returns `None` if the change cannot be spliced or if the
+    /// compilation fails, then the shader must be compiled again.
+    fn splice(
+        &mut self,
+        path: &ModulePath,
+        affected: &HashSet<ModulePath>,
+    ) -> Option<CompileResult> {
+        let options = &self.compiler.options;
+        let assembly = self.assembly.as_mut()?;
+        // warnings would have to be emitted again for the modules that are not spliced.
+        if !self.result.warnings.is_empty() || options.preserve_entry_verbatim && *path == self.root
+        {
+            return None;
+        }
+        let interface = assembly.index.interfaces.get(path)?;
+
+        let mangler = &self.compiler.mangler;
+        let mapper = SourceMapper::new(&self.root, &self.compiler.resolver, mangler);
+        let resolver = compile_resolver(&mapper, options);
+        let mut source = resolver.resolve_module(path).ok()?;
+        std::mem::drop(resolver);
+        source.retarget_idents();
+        let keep = keep_idents(&source, options);
+        let mut module = Module::new(source, path.clone()).ok()?;
+        let positions = decl_positions(&module.source, path);
+        if Interface::new(&module, &positions, &assembly.index.decls) != *interface {
+            return None;
+        }
+        if *path == self.root && keep_positions(&positions, &keep) != assembly.keep_positions() {
+            return None;
+        }
+        module.check_block_scopes(&options.extra_builtins).ok()?;
+        let deprecated = Visit::<Attributes>::visit(&module.source)
+            .flatten()
+            .any(is_deprecated_attr);
+        if deprecated
+            || options.warn_unused_imports && !module.unused_imports().is_empty()
+            || options.warn_unreachable_code && !unreachable_code(&module.source).is_empty()
+        {
+            return None;
+        }
+
+        // link the references to other modules, like `Resolutions::retarget`.
+        let assembled = assembly
+            .provenance
+            .iter()
+            .zip(&assembly.syntax.global_declarations)
+            .filter_map(|(pos, decl)| Some((pos.clone(), decl.ident()?.clone())))
+            .collect::<HashMap<_, _>>();
+        let locals = module
+            .source
+            .global_declarations
+            .iter()
+            .map(|decl| decl.ident().cloned())
+            .collect_vec();
+        let mut external = positions.keys().cloned().collect::<HashSet<_>>();
+        // declarations that are not assembled are only referred to by declarations that
+        // are not assembled either.
+        let mut unassembled = HashMap::new();
+        let mut source = std::mem::take(&mut module.source);
+        for_each_type_mut(&mut source, &mut |ty| {
+            let Some(Target::Decl(decl_path, n)) =
+                target(&module, ty, &positions, &assembly.index.decls)
+            else {
+                return;
+            };
+            if decl_path == *path {
+                if let Some(id) = &locals[n] {
+                    ty.path = None;
+                    ty.ident = id.clone();
+                }
+                return;
+            }
+            let pos = (decl_path, n);
+            let id = match assembled.get(&pos) {
+                Some(id) => id.clone(),
+                None => unassembled
+                    .entry(pos)
+                    .or_insert_with(|| Ident::new(ty.ident.to_string()))
+                    .clone(),
+            };
+            ty.path = None;
+            ty.ident = id.clone();
+            external.insert(id);
+        });
+        if options.validate {
+            validate::validate_module(&source, &options.extra_builtins, &external).ok()?;
+            if let Some(known) = &options.known_attributes {
+                validate_attributes(&source, known).ok()?;
+            }
+        }
+        remove_block_imports(&mut source);
+
+        // replace the declarations, they keep their mangled names.
+        let mut decls = source
+            .global_declarations
+            .into_iter()
+            .map(Some)
+            .collect_vec();
+        let mut renamed = HashMap::new();
+        let spliced = assembly
+            .provenance
+            .iter()
+            .zip(&mut assembly.syntax.global_declarations)
+            .filter(|(pos, _)| pos.0 == *path);
+        for ((_, n), decl) in spliced {
+            let mut new_decl = decls[*n].take()?;
+            if let (Some(old), Some(new)) = (decl.ident(), new_decl.ident_mut()) {
+                new.rename(old.to_string());
+                renamed.insert(old.clone(), new.clone());
+            }
+            *decl = new_decl;
+        }
+        let importers = assembly
+            .provenance
+            .iter()
+            .zip(&mut assembly.syntax.global_declarations)
+            .filter(|(pos, _)| pos.0 != *path && affected.contains(&pos.0));
+        for (_, decl) in importers {
+            for_each_type_mut(decl, &mut |ty| {
+                if let Some(id) = renamed.get(&ty.ident) {
+                    ty.ident = id.clone();
+                }
+            });
+        }
+        if *path == self.root {
+            assembly.keep = keep;
+        }
+
+        let (mut syntax, keep) = detach(&assembly.syntax, &assembly.keep);
+        // the changed module has the same declarations: only its source changed.
+        let sourcemap = self.result.sourcemap.clone().map(|mut sourcemap| {
+            let mapped = mapper.finish();
+            if let Some(source) = mapped.get_source(path) {
+                let name = mapped.get_display_name(path).map(str::to_string);
+                sourcemap.add_source(path.clone(), name, source.to_string());
+                if *path == self.root {
+                    sourcemap.set_default_source(source.to_string());
+                }
+            }
+            sourcemap
+        });
+        let mut outputs = CompileOutputs {
+            mangle_map: self.result.mangle_map.clone(),
+            import_comments: self.result.import_comments.clone(),
+            modules: self.result.modules.clone(),
+            ..Default::default()
+        };
+        compile_post_assembly(&mut syntax, options, &keep).ok()?;
+        std::mem::drop(keep);
+        feature::emit_missing_features(&syntax, options, &mut outputs.warnings).ok()?;
+        let root = &self.root;
+        let resolver = &self.compiler.resolver;
+        self.compiler
+            .finish(root, resolver, syntax, sourcemap, &mut outputs)
+            .ok()
+    }
+}
+
+/// Compile the shader, keeping the assembly to splice changes into.
+fn compile<R: Resolver>(
+    compiler: &Wesl<R>,
+    root: &ModulePath,
+) -> Result<(CompileResult, Option<Assembly>), Error> {
+    let mut outputs = CompileOutputs {
+        keep_assembly: true,
+        ..Default::default()
+    };
+    let result = compiler.compile_with(root.clone(), &compiler.resolver, &mut outputs)?;
+    Ok((result, outputs.assembly))
+}
+
+/// The assembled syntax tree of a compilation, before the post-assembly passes. See
+/// [`WatchCompiler`].
+pub(crate) struct Assembly {
+    index: ModuleIndex,
+    /// Mangled. Its identifiers are not shared with the compiled syntax tree.
+    syntax: TranslationUnit,
+    /// The module path and position of each declaration of `syntax`.
+    provenance: Vec<(ModulePath, usize)>,
+    /// The declarations of the root module to keep, see `keep_idents`.
+    keep: HashSet<Ident>,
+}
+
+impl Assembly {
+    pub(crate) fn new(
+        index: ModuleIndex,
+        syntax: &TranslationUnit,
+        provenance: Vec<(ModulePath, usize)>,
+        keep: &HashSet<Ident>,
+    ) -> Self {
+        let (syntax, keep) = detach(syntax, keep);
+        Self {
+            index,
+            syntax,
+            provenance,
+            keep,
+        }
+    }
+
+    /// Positions of the kept declarations in the root module.
+    fn keep_positions(&self) -> HashSet<usize> {
+        self.provenance
+            .iter()
+            .zip(&self.syntax.global_declarations)
+            .filter(|(_, decl)| decl.ident().is_some_and(|id| self.keep.contains(id)))
+            .map(|((_, n), _)| *n)
+            .collect()
+    }
+}
+
+/// The declarations and interfaces of the modules of a compilation.
+pub(crate) struct ModuleIndex {
+    /// See [`Resolutions::decl_index`].
+    decls: HashMap<(ModulePath, String), (ModulePath, usize)>,
+    interfaces: HashMap<ModulePath, Interface>,
+}
+
+impl ModuleIndex {
+    /// Must be called before mangling, see [`Resolutions::decl_index`].
+    pub(crate) fn new(resolutions: &Resolutions) -> Self {
+        let decls = resolutions.decl_index();
+        let positions = resolutions.decl_positions();
+        let interfaces = resolutions
+            .modules()
+            .map(|module| {
+                let module = module.borrow();
+                let interface = Interface::new(&module, &positions, &decls);
+                (module.path.clone(), interface)
+            })
+            .collect();
+        Self { decls, interfaces }
+    }
+}
+
+/// What the assembly depends on in a module, besides the body of its declarations.
+#[derive(PartialEq)]
+struct Interface {
+    /// Imports, exports, directives and `@base`.
+    header: Vec<String>,
+    /// Names of the global declarations, `None` for `const_assert`s.
+    decls: Vec<Option<String>>,
+    /// The global declarations that each declaration refers to, in order of first
+    /// reference. They determine which declarations are assembled, and in which order.
+    targets: Vec<Vec<Target>>,
+}
+
+impl Interface {
+    fn new(
+        module: &Module,
+        positions: &HashMap<Ident, (ModulePath, usize)>,
+        decls: &HashMap<(ModulePath, String), (ModulePath, usize)>,
+    ) -> Self {
+        let source = &module.source;
+        let header = source
+            .imports
+            .iter()
+            .map(|import| format!("import {import}"))
+            .chain(source.exports.iter().map(|export| export.to_string()))
+            .chain(source.global_directives.iter().map(|dir| dir.to_string()))
+            .chain(source.base.iter().map(|base| format!("@base({base:?})")))
+            .collect();
+        let targets = source
+            .global_declarations
+            .iter()
+            .map(|decl| {
+                all_types(decl)
+                    .filter_map(|ty| target(module, ty, positions, decls))
+                    .unique()
+                    .collect()
+            })
+            .collect();
+        Self {
+            header,
+            decls: source
+                .global_declarations
+                .iter()
+                .map(|decl| decl.ident().map(|id| id.to_string()))
+                .collect(),
+            targets,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Target {
+    /// A declaration of a loaded module, by module path and position.
+    Decl(ModulePath, usize),
+    /// A declaration of a module that is not loaded, by module path and name.
+    Unloaded(ModulePath, String),
+}
+
+/// The global declaration that a type expression refers to. `positions` are the linked
+/// declarations, `decls` is the [`Resolutions::decl_index`].
+fn target(
+    module: &Module,
+    ty: &TypeExpression,
+    positions: &HashMap<Ident, (ModulePath, usize)>,
+    decls: &HashMap<(ModulePath, String), (ModulePath, usize)>,
+) -> Option<Target> {
+    if let Some((path, n)) = positions.get(&ty.ident) {
+        return Some(Target::Decl(path.clone(), *n));
+    }
+    let key = module.target(ty)?;
+    Some(match decls.get(&key) {
+        Some((path, n)) => Target::Decl(path.clone(), *n),
+        None => Target::Unloaded(key.0, key.1),
+    })
+}
+
+fn decl_positions(
+    source: &TranslationUnit,
+    path: &ModulePath,
+) -> HashMap<Ident, (ModulePath, usize)> {
+    source
+        .global_declarations
+        .iter()
+        .enumerate()
+        .filter_map(|(n, decl)| Some((decl.ident()?.clone(), (path.clone(), n))))
+        .collect()
+}
+
+fn keep_positions(
+    positions: &HashMap<Ident, (ModulePath, usize)>,
+    keep: &HashSet<Ident>,
+) -> HashSet<usize> {
+    keep.iter()
+        .filter_map(|id| positions.get(id))
+        .map(|(_, n)| *n)
+        .collect()
+}
+
+/// A copy of a syntax tree and of the kept declarations, with new identifiers that are
+/// shared like the original ones.
+fn detach(syntax: &TranslationUnit, keep: &HashSet<Ident>) -> (TranslationUnit, HashSet<Ident>) {
+    let mut syntax = syntax.clone();
+    let mut idents = HashMap::new();
+    for_each_ident_mut(&mut syntax, &mut |id| {
+        *id = idents
+            .entry(id.clone())
+            .or_insert_with(|| Ident::new(id.to_string()))
+            .clone();
+    });
+    let keep = keep
+        .iter()
+        .map(|id| {
+            idents
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| Ident::new(id.to_string()))
+        })
+        .collect();
+    (syntax, keep)
+}
+
+/// Like the `Display` of [`CompileResult`], with the declarations emitted by `fragments`.
+fn render(
+    result: &CompileResult,
+    fragments: &mut FragmentCache<IndentEmitter>,
+    hashes: &HashMap<ModulePath, u64>,
+) -> String {
+    let mut res = String::new();
+    for import in &result.import_comments {
+        writeln!(res, "// {import}").expect("writing to a string cannot fail");
+    }
+    if !result.import_comments.is_empty() {
+        res.push('\n');
+    }
+    if let Some(verbatim) = &result.verbatim {
+        res.push_str(verbatim);
+    } else {
+        res.push_str(&fragments.emit(&result.syntax, &result.mangle_map, hashes));
+    }
+    res
+}
+
+#[test]
+fn test_watch_compiler() {
+    use crate::VirtualResolver;
+    let sources = |arg: &str, scale: &str| {
+        let mut resolver = VirtualResolver::new();
+        resolver.add_module(
+            "main",
+            format!(
+                "import package::lights::shade;
+                @fragment fn main() -> @location(0) vec4f {{ return vec4f(shade({arg})); }}"
+            )
+            .into(),
+        );
+        resolver.add_module(
+            "lights",
+            "import package::math::scale;
+            struct Light { power: f32 }
+            fn shade(x: f32) -> f32 { return scale(Light(x).power); }
+            fn unused() -> f32 { return scale(0.0); }"
+                .into(),
+        );
+        resolver.add_module("math", scale.to_string().into());
+        resolver
+    };
+    let scale = |factor: &str| format!("fn scale(x: f32) -> f32 {{ return x * {factor}; }}");
+
+    let compiler = Wesl::new("").set_custom_resolver(sources("1.0", &scale("2.0")));
+    let mut watch = WatchCompiler::new(compiler, "main").unwrap();
+    let full = |resolver| {
+        Wesl::new("")
+            .set_custom_resolver(resolver)
+            .compile("main")
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(watch.output(), full(sources("1.0", &scale("2.0"))));
+
+    // edit the leaf module.
+    *watch.resolver_mut() = sources("1.0", &scale("3.0"));
+    let affected = watch.on_change(ModulePath::from_path("/math")).unwrap();
+    let path = ModulePath::from_path;
+    assert_eq!(
+        affected,
+        HashSet::from([path("/math"), path("/lights"), path("/main")])
+    );
+    assert!(watch.spliced());
+    assert_eq!(watch.output(), full(sources("1.0", &scale("3.0"))));
+    assert!(watch.output().contains("x * 3.0"));
+
+    // edit the root module.
+    *watch.resolver_mut() = sources("2.0", &scale("3.0"));
+    watch.on_change(path("/main")).unwrap();
+    assert!(watch.spliced());
+    assert_eq!(watch.output(), full(sources("2.0", &scale("3.0"))));
+
+    // the leaf module refers to a new declaration: its interface changed.
+    let helper = "fn twice(x: f32) -> f32 { return x * 2.0; }
+        fn scale(x: f32) -> f32 { return twice(x); }";
+    *watch.resolver_mut() = sources("2.0", helper);
+    watch.on_change(path("/math")).unwrap();
+    assert!(!watch.spliced());
+    assert_eq!(watch.output(), full(sources("2.0", helper)));
+
+    // a module outside the shader.
+    let affected = watch.on_change(path("/other")).unwrap();
+    assert!(affected.is_empty());
+}
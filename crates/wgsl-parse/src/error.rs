@@ -26,8 +26,16 @@ pub enum ErrorKind {
     DiagnosticSeverity,
     #[error("invalid `{0}` attribute, {1}")]
     Attribute(&'static str, &'static str),
+    #[error("the `{0}` attribute does not take a string argument")]
+    StringArgument(String),
     #[error("invalid `var` template arguments, {0}")]
     VarTemplate(&'static str),
+    #[error("brackets are nested too deeply (maximum is {0})")]
+    NestingDepth(usize),
+    #[error("expression has too many operators (maximum is {0})")]
+    ExpressionOperators(u32),
+    #[error("literal `{0}` is out of range for `{1}`")]
+    LiteralRange(String, &'static str),
 }
 
 #[derive(Default, Clone, Debug, PartialEq)]
@@ -36,7 +44,12 @@ pub(crate) enum CustomLalrError {
     LexerError,
     DiagnosticSeverity,
     Attribute(&'static str, &'static str),
+    #[cfg(feature = "imports")]
+    StringArgument(String),
     VarTemplate(&'static str),
+    NestingDepth(usize),
+    ExpressionOperators(u32),
+    LiteralRange(String, &'static str),
 }
 
 type LalrError = lalrpop_util::ParseError<usize, Token, (usize, CustomLalrError, usize)>;
@@ -105,7 +118,14 @@ impl From<LalrError> for Error {
                     CustomLalrError::Attribute(attr, expected) => {
                         ErrorKind::Attribute(attr, expected)
                     }
+                    #[cfg(feature = "imports")]
+                    CustomLalrError::StringArgument(attr) => ErrorKind::StringArgument(attr),
                     CustomLalrError::VarTemplate(reason) => ErrorKind::VarTemplate(reason),
+                    CustomLalrError::NestingDepth(max) => ErrorKind::NestingDepth(max),
+                    CustomLalrError::ExpressionOperators(max) => {
+                        ErrorKind::ExpressionOperators(max)
+                    }
+                    CustomLalrError::LiteralRange(lit, ty) => ErrorKind::LiteralRange(lit, ty),
                 };
                 Self { span, error }
             }
@@ -1,8 +1,10 @@
 //! Prefer using [`Parser::parse_str`]. You shouldn't need to manipulate the lexer.
 
-use crate::error::CustomLalrError;
+use crate::{error::CustomLalrError, parser::ParseLimits};
 use logos::{Logos, SpannedIter};
-use std::{fmt::Display, num::NonZeroU8, sync::LazyLock};
+use std::{
+    cell::RefCell, collections::HashMap, fmt::Display, num::NonZeroU8, rc::Rc, sync::LazyLock,
+};
 
 type Span = std::ops::Range<usize>;
 
@@ -74,83 +76,130 @@ static FLOAT_HEX_OPTIONS: LazyLock<lexical::parse_float_options::Options> = Lazy
         .unwrap()
 });
 
-fn parse_dec_abstract_int(lex: &mut logos::Lexer<Token>) -> Option<i64> {
+fn out_of_range(lex: &logos::Lexer<Token>, ty: &'static str) -> CustomLalrError {
+    CustomLalrError::LiteralRange(lex.slice().to_string(), ty)
+}
+
+fn parse_dec_abstract_int(lex: &mut logos::Lexer<Token>) -> Result<i64, CustomLalrError> {
     let options = &lexical::parse_integer_options::STANDARD;
     let str = lex.slice();
-    lexical::parse_with_options::<i64, _, DEC_FORMAT>(str, options).ok()
+    lexical::parse_with_options::<i64, _, DEC_FORMAT>(str, options)
+        .map_err(|_| out_of_range(lex, "AbstractInt"))
 }
 
-fn parse_hex_abstract_int(lex: &mut logos::Lexer<Token>) -> Option<i64> {
+fn parse_hex_abstract_int(lex: &mut logos::Lexer<Token>) -> Result<i64, CustomLalrError> {
     let options = &lexical::parse_integer_options::STANDARD;
     let str = lex.slice();
-    lexical::parse_with_options::<i64, _, HEX_FORMAT>(str, options).ok()
+    lexical::parse_with_options::<i64, _, HEX_FORMAT>(str, options)
+        .map_err(|_| out_of_range(lex, "AbstractInt"))
 }
 
-fn parse_dec_i32(lex: &mut logos::Lexer<Token>) -> Option<i32> {
+fn parse_dec_i32(lex: &mut logos::Lexer<Token>) -> Result<i32, CustomLalrError> {
     let options = &lexical::parse_integer_options::STANDARD;
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<i32, _, DEC_FORMAT>(str, options).ok()
+    lexical::parse_with_options::<i32, _, DEC_FORMAT>(str, options)
+        .map_err(|_| out_of_range(lex, "i32"))
 }
 
-fn parse_hex_i32(lex: &mut logos::Lexer<Token>) -> Option<i32> {
+fn parse_hex_i32(lex: &mut logos::Lexer<Token>) -> Result<i32, CustomLalrError> {
     let options = &lexical::parse_integer_options::STANDARD;
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<i32, _, HEX_FORMAT>(str, options).ok()
+    lexical::parse_with_options::<i32, _, HEX_FORMAT>(str, options)
+        .map_err(|_| out_of_range(lex, "i32"))
 }
 
-fn parse_dec_u32(lex: &mut logos::Lexer<Token>) -> Option<u32> {
+fn parse_dec_u32(lex: &mut logos::Lexer<Token>) -> Result<u32, CustomLalrError> {
     let options = &lexical::parse_integer_options::STANDARD;
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<u32, _, DEC_FORMAT>(str, options).ok()
+    lexical::parse_with_options::<u32, _, DEC_FORMAT>(str, options)
+        .map_err(|_| out_of_range(lex, "u32"))
 }
 
-fn parse_hex_u32(lex: &mut logos::Lexer<Token>) -> Option<u32> {
+fn parse_hex_u32(lex: &mut logos::Lexer<Token>) -> Result<u32, CustomLalrError> {
     let options = &lexical::parse_integer_options::STANDARD;
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<u32, _, HEX_FORMAT>(str, options).ok()
+    lexical::parse_with_options::<u32, _, HEX_FORMAT>(str, options)
+        .map_err(|_| out_of_range(lex, "u32"))
+}
+
+/// Float literals that overflow parse to infinity, they are rejected.
+fn finite<T: Into<f64> + Copy>(
+    lex: &logos::Lexer<Token>,
+    ty: &'static str,
+    val: Option<T>,
+) -> Result<T, CustomLalrError> {
+    val.filter(|val| (*val).into().is_finite())
+        .ok_or_else(|| out_of_range(lex, ty))
+}
+
+/// The largest f16 is 65504, larger values round to it up to 65520 (exclusive).
+fn finite_f16(lex: &logos::Lexer<Token>, val: Option<f32>) -> Result<f32, CustomLalrError> {
+    val.filter(|val| val.abs() < 65520.0)
+        .ok_or_else(|| out_of_range(lex, "f16"))
 }
 
-fn parse_dec_abs_float(lex: &mut logos::Lexer<Token>) -> Option<f64> {
+fn parse_dec_abs_float(lex: &mut logos::Lexer<Token>) -> Result<f64, CustomLalrError> {
     let options = &lexical::parse_float_options::STANDARD;
     let str = lex.slice();
-    lexical::parse_with_options::<f64, _, DEC_FORMAT>(str, options).ok()
+    let val = lexical::parse_with_options::<f64, _, DEC_FORMAT>(str, options).ok();
+    finite(lex, "AbstractFloat", val)
 }
 
-fn parse_hex_abs_float(lex: &mut logos::Lexer<Token>) -> Option<f64> {
+fn parse_hex_abs_float(lex: &mut logos::Lexer<Token>) -> Result<f64, CustomLalrError> {
     let str = lex.slice();
-    lexical::parse_with_options::<f64, _, HEX_FORMAT>(str, &FLOAT_HEX_OPTIONS).ok()
+    let val = lexical::parse_with_options::<f64, _, HEX_FORMAT>(str, &FLOAT_HEX_OPTIONS).ok();
+    finite(lex, "AbstractFloat", val)
 }
 
-fn parse_dec_f32(lex: &mut logos::Lexer<Token>) -> Option<f32> {
+fn parse_dec_f32(lex: &mut logos::Lexer<Token>) -> Result<f32, CustomLalrError> {
     let options = &lexical::parse_float_options::STANDARD;
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<f32, _, DEC_FORMAT>(str, options).ok()
+    let val = lexical::parse_with_options::<f32, _, DEC_FORMAT>(str, options).ok();
+    finite(lex, "f32", val)
 }
 
-fn parse_hex_f32(lex: &mut logos::Lexer<Token>) -> Option<f32> {
+fn parse_hex_f32(lex: &mut logos::Lexer<Token>) -> Result<f32, CustomLalrError> {
     let str = lex.slice();
     // TODO
     let options = &lexical::parse_float_options::STANDARD;
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<f32, _, HEX_FORMAT>(str, options).ok()
+    let val = lexical::parse_with_options::<f32, _, HEX_FORMAT>(str, options).ok();
+    finite(lex, "f32", val)
 }
 
-fn parse_dec_f16(lex: &mut logos::Lexer<Token>) -> Option<f32> {
+fn parse_dec_f16(lex: &mut logos::Lexer<Token>) -> Result<f32, CustomLalrError> {
     let options = &lexical::parse_float_options::STANDARD;
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<f32, _, DEC_FORMAT>(str, options).ok()
+    let val = lexical::parse_with_options::<f32, _, DEC_FORMAT>(str, options).ok();
+    finite_f16(lex, val)
 }
 
-fn parse_hex_f16(lex: &mut logos::Lexer<Token>) -> Option<f32> {
+fn parse_hex_f16(lex: &mut logos::Lexer<Token>) -> Result<f32, CustomLalrError> {
     let str = lex.slice();
     let str = &str[..str.len() - 1];
-    lexical::parse_with_options::<f32, _, HEX_FORMAT>(str, &FLOAT_HEX_OPTIONS).ok()
+    let val = lexical::parse_with_options::<f32, _, HEX_FORMAT>(str, &FLOAT_HEX_OPTIONS).ok();
+    finite_f16(lex, val)
+}
+
+/// Parse a `@base("path")` directive at the start of `source`. Returns the path and the
+/// length of the directive.
+#[cfg(feature = "imports")]
+fn parse_base_directive(source: &str) -> Option<(String, usize)> {
+    let rem = source.strip_prefix("@base")?.trim_start();
+    let rem = rem.strip_prefix('(')?.trim_start();
+    let rem = rem.strip_prefix('"')?;
+    let (path, rem) = rem.split_once('"')?;
+    if path.contains('\n') {
+        return None;
+    }
+    let rem = rem.trim_start().strip_prefix(')')?;
+    Some((path.to_string(), source.len() - rem.len()))
 }
 
 fn parse_block_comment(lex: &mut logos::Lexer<Token>) -> logos::Skip {
@@ -398,6 +447,21 @@ pub(crate) enum Token {
     #[cfg(feature = "imports")]
     #[token("import")]
     KwImport,
+    // `type` is a reserved word in WGSL.
+    #[cfg(feature = "imports")]
+    #[token("type")]
+    KwType,
+    #[cfg(feature = "imports")]
+    #[token("export")]
+    KwExport,
+    // the whole `@base("path")` is one token, emitted by [`Lexer`] only at the start of
+    // the source, because `base` is not a keyword.
+    #[cfg(feature = "imports")]
+    BaseDirective(String),
+    // WGSL has no strings, this is only the message of `@deprecated("message")`.
+    #[cfg(feature = "imports")]
+    #[regex(r#""[^"\n]*""#, |lex| { let s = lex.slice(); s[1..s.len() - 1].to_string() })]
+    StringLiteral(String),
 }
 
 impl Token {
@@ -598,6 +662,14 @@ impl Display for Token {
             Token::KwAs => write!(f, "as"),
             #[cfg(feature = "imports")]
             Token::KwImport => write!(f, "import"),
+            #[cfg(feature = "imports")]
+            Token::KwType => write!(f, "type"),
+            #[cfg(feature = "imports")]
+            Token::KwExport => write!(f, "export"),
+            #[cfg(feature = "imports")]
+            Token::BaseDirective(path) => write!(f, "@base(\"{path}\")"),
+            #[cfg(feature = "imports")]
+            Token::StringLiteral(s) => write!(f, "\"{s}\""),
         }
     }
 }
@@ -605,6 +677,26 @@ impl Display for Token {
 pub type Spanned<Tok, Loc, ParseError> = Result<(Loc, Tok, Loc), (Loc, ParseError, Loc)>;
 type NextToken = Option<(Result<Token, CustomLalrError>, Span)>;
 
+/// Results of template list recognition, by offset in the source of the outermost lexer.
+///
+/// Nested template lists are recognized again by each enclosing recognition. Without
+/// caching, deeply nested templates (`array<array<array<...>>>`) take exponential time.
+type TemplateCache = Rc<RefCell<HashMap<usize, bool>>>;
+
+/// Default of [`ParseLimits::max_nesting_depth`].
+///
+/// The WGSL spec allows implementations to limit nesting of brace-enclosed statements
+/// to 127.
+pub(crate) const MAX_NESTING_DEPTH: usize = 127;
+
+/// Operator count of an open bracket group. `cur` is the count of the current
+/// comma-separated item, `max` the largest count of the previous items.
+#[derive(Clone, Copy, Default)]
+struct Group {
+    cur: u32,
+    max: u32,
+}
+
 #[derive(Clone)]
 pub(crate) struct Lexer<'s> {
     source: &'s str,
@@ -612,10 +704,47 @@ pub(crate) struct Lexer<'s> {
     next_token: NextToken,
     recognizing_template: bool,
     opened_templates: u32,
+    offset: usize, // offset of `source` in the source of the outermost lexer
+    template_cache: TemplateCache,
+    groups: Vec<Group>,  // never empty, the first group is the top-level
+    base_nesting: usize, // nesting depth of the outer lexer, when recognizing a template
+    limits: ParseLimits,
 }
 
 impl<'s> Lexer<'s> {
     pub fn new(source: &'s str) -> Self {
+        Self::with_limits(source, ParseLimits::default())
+    }
+
+    pub fn with_limits(source: &'s str, limits: ParseLimits) -> Self {
+        let lexer = Self::new_with_cache(source, 0, Default::default(), 0, limits);
+        #[cfg(feature = "imports")]
+        let lexer = lexer.lex_base_directive();
+        lexer
+    }
+
+    /// A `@base` directive is only recognized as the first token of the source, other
+    /// attributes named `base` are lexed as usual.
+    #[cfg(feature = "imports")]
+    fn lex_base_directive(mut self) -> Self {
+        if let Some((Ok(Token::SymAttr), span)) = &self.next_token {
+            let start = span.start;
+            if let Some((path, len)) = parse_base_directive(&self.source[start..]) {
+                let end = self.token_stream.span().end;
+                self.token_stream.bump(start + len - end);
+                self.next_token = Some((Ok(Token::BaseDirective(path)), start..start + len));
+            }
+        }
+        self
+    }
+
+    fn new_with_cache(
+        source: &'s str,
+        offset: usize,
+        template_cache: TemplateCache,
+        base_nesting: usize,
+        limits: ParseLimits,
+    ) -> Self {
         let mut token_stream = Token::lexer_with_extras(source, LexerState::default()).spanned();
         let next_token = token_stream.next();
         Self {
@@ -624,7 +753,96 @@ impl<'s> Lexer<'s> {
             next_token,
             recognizing_template: false,
             opened_templates: 0,
+            offset,
+            template_cache,
+            groups: vec![Group::default()],
+            base_nesting,
+            limits,
+        }
+    }
+
+    fn nesting(&self) -> usize {
+        self.base_nesting + self.groups.len() - 1
+    }
+
+    /// Enforce the [`ParseLimits`].
+    fn check_depth(&mut self, tok: &Token) -> Result<(), CustomLalrError> {
+        match tok {
+            Token::SymParenLeft
+            | Token::SymBracketLeft
+            | Token::SymBraceLeft
+            | Token::TemplateArgsStart => {
+                let max = self.limits.max_nesting_depth;
+                if self.nesting() >= max {
+                    return Err(CustomLalrError::NestingDepth(max));
+                }
+                self.groups.push(Group::default());
+            }
+            // blocks are statements, their content does not nest in an expression.
+            Token::SymBraceRight if self.groups.len() > 1 => {
+                self.groups.pop();
+            }
+            Token::SymParenRight | Token::SymBracketRight | Token::TemplateArgsEnd
+                if self.groups.len() > 1 =>
+            {
+                let group = self.groups.pop().unwrap(); // safety: checked above
+                let parent = self.groups.last_mut().unwrap(); // safety: idem
+                parent.cur += group.cur.max(group.max) + 1;
+            }
+            Token::SymComma | Token::SymSemicolon => {
+                let group = self.groups.last_mut().unwrap(); // safety: never empty
+                group.max = group.max.max(group.cur);
+                group.cur = 0;
+            }
+            Token::SymAnd
+            | Token::SymAndAnd
+            | Token::SymForwardSlash
+            | Token::SymBang
+            | Token::SymEqualEqual
+            | Token::SymNotEqual
+            | Token::SymGreaterThan
+            | Token::SymGreaterThanEqual
+            | Token::SymShiftRight
+            | Token::SymLessThan
+            | Token::SymLessThanEqual
+            | Token::SymShiftLeft
+            | Token::SymModulo
+            | Token::SymMinus
+            | Token::SymPeriod
+            | Token::SymPlus
+            | Token::SymOr
+            | Token::SymOrOr
+            | Token::SymStar
+            | Token::SymTilde
+            | Token::SymXor => {
+                self.groups.last_mut().unwrap().cur += 1; // safety: never empty
+            }
+            _ => {}
+        }
+        if let Some(max) = self.limits.max_expression_operators {
+            if self.groups.last().unwrap().cur > max {
+                return Err(CustomLalrError::ExpressionOperators(max));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`recognize_template_list`], with results cached across nested recognitions.
+    fn recognize_template_list_at(&self, start: usize) -> bool {
+        let offset = self.offset + start;
+        if let Some(res) = self.template_cache.borrow().get(&offset) {
+            return *res;
         }
+        let lexer = Lexer::new_with_cache(
+            &self.source[start..],
+            offset,
+            self.template_cache.clone(),
+            self.nesting(),
+            self.limits,
+        );
+        let res = recognize_template_list_impl(lexer);
+        self.template_cache.borrow_mut().insert(offset, res);
+        res
     }
 
     fn take_two_tokens(&mut self) -> (NextToken, NextToken) {
@@ -656,14 +874,12 @@ impl<'s> Lexer<'s> {
         if let Some((Ok(next_tok), next_span)) = &mut next {
             if (matches!(cur_tok, Token::Ident(_)) || cur_tok.is_keyword())
                 && *next_tok == Token::SymLessThan
+                && self.recognize_template_list_at(next_span.start)
             {
-                let source = &self.source[next_span.start..];
-                if recognize_template_list(source) {
-                    *next_tok = Token::TemplateArgsStart;
-                    let cur_depth = self.token_stream.extras.depth;
-                    self.token_stream.extras.template_depths.push(cur_depth);
-                    self.opened_templates += 1;
-                }
+                *next_tok = Token::TemplateArgsStart;
+                let cur_depth = self.token_stream.extras.depth;
+                self.token_stream.extras.template_depths.push(cur_depth);
+                self.opened_templates += 1;
             }
         }
 
@@ -676,6 +892,9 @@ impl<'s> Lexer<'s> {
         }
 
         self.next_token = next;
+        if let Err(e) = self.check_depth(&cur_tok) {
+            return Some((Err(e), cur_span));
+        }
         Some((Ok(cur_tok), cur_span))
     }
 }
@@ -719,8 +938,12 @@ impl<'s> Lexer<'s> {
 ///
 /// [template list discovery algorigthm]: https://www.w3.org/TR/WGSL/#template-list-discovery
 /// [*template_list*]: https://www.w3.org/TR/WGSL/#syntax-template_list
-pub fn recognize_template_list(source: &str) -> bool {
-    let mut lexer = Lexer::new(source);
+#[cfg(test)]
+fn recognize_template_list(source: &str) -> bool {
+    recognize_template_list_impl(Lexer::new(source))
+}
+
+fn recognize_template_list_impl(mut lexer: Lexer) -> bool {
     match lexer.next_token {
         Some((Ok(ref mut t), _)) if *t == Token::SymLessThan => *t = Token::TemplateArgsStart,
         _ => return false,
@@ -748,3 +971,127 @@ impl Iterator for Lexer<'_> {
         })
     }
 }
+
+#[test]
+fn test_nesting_limits() {
+    use crate::error::ErrorKind;
+    let parse = |source: String| crate::parse_str(&source).map(|wgsl| wgsl.to_string());
+
+    let n = MAX_NESTING_DEPTH;
+    let parens = |n| format!("const x = {}1{};", "(".repeat(n), ")".repeat(n));
+    assert!(parse(parens(n)).is_ok());
+    let err = parse(parens(n + 1)).unwrap_err();
+    assert_eq!(err.error, ErrorKind::NestingDepth(MAX_NESTING_DEPTH));
+    let blocks = |n| format!("fn f() {}{}", "{".repeat(n), "}".repeat(n));
+    assert!(parse(blocks(n)).is_ok());
+    assert!(parse(blocks(n + 1)).is_err());
+    // nested templates also used to take exponential time.
+    let arrays = |n| format!("alias a = {}u32{};", "array<".repeat(n), ">".repeat(n));
+    assert!(parse(arrays(n - 1)).is_ok());
+    assert!(parse(arrays(n + 1)).is_err());
+
+    // long flat expressions are valid WGSL, they parse by default.
+    let n = 600;
+    let sum = |n| format!("const x = 1{};", "+1".repeat(n));
+    assert!(parse(sum(n)).is_ok());
+    let body = format!("fn f(x: f32) -> f32 {{ return x{}; }}", " + x".repeat(n));
+    assert!(parse(body).is_ok());
+
+    // the number of operators can be limited, e.g. for untrusted input.
+    let max = 512;
+    let limits = crate::ParseLimits {
+        max_expression_operators: Some(max),
+        ..Default::default()
+    };
+    let parse = |source: String| crate::parse_str_with_limits(&source, limits);
+    let n = max as usize;
+    assert!(parse(sum(n)).is_ok());
+    let err = parse(sum(n + 1)).unwrap_err();
+    assert_eq!(err.error, ErrorKind::ExpressionOperators(max));
+    // operators in brackets count towards the enclosing expression.
+    let nested = format!("const x = {}1{};", "(1+".repeat(300), ")".repeat(300));
+    assert!(parse(nested).is_err());
+    // but list items and statements do not.
+    let list = format!("const x = array(1{});", ", -1".repeat(2 * n));
+    assert!(parse(list).is_ok());
+    let body = format!("fn f() {{ {} }}", "x = -1 + 2;".repeat(2 * n));
+    assert!(parse(body).is_ok());
+}
+
+#[test]
+fn test_literal_range() {
+    use crate::error::ErrorKind;
+    let source = "const x = 4294967295u;";
+    assert!(crate::parse_str(source).is_ok());
+
+    let source = "const x = 4294967296u;";
+    let err = crate::parse_str(source).unwrap_err();
+    assert_eq!(
+        err.error,
+        ErrorKind::LiteralRange("4294967296u".to_string(), "u32")
+    );
+    assert_eq!(&source[err.span.range()], "4294967296u");
+    assert_eq!(
+        err.error.to_string(),
+        "literal `4294967296u` is out of range for `u32`"
+    );
+
+    let err = crate::parse_str("const x = 2147483648i;").unwrap_err();
+    assert!(matches!(err.error, ErrorKind::LiteralRange(_, "i32")));
+    let err = crate::parse_str("const x = 1e39f;").unwrap_err();
+    assert!(matches!(err.error, ErrorKind::LiteralRange(_, "f32")));
+    let err = crate::parse_str("const x = 70000h;").unwrap_err();
+    assert!(matches!(err.error, ErrorKind::LiteralRange(_, "f16")));
+}
+
+#[cfg(feature = "imports")]
+#[test]
+fn test_template_module_paths() {
+    assert!(recognize_template_list("<package::util::Light, 2>"));
+    assert!(recognize_template_list("<super::super::Scalar>"));
+    assert!(recognize_template_list("<util::Light, util::N>"));
+
+    use crate::syntax::{Expression, GlobalDeclaration};
+    let wgsl = crate::parse_str("const a = array<package::util::Light, 2>(x, y);").unwrap();
+    let GlobalDeclaration::Declaration(decl) = &wgsl.global_declarations[0] else {
+        panic!("expected a declaration")
+    };
+    let Some(Expression::FunctionCall(call)) = decl.initializer.as_deref() else {
+        panic!("expected a constructor")
+    };
+    let args = call.ty.template_args.as_ref().unwrap();
+    let Expression::TypeOrIdentifier(ty) = args[0].expression.node() else {
+        panic!("expected a type")
+    };
+    assert_eq!(ty.path.as_ref().unwrap().to_string(), "package::util");
+    assert_eq!(*ty.ident.name(), "Light");
+}
+
+#[cfg(feature = "imports")]
+#[test]
+fn test_base_directive() {
+    let wgsl = crate::parse_str("// comment\n@base ( \"shaders/lib.v2\" );\nconst a = 1;").unwrap();
+    assert_eq!(wgsl.base.as_deref(), Some("shaders/lib.v2"));
+    // not the leading directive: an attribute named `base`.
+    let wgsl = crate::parse_str("const a = 1;\n@base(1) fn f() {}").unwrap();
+    assert_eq!(wgsl.base, None);
+    assert!(crate::parse_str("const a = 1;\n@base(\"x\");").is_err());
+}
+
+#[cfg(feature = "imports")]
+#[test]
+fn test_deprecated_attribute() {
+    use crate::syntax::{Attribute, GlobalDeclaration};
+    let wgsl = crate::parse_str("@deprecated(\"use g\") fn f() {}\n@deprecated fn g() {}").unwrap();
+    let attrs = |i: usize| match &wgsl.global_declarations[i] {
+        GlobalDeclaration::Function(f) => f.attributes.clone(),
+        _ => unreachable!(),
+    };
+    assert_eq!(attrs(0), [Attribute::Deprecated(Some("use g".to_string()))]);
+    assert_eq!(attrs(1), [Attribute::Deprecated(None)]);
+    assert!(wgsl
+        .to_string()
+        .starts_with("@deprecated(\"use g\")\nfn f()"));
+    assert!(crate::parse_str("@deprecated(g) fn f() {}").is_err());
+    assert!(crate::parse_str("@vertex(\"x\") fn f() {}").is_err());
+}
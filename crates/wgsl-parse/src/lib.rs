@@ -9,7 +9,15 @@ mod parser;
 mod parser_support;
 mod syntax_display;
 mod syntax_impl;
+#[cfg(feature = "serde")]
+mod syntax_json;
+mod syntax_tree;
 
 pub use error::Error;
-pub use parser::{parse_str, recognize_str};
-pub use syntax_impl::Decorated;
+pub use parser::{
+    parse_str, parse_str_with_limits, parse_str_with_spans, recognize_str, ParseLimits, SyntaxSpans,
+};
+pub use syntax_display::display_indented;
+pub use syntax_impl::{Decorated, FunctionSignature, ParameterSignature};
+#[cfg(feature = "serde")]
+pub use syntax_json::JSON_SCHEMA_VERSION;
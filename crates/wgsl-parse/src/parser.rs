@@ -1,11 +1,12 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use lalrpop_util::lalrpop_mod;
 
 use crate::{
     error::Error,
-    lexer::Lexer,
-    syntax::{Expression, GlobalDeclaration, GlobalDirective, Statement, TranslationUnit},
+    lexer::{Lexer, MAX_NESTING_DEPTH},
+    span::Span,
+    syntax::{Expression, GlobalDeclaration, GlobalDirective, Ident, Statement, TranslationUnit},
 };
 
 lalrpop_mod!(
@@ -17,13 +18,70 @@ lalrpop_mod!(
     wgsl_recognize
 );
 
+/// Limits on the depth of the syntax tree, see [`parse_str_with_limits`].
+///
+/// They bound the depth of the syntax tree, so that untrusted input cannot overflow the
+/// stack of the recursive functions that walk the tree (including `Drop`).
+/// Raise them only when the tree is processed on a thread with a larger stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum number of nested brackets: `()`, `[]`, `{}` and template lists.
+    pub max_nesting_depth: usize,
+    /// Maximum number of operators in an expression, e.g. `a + b + c + ...`. Disabled
+    /// by default.
+    ///
+    /// Binary operators nest to the left, so a long flat expression is a deep tree.
+    /// Operators nested in brackets count towards the enclosing expression, but operands
+    /// separated by `,` or `;` do not, so long lists like `array(1, 2, 3, ...)` are fine.
+    pub max_expression_operators: Option<u32>,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_nesting_depth: MAX_NESTING_DEPTH,
+            max_expression_operators: None,
+        }
+    }
+}
+
+/// Locations of the syntax nodes that have no [`Span`] in the syntax tree, see
+/// [`parse_str_with_spans`].
+#[derive(Clone, Debug, Default)]
+pub struct SyntaxSpans {
+    /// Location of each type expression, from its module path to its name, without the
+    /// template arguments. Keyed by the [`Ident`] of the type expression.
+    pub types: HashMap<Ident, Span>,
+    /// Location of each module-scope import statement, from the attributes to the
+    /// semicolon, in the order of [`TranslationUnit::imports`].
+    #[cfg(feature = "imports")]
+    pub imports: Vec<Span>,
+}
+
 /// Parse a string into a syntax tree ([`TranslationUnit`]).
 ///
 /// Identical to [`TranslationUnit::from_str`].
 pub fn parse_str(source: &str) -> Result<TranslationUnit, Error> {
+    parse_str_with_limits(source, ParseLimits::default())
+}
+
+/// Like [`parse_str`], with custom [`ParseLimits`].
+pub fn parse_str_with_limits(source: &str, limits: ParseLimits) -> Result<TranslationUnit, Error> {
+    let lexer = Lexer::with_limits(source, limits);
+    let parser = wgsl::TranslationUnitParser::new();
+    parser
+        .parse(&mut Default::default(), lexer)
+        .map_err(Into::into)
+}
+
+/// Like [`parse_str`], and also return the locations of the nodes that have no
+/// [`Span`] in the syntax tree, e.g. to rewrite the source text in place.
+pub fn parse_str_with_spans(source: &str) -> Result<(TranslationUnit, SyntaxSpans), Error> {
     let lexer = Lexer::new(source);
     let parser = wgsl::TranslationUnitParser::new();
-    parser.parse(lexer).map_err(Into::into)
+    let mut spans = SyntaxSpans::default();
+    let wesl = parser.parse(&mut spans, lexer)?;
+    Ok((wesl, spans))
 }
 
 /// Test whether a string represent a valid WGSL module ([`TranslationUnit`]).
@@ -46,7 +104,9 @@ impl FromStr for TranslationUnit {
     fn from_str(source: &str) -> Result<Self, Self::Err> {
         let lexer = Lexer::new(source);
         let parser = wgsl::TranslationUnitParser::new();
-        parser.parse(lexer).map_err(Into::into)
+        parser
+            .parse(&mut Default::default(), lexer)
+            .map_err(Into::into)
     }
 }
 impl FromStr for GlobalDirective {
@@ -55,7 +115,9 @@ impl FromStr for GlobalDirective {
     fn from_str(source: &str) -> Result<Self, Self::Err> {
         let lexer = Lexer::new(source);
         let parser = wgsl::GlobalDirectiveParser::new();
-        parser.parse(lexer).map_err(Into::into)
+        parser
+            .parse(&mut Default::default(), lexer)
+            .map_err(Into::into)
     }
 }
 impl FromStr for GlobalDeclaration {
@@ -64,7 +126,9 @@ impl FromStr for GlobalDeclaration {
     fn from_str(source: &str) -> Result<Self, Self::Err> {
         let lexer = Lexer::new(source);
         let parser = wgsl::GlobalDeclParser::new();
-        parser.parse(lexer).map_err(Into::into)
+        parser
+            .parse(&mut Default::default(), lexer)
+            .map_err(Into::into)
     }
 }
 impl FromStr for Statement {
@@ -73,7 +137,9 @@ impl FromStr for Statement {
     fn from_str(source: &str) -> Result<Self, Self::Err> {
         let lexer = Lexer::new(source);
         let parser = wgsl::StatementParser::new();
-        parser.parse(lexer).map_err(Into::into)
+        parser
+            .parse(&mut Default::default(), lexer)
+            .map_err(Into::into)
     }
 }
 impl FromStr for Expression {
@@ -82,7 +148,9 @@ impl FromStr for Expression {
     fn from_str(source: &str) -> Result<Self, Self::Err> {
         let lexer = Lexer::new(source);
         let parser = wgsl::ExpressionParser::new();
-        parser.parse(lexer).map_err(Into::into)
+        parser
+            .parse(&mut Default::default(), lexer)
+            .map_err(Into::into)
     }
 }
 #[cfg(feature = "imports")]
@@ -92,6 +160,8 @@ impl FromStr for crate::syntax::ImportStatement {
     fn from_str(source: &str) -> Result<Self, Self::Err> {
         let lexer = Lexer::new(source);
         let parser = wgsl::ImportStatementParser::new();
-        parser.parse(lexer).map_err(Into::into)
+        parser
+            .parse(&mut Default::default(), lexer)
+            .map_err(Into::into)
     }
 }
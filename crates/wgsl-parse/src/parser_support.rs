@@ -321,6 +321,11 @@ pub(crate) fn parse_attribute(
         },
         #[cfg(feature = "generics")]
         "type" => parse_attr_type(args).map(Attribute::Type),
+        #[cfg(feature = "imports")]
+        "deprecated" => match zero_args(args) {
+            true => Ok(Attribute::Deprecated(None)),
+            false => Err(E::Attribute("deprecated", "expected a string message")),
+        },
         _ => Ok(Attribute::Custom(CustomAttribute {
             name,
             arguments: args,
@@ -328,6 +333,15 @@ pub(crate) fn parse_attribute(
     }
 }
 
+// format: @deprecated("message")
+#[cfg(feature = "imports")]
+pub(crate) fn parse_string_attribute(name: String, message: String) -> Result<Attribute, E> {
+    match name.as_str() {
+        "deprecated" => Ok(Attribute::Deprecated(Some(message))),
+        _ => Err(E::StringArgument(name)),
+    }
+}
+
 // format: @type(T, foo | bar | baz)
 #[cfg(feature = "generics")]
 fn parse_attr_type(arguments: Option<Vec<ExpressionNode>>) -> Result<TypeConstraint, E> {
@@ -33,8 +33,14 @@ use serde::{Deserialize, Serialize};
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct TranslationUnit {
+    /// The `@base("path")` directive: relative imports of the module are resolved as if
+    /// the module was in the directory `path`, relative to the package root.
+    #[cfg(feature = "imports")]
+    pub base: Option<String>,
     #[cfg(feature = "imports")]
     pub imports: Vec<ImportStatement>,
+    #[cfg(feature = "imports")]
+    pub exports: Vec<ExportStatement>,
     pub global_directives: Vec<GlobalDirective>,
     pub global_declarations: Vec<GlobalDeclaration>,
 }
@@ -85,12 +91,20 @@ impl std::hash::Hash for Ident {
     }
 }
 
+/// An import statement, e.g. `import package::a::b::thing;`.
+///
+/// The last segment of the import is always the imported item, the segments before it
+/// are the module path: here the module is `package::a::b` and the item is `thing`.
+/// With a collection (`import package::a::{b::thing, other};`), the rule applies to each
+/// member after joining its segments to the statement's `path`.
 #[cfg(feature = "imports")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct ImportStatement {
     #[cfg(feature = "attributes")]
     pub attributes: Attributes,
+    /// `import type a::Thing;`: the items are only used in type positions, not as values.
+    pub type_only: bool,
     pub path: ModulePath,
     pub content: ImportContent,
 }
@@ -112,6 +126,8 @@ pub struct ModulePath {
     pub components: Vec<String>,
 }
 
+/// A member of an import collection. `path` are the module path segments preceding the
+/// item or nested collection.
 #[cfg(feature = "imports")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -136,6 +152,15 @@ pub struct ImportItem {
     pub rename: Option<Ident>,
 }
 
+/// Re-export of imported items, e.g. `export foo;` or `export { foo, bar as baz };`.
+/// Other modules can import the items from the exporting module.
+#[cfg(feature = "imports")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportStatement {
+    pub items: Vec<ImportItem>,
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, From, IsVariant, Unwrap)]
 pub enum GlobalDirective {
@@ -372,6 +397,9 @@ pub enum Attribute {
     Else,
     #[cfg(feature = "generics")]
     Type(TypeConstraint),
+    /// `@deprecated` or `@deprecated("message")`.
+    #[cfg(feature = "imports")]
+    Deprecated(Option<String>),
     Custom(CustomAttribute),
 }
 
@@ -534,6 +562,9 @@ pub type StatementNode = Spanned<Statement>;
 #[derive(Clone, Debug, PartialEq)]
 pub struct CompoundStatement {
     pub attributes: Attributes,
+    /// Block-scoped imports: the imported names are only visible inside the block.
+    #[cfg(feature = "imports")]
+    pub imports: Vec<ImportStatement>,
     pub statements: Vec<StatementNode>,
 }
 
@@ -1,6 +1,9 @@
 use crate::{span::Spanned, syntax::*};
 use core::fmt;
-use std::fmt::{Display, Formatter};
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter},
+};
 
 use itertools::Itertools;
 
@@ -19,11 +22,27 @@ impl<T: Display> Display for Spanned<T> {
     }
 }
 
+thread_local! {
+    /// The indentation of one nesting level. See [`display_indented`].
+    static INDENT: RefCell<String> = RefCell::new("    ".to_string());
+}
+
+/// Display a syntax node with `indent` as the indentation of one nesting level, e.g. a
+/// tab. The [`Display`] implementations of the syntax tree indent with 4 spaces.
+pub fn display_indented<'a>(node: &'a impl Display, indent: &'a str) -> impl Display + 'a {
+    FormatFn(move |f| {
+        let prev = INDENT.replace(indent.to_string());
+        let res = node.fmt(f);
+        INDENT.set(prev);
+        res
+    })
+}
+
 struct Indent<T: Display>(pub T);
 
 impl<T: Display> Display for Indent<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let indent = "    ";
+        let indent = INDENT.with_borrow(String::clone);
         let inner_display = self.0.to_string();
         let fmt = inner_display
             .lines()
@@ -35,12 +54,22 @@ impl<T: Display> Display for Indent<T> {
 
 impl Display for TranslationUnit {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "imports")]
+        if let Some(base) = &self.base {
+            writeln!(f, "@base(\"{base}\");\n")?;
+        }
         #[cfg(feature = "imports")]
         if !self.imports.is_empty() {
             for import in &self.imports {
                 writeln!(f, "import {import}\n")?;
             }
         }
+        #[cfg(feature = "imports")]
+        if !self.exports.is_empty() {
+            for export in &self.exports {
+                writeln!(f, "{export}\n")?;
+            }
+        }
         if !self.global_directives.is_empty() {
             let directives = self.global_directives.iter().format("\n");
             write!(f, "{directives}\n\n")?;
@@ -67,7 +96,26 @@ impl Display for ImportStatement {
         write!(f, "{}", fmt_attrs(&self.attributes, false))?;
         let path = &self.path;
         let content = &self.content;
-        write!(f, "{path}::{content};")
+        let ty = if self.type_only { "type " } else { "" };
+        write!(f, "{ty}{path}::{content};")
+    }
+}
+
+#[cfg(feature = "imports")]
+impl Display for ExportStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let items = self.items.iter().format_with(", ", |item, f| {
+            f(&item.ident)?;
+            if let Some(rename) = &item.rename {
+                f(&format_args!(" as {rename}"))?;
+            }
+            Ok(())
+        });
+        if self.items.len() == 1 {
+            write!(f, "export {items};")
+        } else {
+            write!(f, "export {{ {items} }};")
+        }
     }
 }
 
@@ -376,6 +424,10 @@ impl Display for Attribute {
             Attribute::Else => write!(f, "@else"),
             #[cfg(feature = "generics")]
             Attribute::Type(e1) => write!(f, "@type({e1})"),
+            #[cfg(feature = "imports")]
+            Attribute::Deprecated(None) => write!(f, "@deprecated"),
+            #[cfg(feature = "imports")]
+            Attribute::Deprecated(Some(message)) => write!(f, "@deprecated(\"{message}\")"),
             Attribute::Custom(custom) => {
                 let name = &custom.name;
                 let args = custom.arguments.iter().format_with("", |args, f| {
@@ -574,14 +626,18 @@ impl Display for Statement {
 
 impl Display for CompoundStatement {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", fmt_attrs(&self.attributes, false))?;
+        writeln!(f, "{}{{", fmt_attrs(&self.attributes, false))?;
+        #[cfg(feature = "imports")]
+        for import in &self.imports {
+            writeln!(f, "{}", Indent(format_args!("import {import}")))?;
+        }
         let stmts = Indent(
             self.statements
                 .iter()
                 .filter(|stmt| !matches!(stmt.node(), Statement::Void))
                 .format("\n"),
         );
-        write!(f, "{{\n{stmts}\n}}")
+        write!(f, "{stmts}\n}}")
     }
 }
 
@@ -20,6 +20,54 @@ impl TranslationUnit {
             }
         })
     }
+
+    /// Get the signature of the function declaration named `name`, if it exists.
+    pub fn function_signature(&self, name: &str) -> Option<FunctionSignature> {
+        self.global_declarations.iter().find_map(|decl| match decl {
+            GlobalDeclaration::Function(f) if *f.ident.name() == name => Some(f.signature()),
+            _ => None,
+        })
+    }
+}
+
+/// The signature of a [`Function`]: its name, parameters and return type.
+///
+/// Obtained with [`TranslationUnit::function_signature`] or [`Function::signature`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub parameters: Vec<ParameterSignature>,
+    pub return_attributes: Attributes,
+    /// `None` if the function does not return a value.
+    pub return_type: Option<TypeExpression>,
+}
+
+/// A parameter of a [`FunctionSignature`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterSignature {
+    pub name: String,
+    pub attributes: Attributes,
+    pub ty: TypeExpression,
+}
+
+impl Function {
+    /// Get the signature of the function.
+    pub fn signature(&self) -> FunctionSignature {
+        FunctionSignature {
+            name: self.ident.to_string(),
+            parameters: self
+                .parameters
+                .iter()
+                .map(|param| ParameterSignature {
+                    name: param.ident.to_string(),
+                    attributes: param.attributes.clone(),
+                    ty: param.ty.clone(),
+                })
+                .collect(),
+            return_attributes: self.return_attributes.clone(),
+            return_type: self.return_type.clone(),
+        }
+    }
 }
 
 #[cfg(feature = "imports")]
@@ -109,7 +157,7 @@ impl ModulePath {
     pub fn join_path(&self, path: &Self) -> Option<Self> {
         match path.origin {
             PathOrigin::Relative(n) => {
-                let to_keep = self.components.len().min(n) - n;
+                let to_keep = self.components.len().saturating_sub(n);
                 let components = self
                     .components
                     .iter()
@@ -140,8 +188,12 @@ impl ModulePath {
     }
     pub fn starts_with(&self, prefix: &Self) -> bool {
         self.origin == prefix.origin
-            && prefix.components.len() >= self.components.len()
-            && prefix.components.iter().zip(&self.components).all_equal()
+            && self.components.len() >= prefix.components.len()
+            && prefix
+                .components
+                .iter()
+                .zip(&self.components)
+                .all(|(a, b)| a == b)
     }
     pub fn is_empty(&self) -> bool {
         self.origin.is_package() && self.components.is_empty()
@@ -188,6 +240,16 @@ impl TypeExpression {
 }
 
 impl CompoundStatement {
+    /// Create a block without imports.
+    pub fn new(attributes: Attributes, statements: Vec<StatementNode>) -> Self {
+        Self {
+            attributes,
+            #[cfg(feature = "imports")]
+            imports: Vec::new(),
+            statements,
+        }
+    }
+
     /// Remove all [`Statement::Void`]
     pub fn remove_voids(&mut self) {
         self.statements.retain_mut(|stmt| match stmt.node_mut() {
@@ -250,6 +312,48 @@ impl From<Ident> for TypeExpression {
     }
 }
 
+impl From<WhileStatement> for LoopStatement {
+    /// `while cond { body }` is equivalent to `loop { if !(cond) { break; } body }`.
+    ///
+    /// A `continue` in the body jumps to the next iteration, where the condition is
+    /// checked again, like in the `while` loop. The loop has no continuing block.
+    fn from(stmt: WhileStatement) -> Self {
+        let span = stmt.condition.span().clone();
+        let condition = Expression::Unary(UnaryExpression {
+            operator: UnaryOperator::LogicalNegation,
+            operand: Spanned::new(
+                Expression::Parenthesized(ParenthesizedExpression {
+                    expression: stmt.condition,
+                }),
+                span.clone(),
+            ),
+        });
+        let brk = Statement::Break(BreakStatement {
+            #[cfg(feature = "attributes")]
+            attributes: Default::default(),
+        });
+        let check = Statement::If(IfStatement {
+            attributes: Default::default(),
+            if_clause: IfClause {
+                expression: Spanned::new(condition, span.clone()),
+                body: CompoundStatement::new(
+                    Default::default(),
+                    vec![Spanned::new(brk, span.clone())],
+                ),
+            },
+            else_if_clauses: Vec::new(),
+            else_clause: None,
+        });
+        let mut body = stmt.body;
+        body.statements.insert(0, Spanned::new(check, span));
+        Self {
+            attributes: stmt.attributes,
+            body,
+            continuing: None,
+        }
+    }
+}
+
 impl From<ExpressionNode> for ReturnStatement {
     fn from(expression: ExpressionNode) -> Self {
         Self {
@@ -568,3 +672,33 @@ impl_decorated_struct!(DiscardStatement);
 
 #[cfg(feature = "attributes")]
 impl_decorated_struct!(FunctionCallStatement);
+
+#[test]
+fn test_function_signature() {
+    let wgsl = crate::parse_str(
+        "fn add(a: f32, b: vec2<f32>) -> f32 { return a + b.x; }
+        @fragment fn main(@location(0) @interpolate(flat) x: u32) {}",
+    )
+    .unwrap();
+
+    let sig = wgsl.function_signature("add").unwrap();
+    assert_eq!(sig.name, "add");
+    let params = sig
+        .parameters
+        .iter()
+        .map(|param| (param.name.as_str(), param.ty.to_string()))
+        .collect_vec();
+    assert_eq!(
+        params,
+        [("a", "f32".to_string()), ("b", "vec2<f32>".to_string())]
+    );
+    assert_eq!(sig.return_type.unwrap().to_string(), "f32");
+    assert!(sig.return_attributes.is_empty());
+
+    let sig = wgsl.function_signature("main").unwrap();
+    assert_eq!(sig.return_type, None);
+    assert_eq!(sig.parameters[0].attributes.len(), 2);
+    assert_eq!(sig.parameters[0].ty.to_string(), "u32");
+
+    assert_eq!(wgsl.function_signature("missing"), None);
+}
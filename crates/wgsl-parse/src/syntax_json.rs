@@ -0,0 +1,727 @@
+//! A stable JSON representation of the syntax tree, for tools not written in Rust.
+//! See [`TranslationUnit::to_json`].
+
+use std::fmt::{Debug, Display};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    span::{Span, Spanned},
+    syntax::*,
+};
+
+/// Version of the JSON schema produced by [`TranslationUnit::to_json`].
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map(ToJson::to_json).unwrap_or(Value::Null)
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Spanned<T> {
+    fn to_json(&self) -> Value {
+        let mut json = self.node().to_json();
+        if let Value::Object(fields) = &mut json {
+            fields.insert("span".to_string(), self.span().to_json());
+        }
+        json
+    }
+}
+
+impl ToJson for Span {
+    fn to_json(&self) -> Value {
+        Value::Array(vec![Value::from(self.start), Value::from(self.end)])
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl ToJson for Ident {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+fn string(s: impl Display) -> Value {
+    Value::String(s.to_string())
+}
+
+/// A float literal, spelled like in WGSL, e.g. `2.0` and not `2` or `2.000000001`.
+fn float(n: impl Debug) -> Value {
+    format!("{n:?}")
+        .parse::<f64>()
+        .map_or(Value::Null, Value::from)
+}
+
+/// An object, fields keep their order.
+fn fields(fields: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+/// An object with a `"kind"` field first.
+fn object(kind: &'static str, rest: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    fields(std::iter::once(("kind", string(kind))).chain(rest))
+}
+
+// some nodes only have attributes with the `attributes` feature.
+macro_rules! attrs {
+    ($node:expr) => {{
+        #[cfg(feature = "attributes")]
+        let attrs = $node.attributes.to_json();
+        #[cfg(not(feature = "attributes"))]
+        let attrs = {
+            let _ = &$node;
+            Value::Array(Vec::new())
+        };
+        attrs
+    }};
+}
+
+impl TranslationUnit {
+    /// Serialize the syntax tree to a stable JSON representation, for tools not written
+    /// in Rust.
+    ///
+    /// This is distinct from the `serde` representation, which mirrors the Rust types and
+    /// changes whenever they do. The schema is versioned by [`crate::JSON_SCHEMA_VERSION`],
+    /// which is bumped on every breaking change.
+    /// Requires the `serde` feature.
+    ///
+    /// # Schema
+    ///
+    /// Every node is a JSON object. Enum-like nodes (directives, declarations, statements,
+    /// expressions) carry a `"kind"` field identifying the variant. Optional values are
+    /// `null` when absent. Expression and statement nodes carry a `"span"` field, a
+    /// `[start, end]` byte range in the source.
+    ///
+    /// * translation unit: `{ "schema_version", "base", "imports", "exports", "directives",
+    ///   "declarations" }`, where `base` is the `@base` directive path or `null`
+    /// * import: `{ "attributes", "type_only", "path", "content" }`, where `path` is
+    ///   `{ "origin": "absolute" | "relative" | "package", "depth", "components" }` and
+    ///   `content` is either `{ "kind": "item", "name", "rename" }` or
+    ///   `{ "kind": "collection", "imports": [{ "path", "content" }] }`
+    /// * export: `{ "items": [{ "name", "rename" }] }`
+    /// * directive kinds: `diagnostic { "severity", "rule" }`, `enable { "extensions" }`,
+    ///   `requires { "extensions" }`
+    /// * declaration kinds: `void`, `declaration { "declaration_kind", "address_space",
+    ///   "access_mode", "name", "type", "initializer" }`, `type_alias { "name", "type" }`,
+    ///   `struct { "name", "members": [{ "attributes", "name", "type" }] }`,
+    ///   `function { "name", "parameters": [{ "attributes", "name", "type" }],
+    ///   "return_attributes", "return_type", "body" }`, `const_assert { "expression" }`
+    /// * attribute: `{ "name", "arguments" }`, `arguments` is a list of expressions, or
+    ///   `null` for attributes without arguments. `builtin`, `interpolate` and
+    ///   `diagnostic` take string arguments instead.
+    /// * type: `{ "path", "name", "template_args" }`, `template_args` is a list of
+    ///   expressions or `null`.
+    /// * expression kinds: `literal { "type", "value" }`, `parenthesized { "expression" }`,
+    ///   `named_component { "base", "component" }`, `indexing { "base", "index" }`,
+    ///   `unary { "operator", "operand" }`, `binary { "operator", "left", "right" }`,
+    ///   `function_call { "callee", "arguments" }`, `type_or_identifier { "type" }`
+    /// * statement kinds: `void`, `compound { "imports", "statements" }`,
+    ///   `assignment { "operator", "lhs", "rhs" }`, `increment { "expression" }`,
+    ///   `decrement { "expression" }`, `if { "condition", "body", "else_ifs", "else" }`,
+    ///   `switch { "expression", "body_attributes", "clauses": [{ "attributes",
+    ///   "selectors", "body" }] }`, `loop { "body", "continuing": { "attributes", "body",
+    ///   "break_if" } }`, `for { "initializer", "condition", "update", "body" }`,
+    ///   `while { "condition", "body" }`, `break`, `continue`, `return { "expression" }`,
+    ///   `discard`, `function_call { "callee", "arguments" }`, `const_assert`,
+    ///   `declaration` (same fields as the global declarations)
+    ///
+    /// All declarations and statements have `"attributes"`. Operators, address spaces,
+    /// access modes and other keywords are spelled as in WGSL. A `default` case selector
+    /// is the string `"default"`.
+    pub fn to_json(&self) -> String {
+        #[cfg(feature = "imports")]
+        let base = self.base.as_deref().map_or(Value::Null, string);
+        #[cfg(not(feature = "imports"))]
+        let base = Value::Null;
+        #[cfg(feature = "imports")]
+        let imports = self.imports.to_json();
+        #[cfg(not(feature = "imports"))]
+        let imports = Value::Array(Vec::new());
+        #[cfg(feature = "imports")]
+        let exports = self.exports.to_json();
+        #[cfg(not(feature = "imports"))]
+        let exports = Value::Array(Vec::new());
+        fields([
+            ("schema_version", Value::from(JSON_SCHEMA_VERSION)),
+            ("base", base),
+            ("imports", imports),
+            ("exports", exports),
+            ("directives", self.global_directives.to_json()),
+            ("declarations", self.global_declarations.to_json()),
+        ])
+        .to_string()
+    }
+}
+
+#[cfg(feature = "imports")]
+impl ToJson for ImportStatement {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", attrs!(self)),
+            ("type_only", Value::Bool(self.type_only)),
+            ("path", self.path.to_json()),
+            ("content", self.content.to_json()),
+        ])
+    }
+}
+
+#[cfg(feature = "imports")]
+impl ToJson for ExportStatement {
+    fn to_json(&self) -> Value {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                fields([
+                    ("name", item.ident.to_json()),
+                    ("rename", item.rename.to_json()),
+                ])
+            })
+            .collect();
+        fields([("items", Value::Array(items))])
+    }
+}
+
+#[cfg(feature = "imports")]
+impl ToJson for ModulePath {
+    fn to_json(&self) -> Value {
+        let (origin, depth) = match self.origin {
+            PathOrigin::Absolute => ("absolute", 0),
+            PathOrigin::Relative(n) => ("relative", n),
+            PathOrigin::Package => ("package", 0),
+        };
+        fields([
+            ("origin", string(origin)),
+            ("depth", Value::from(depth)),
+            ("components", self.components.to_json()),
+        ])
+    }
+}
+
+#[cfg(feature = "imports")]
+impl ToJson for ImportContent {
+    fn to_json(&self) -> Value {
+        match self {
+            ImportContent::Item(item) => object(
+                "item",
+                [
+                    ("name", item.ident.to_json()),
+                    ("rename", item.rename.to_json()),
+                ],
+            ),
+            ImportContent::Collection(coll) => object("collection", [("imports", coll.to_json())]),
+        }
+    }
+}
+
+#[cfg(feature = "imports")]
+impl ToJson for Import {
+    fn to_json(&self) -> Value {
+        fields([
+            ("path", self.path.to_json()),
+            ("content", self.content.to_json()),
+        ])
+    }
+}
+
+impl ToJson for GlobalDirective {
+    fn to_json(&self) -> Value {
+        match self {
+            GlobalDirective::Diagnostic(d) => object(
+                "diagnostic",
+                [
+                    ("attributes", attrs!(d)),
+                    ("severity", string(&d.severity)),
+                    ("rule", d.rule_name.to_json()),
+                ],
+            ),
+            GlobalDirective::Enable(d) => object(
+                "enable",
+                [
+                    ("attributes", attrs!(d)),
+                    ("extensions", d.extensions.to_json()),
+                ],
+            ),
+            GlobalDirective::Requires(d) => object(
+                "requires",
+                [
+                    ("attributes", attrs!(d)),
+                    ("extensions", d.extensions.to_json()),
+                ],
+            ),
+        }
+    }
+}
+
+impl ToJson for GlobalDeclaration {
+    fn to_json(&self) -> Value {
+        match self {
+            GlobalDeclaration::Void => object("void", [("attributes", Value::Array(Vec::new()))]),
+            GlobalDeclaration::Declaration(decl) => decl.to_json(),
+            GlobalDeclaration::TypeAlias(decl) => object(
+                "type_alias",
+                [
+                    ("attributes", attrs!(decl)),
+                    ("name", decl.ident.to_json()),
+                    ("type", decl.ty.to_json()),
+                ],
+            ),
+            GlobalDeclaration::Struct(decl) => object(
+                "struct",
+                [
+                    ("attributes", attrs!(decl)),
+                    ("name", decl.ident.to_json()),
+                    ("members", decl.members.to_json()),
+                ],
+            ),
+            GlobalDeclaration::Function(decl) => decl.to_json(),
+            GlobalDeclaration::ConstAssert(decl) => decl.to_json(),
+        }
+    }
+}
+
+impl ToJson for Declaration {
+    fn to_json(&self) -> Value {
+        let (kind, addr_space) = match self.kind {
+            DeclarationKind::Const => ("const", None),
+            DeclarationKind::Override => ("override", None),
+            DeclarationKind::Let => ("let", None),
+            DeclarationKind::Var(addr_space) => ("var", addr_space),
+        };
+        let (addr_space, access_mode) = match addr_space {
+            Some(AddressSpace::Storage(access_mode)) => (
+                string("storage"),
+                access_mode.map(string).unwrap_or(Value::Null),
+            ),
+            Some(addr_space) => (string(addr_space), Value::Null),
+            None => (Value::Null, Value::Null),
+        };
+        object(
+            "declaration",
+            [
+                ("attributes", self.attributes.to_json()),
+                ("declaration_kind", string(kind)),
+                ("address_space", addr_space),
+                ("access_mode", access_mode),
+                ("name", self.ident.to_json()),
+                ("type", self.ty.to_json()),
+                ("initializer", self.initializer.to_json()),
+            ],
+        )
+    }
+}
+
+impl ToJson for StructMember {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", self.attributes.to_json()),
+            ("name", self.ident.to_json()),
+            ("type", self.ty.to_json()),
+        ])
+    }
+}
+
+impl ToJson for Function {
+    fn to_json(&self) -> Value {
+        object(
+            "function",
+            [
+                ("attributes", self.attributes.to_json()),
+                ("name", self.ident.to_json()),
+                ("parameters", self.parameters.to_json()),
+                ("return_attributes", self.return_attributes.to_json()),
+                ("return_type", self.return_type.to_json()),
+                ("body", self.body.to_json()),
+            ],
+        )
+    }
+}
+
+impl ToJson for FormalParameter {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", self.attributes.to_json()),
+            ("name", self.ident.to_json()),
+            ("type", self.ty.to_json()),
+        ])
+    }
+}
+
+impl ToJson for ConstAssert {
+    fn to_json(&self) -> Value {
+        object(
+            "const_assert",
+            [
+                ("attributes", attrs!(self)),
+                ("expression", self.expression.to_json()),
+            ],
+        )
+    }
+}
+
+impl ToJson for Attribute {
+    fn to_json(&self) -> Value {
+        fn attr(name: &'static str, args: Option<Vec<Value>>) -> Value {
+            fields([
+                ("name", string(name)),
+                ("arguments", args.map(Value::Array).unwrap_or(Value::Null)),
+            ])
+        }
+        match self {
+            Attribute::Align(e) => attr("align", Some(vec![e.to_json()])),
+            Attribute::Binding(e) => attr("binding", Some(vec![e.to_json()])),
+            Attribute::BlendSrc(e) => attr("blend_src", Some(vec![e.to_json()])),
+            Attribute::Builtin(b) => attr("builtin", Some(vec![string(b)])),
+            Attribute::Const => attr("const", None),
+            Attribute::Diagnostic(d) => attr(
+                "diagnostic",
+                Some(vec![string(&d.severity), d.rule.to_json()]),
+            ),
+            Attribute::Group(e) => attr("group", Some(vec![e.to_json()])),
+            Attribute::Id(e) => attr("id", Some(vec![e.to_json()])),
+            Attribute::Interpolate(i) => attr(
+                "interpolate",
+                Some(
+                    std::iter::once(string(i.ty))
+                        .chain(i.sampling.map(string))
+                        .collect(),
+                ),
+            ),
+            Attribute::Invariant => attr("invariant", None),
+            Attribute::Location(e) => attr("location", Some(vec![e.to_json()])),
+            Attribute::MustUse => attr("must_use", None),
+            Attribute::Size(e) => attr("size", Some(vec![e.to_json()])),
+            Attribute::WorkgroupSize(w) => attr(
+                "workgroup_size",
+                Some(
+                    std::iter::once(&w.x)
+                        .chain(&w.y)
+                        .chain(&w.z)
+                        .map(ToJson::to_json)
+                        .collect(),
+                ),
+            ),
+            Attribute::Vertex => attr("vertex", None),
+            Attribute::Fragment => attr("fragment", None),
+            Attribute::Compute => attr("compute", None),
+            #[cfg(feature = "condcomp")]
+            Attribute::If(e) => attr("if", Some(vec![e.to_json()])),
+            #[cfg(feature = "condcomp")]
+            Attribute::Elif(e) => attr("elif", Some(vec![e.to_json()])),
+            #[cfg(feature = "condcomp")]
+            Attribute::Else => attr("else", None),
+            #[cfg(feature = "generics")]
+            Attribute::Type(t) => fields([
+                ("name", string("type")),
+                ("arguments", Value::Array(vec![t.ident.to_json()])),
+                ("variants", t.variants.to_json()),
+            ]),
+            #[cfg(feature = "imports")]
+            Attribute::Deprecated(message) => attr(
+                "deprecated",
+                message.as_ref().map(|message| vec![message.to_json()]),
+            ),
+            Attribute::Custom(c) => fields([
+                ("name", c.name.to_json()),
+                ("arguments", c.arguments.to_json()),
+            ]),
+        }
+    }
+}
+
+impl ToJson for TypeExpression {
+    fn to_json(&self) -> Value {
+        #[cfg(feature = "imports")]
+        let path = self.path.to_json();
+        #[cfg(not(feature = "imports"))]
+        let path = Value::Null;
+        fields([
+            ("path", path),
+            ("name", self.ident.to_json()),
+            ("template_args", self.template_args.to_json()),
+        ])
+    }
+}
+
+impl ToJson for TemplateArg {
+    fn to_json(&self) -> Value {
+        self.expression.to_json()
+    }
+}
+
+impl ToJson for Expression {
+    fn to_json(&self) -> Value {
+        match self {
+            Expression::Literal(lit) => {
+                let (ty, value) = match lit {
+                    LiteralExpression::Bool(b) => ("bool", Value::Bool(*b)),
+                    LiteralExpression::AbstractInt(n) => ("abstract_int", Value::from(*n)),
+                    LiteralExpression::AbstractFloat(n) => ("abstract_float", float(n)),
+                    LiteralExpression::I32(n) => ("i32", Value::from(*n)),
+                    LiteralExpression::U32(n) => ("u32", Value::from(*n)),
+                    LiteralExpression::F32(n) => ("f32", float(n)),
+                    LiteralExpression::F16(n) => ("f16", float(n)),
+                };
+                object("literal", [("type", string(ty)), ("value", value)])
+            }
+            Expression::Parenthesized(e) => {
+                object("parenthesized", [("expression", e.expression.to_json())])
+            }
+            Expression::NamedComponent(e) => object(
+                "named_component",
+                [
+                    ("base", e.base.to_json()),
+                    ("component", e.component.to_json()),
+                ],
+            ),
+            Expression::Indexing(e) => object(
+                "indexing",
+                [("base", e.base.to_json()), ("index", e.index.to_json())],
+            ),
+            Expression::Unary(e) => object(
+                "unary",
+                [
+                    ("operator", string(e.operator)),
+                    ("operand", e.operand.to_json()),
+                ],
+            ),
+            Expression::Binary(e) => object(
+                "binary",
+                [
+                    ("operator", string(e.operator)),
+                    ("left", e.left.to_json()),
+                    ("right", e.right.to_json()),
+                ],
+            ),
+            Expression::FunctionCall(e) => object("function_call", call_fields(e)),
+            Expression::TypeOrIdentifier(ty) => {
+                object("type_or_identifier", [("type", ty.to_json())])
+            }
+        }
+    }
+}
+
+fn call_fields(call: &FunctionCall) -> [(&'static str, Value); 2] {
+    [
+        ("callee", call.ty.to_json()),
+        ("arguments", call.arguments.to_json()),
+    ]
+}
+
+impl ToJson for Statement {
+    fn to_json(&self) -> Value {
+        match self {
+            Statement::Void => object("void", [("attributes", Value::Array(Vec::new()))]),
+            Statement::Compound(s) => s.to_json(),
+            Statement::Assignment(s) => object(
+                "assignment",
+                [
+                    ("attributes", attrs!(s)),
+                    ("operator", string(&s.operator)),
+                    ("lhs", s.lhs.to_json()),
+                    ("rhs", s.rhs.to_json()),
+                ],
+            ),
+            Statement::Increment(s) => object(
+                "increment",
+                [
+                    ("attributes", attrs!(s)),
+                    ("expression", s.expression.to_json()),
+                ],
+            ),
+            Statement::Decrement(s) => object(
+                "decrement",
+                [
+                    ("attributes", attrs!(s)),
+                    ("expression", s.expression.to_json()),
+                ],
+            ),
+            Statement::If(s) => object(
+                "if",
+                [
+                    ("attributes", s.attributes.to_json()),
+                    ("condition", s.if_clause.expression.to_json()),
+                    ("body", s.if_clause.body.to_json()),
+                    ("else_ifs", s.else_if_clauses.to_json()),
+                    ("else", s.else_clause.to_json()),
+                ],
+            ),
+            Statement::Switch(s) => object(
+                "switch",
+                [
+                    ("attributes", s.attributes.to_json()),
+                    ("expression", s.expression.to_json()),
+                    ("body_attributes", s.body_attributes.to_json()),
+                    ("clauses", s.clauses.to_json()),
+                ],
+            ),
+            Statement::Loop(s) => object(
+                "loop",
+                [
+                    ("attributes", s.attributes.to_json()),
+                    ("body", s.body.to_json()),
+                    ("continuing", s.continuing.to_json()),
+                ],
+            ),
+            Statement::For(s) => object(
+                "for",
+                [
+                    ("attributes", s.attributes.to_json()),
+                    ("initializer", s.initializer.to_json()),
+                    ("condition", s.condition.to_json()),
+                    ("update", s.update.to_json()),
+                    ("body", s.body.to_json()),
+                ],
+            ),
+            Statement::While(s) => object(
+                "while",
+                [
+                    ("attributes", s.attributes.to_json()),
+                    ("condition", s.condition.to_json()),
+                    ("body", s.body.to_json()),
+                ],
+            ),
+            Statement::Break(s) => object("break", [("attributes", attrs!(s))]),
+            Statement::Continue(s) => object("continue", [("attributes", attrs!(s))]),
+            Statement::Return(s) => object(
+                "return",
+                [
+                    ("attributes", attrs!(s)),
+                    ("expression", s.expression.to_json()),
+                ],
+            ),
+            Statement::Discard(s) => object("discard", [("attributes", attrs!(s))]),
+            Statement::FunctionCall(s) => {
+                let [callee, arguments] = call_fields(&s.call);
+                object(
+                    "function_call",
+                    [("attributes", attrs!(s)), callee, arguments],
+                )
+            }
+            Statement::ConstAssert(s) => s.to_json(),
+            Statement::Declaration(s) => s.to_json(),
+        }
+    }
+}
+
+impl ToJson for CompoundStatement {
+    fn to_json(&self) -> Value {
+        #[cfg(feature = "imports")]
+        let imports = self.imports.to_json();
+        #[cfg(not(feature = "imports"))]
+        let imports = Value::Array(Vec::new());
+        object(
+            "compound",
+            [
+                ("attributes", self.attributes.to_json()),
+                ("imports", imports),
+                ("statements", self.statements.to_json()),
+            ],
+        )
+    }
+}
+
+impl ToJson for ElseIfClause {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", attrs!(self)),
+            ("condition", self.expression.to_json()),
+            ("body", self.body.to_json()),
+        ])
+    }
+}
+
+impl ToJson for ElseClause {
+    fn to_json(&self) -> Value {
+        fields([("attributes", attrs!(self)), ("body", self.body.to_json())])
+    }
+}
+
+impl ToJson for SwitchClause {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", attrs!(self)),
+            ("selectors", self.case_selectors.to_json()),
+            ("body", self.body.to_json()),
+        ])
+    }
+}
+
+impl ToJson for CaseSelector {
+    fn to_json(&self) -> Value {
+        match self {
+            CaseSelector::Default => string("default"),
+            CaseSelector::Expression(e) => e.to_json(),
+        }
+    }
+}
+
+impl ToJson for ContinuingStatement {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", attrs!(self)),
+            ("body", self.body.to_json()),
+            ("break_if", self.break_if.to_json()),
+        ])
+    }
+}
+
+impl ToJson for BreakIfStatement {
+    fn to_json(&self) -> Value {
+        fields([
+            ("attributes", attrs!(self)),
+            ("expression", self.expression.to_json()),
+        ])
+    }
+}
+
+#[test]
+fn test_to_json() {
+    let wgsl = crate::parse_str(
+        "const n = 2u;
+        @fragment fn f(@location(0) x: f32) -> @location(0) vec4f { return vec4f(x * 2.0); }",
+    )
+    .unwrap();
+    let expected = concat!(
+        r#"{"schema_version":1,"base":null,"imports":[],"exports":[],"directives":[],"#,
+        r#""declarations":[{"kind":"declaration","attributes":[],"#,
+        r#""declaration_kind":"const","address_space":null,"access_mode":null,"name":"n","#,
+        r#""type":null,"initializer":{"kind":"literal","type":"u32","value":2,"span":[10,"#,
+        r#"12]}},{"kind":"function","attributes":[{"name":"fragment","arguments":null}],"#,
+        r#""name":"f","parameters":[{"attributes":[{"name":"location","#,
+        r#""arguments":[{"kind":"literal","type":"abstract_int","value":0,"span":[47,"#,
+        r#"48]}]}],"name":"x","type":{"path":null,"name":"f32","template_args":null}}],"#,
+        r#""return_attributes":[{"name":"location","arguments":[{"kind":"literal","#,
+        r#""type":"abstract_int","value":0,"span":[71,72]}]}],"return_type":{"path":null,"#,
+        r#""name":"vec4f","template_args":null},"body":{"kind":"compound","attributes":[],"imports":[],"#,
+        r#""statements":[{"kind":"return","attributes":[],"#,
+        r#""expression":{"kind":"function_call","callee":{"path":null,"name":"vec4f","#,
+        r#""template_args":null},"arguments":[{"kind":"binary","operator":"*","#,
+        r#""left":{"kind":"type_or_identifier","type":{"path":null,"name":"x","#,
+        r#""template_args":null},"span":[95,96]},"right":{"kind":"literal","#,
+        r#""type":"abstract_float","value":2.0,"span":[99,102]},"span":[95,102]}],"#,
+        r#""span":[89,103]},"span":[82,104]}]}}]}"#,
+    );
+    assert_eq!(wgsl.to_json(), expected);
+}
@@ -0,0 +1,752 @@
+//! The `debug_tree` methods, which print the syntax tree as an indented tree of nodes,
+//! for debugging.
+//!
+//! The tree has the node kinds and field names of the JSON schema of
+//! `TranslationUnit::to_json`, but is printed straight from the syntax tree.
+
+use std::fmt::{Debug, Display};
+
+use crate::{span::Spanned, syntax::*};
+
+/// The indented lines of a tree.
+struct Tree {
+    out: String,
+    indent: usize,
+}
+
+impl Tree {
+    fn line(&mut self, line: impl Display) {
+        self.out.push_str(&"  ".repeat(self.indent));
+        self.out.push_str(&line.to_string());
+        self.out.push('\n');
+    }
+
+    /// Write the fields of `node`, one level deeper.
+    fn nested(&mut self, node: &(impl DebugTree + ?Sized)) {
+        self.indent += 1;
+        node.fields(self);
+        self.indent -= 1;
+    }
+
+    fn field(&mut self, key: &str, node: &(impl DebugTree + ?Sized)) {
+        match node.scalar() {
+            Some(value) => self.line(format_args!("{key}: {value}")),
+            None => {
+                self.line(format_args!("{key}:{}", kind(node)));
+                self.nested(node);
+            }
+        }
+    }
+
+    /// Absent fields are omitted.
+    fn opt(&mut self, key: &str, node: Option<&impl DebugTree>) {
+        if let Some(node) = node {
+            self.field(key, node);
+        }
+    }
+
+    /// Empty lists are omitted.
+    fn list<T: DebugTree>(&mut self, key: &str, items: impl IntoIterator<Item = T>) {
+        let mut items = items.into_iter().peekable();
+        if items.peek().is_none() {
+            return;
+        }
+        self.line(format_args!("{key}:"));
+        self.indent += 1;
+        for item in items {
+            match item.scalar() {
+                Some(value) => self.line(format_args!("- {value}")),
+                None => {
+                    self.line(format_args!("-{}", kind(&item)));
+                    self.nested(&item);
+                }
+            }
+        }
+        self.indent -= 1;
+    }
+}
+
+/// The `" kind"` of a node, or an empty string.
+fn kind(node: &(impl DebugTree + ?Sized)) -> String {
+    node.kind()
+        .map(|kind| format!(" {kind}"))
+        .unwrap_or_default()
+}
+
+trait DebugTree {
+    /// The variant of enum-like nodes, e.g. `binary`.
+    fn kind(&self) -> Option<&'static str> {
+        None
+    }
+    /// Scalars are printed on the line of their field, e.g. `name: "x"`.
+    fn scalar(&self) -> Option<String> {
+        None
+    }
+    fn fields(&self, _tree: &mut Tree) {}
+}
+
+/// A string or a number, strings are quoted.
+struct Scalar(String);
+
+fn string(s: impl Display) -> Scalar {
+    Scalar(format!("{:?}", s.to_string()))
+}
+
+fn number(n: impl Debug) -> Scalar {
+    Scalar(format!("{n:?}"))
+}
+
+impl DebugTree for Scalar {
+    fn scalar(&self) -> Option<String> {
+        Some(self.0.clone())
+    }
+}
+
+impl<T: DebugTree + ?Sized> DebugTree for &T {
+    fn kind(&self) -> Option<&'static str> {
+        (**self).kind()
+    }
+    fn scalar(&self) -> Option<String> {
+        (**self).scalar()
+    }
+    fn fields(&self, tree: &mut Tree) {
+        (**self).fields(tree)
+    }
+}
+
+impl<T: DebugTree> DebugTree for Spanned<T> {
+    fn kind(&self) -> Option<&'static str> {
+        self.node().kind()
+    }
+    fn scalar(&self) -> Option<String> {
+        self.node().scalar()
+    }
+    fn fields(&self, tree: &mut Tree) {
+        self.node().fields(tree)
+    }
+}
+
+impl DebugTree for String {
+    fn scalar(&self) -> Option<String> {
+        string(self).scalar()
+    }
+}
+
+impl DebugTree for Ident {
+    fn scalar(&self) -> Option<String> {
+        string(self).scalar()
+    }
+}
+
+// some nodes only have attributes with the `attributes` feature.
+macro_rules! attrs {
+    ($tree:expr, $node:expr) => {{
+        #[cfg(feature = "attributes")]
+        $tree.list("attributes", &$node.attributes);
+        #[cfg(not(feature = "attributes"))]
+        let _ = &$node;
+    }};
+}
+
+fn to_tree(node: &impl DebugTree, name: &str) -> String {
+    let mut tree = Tree {
+        out: node.kind().unwrap_or(name).to_string(),
+        indent: 1,
+    };
+    tree.out.push('\n');
+    node.fields(&mut tree);
+    tree.out
+}
+
+impl TranslationUnit {
+    /// Print the syntax tree as an indented tree of nodes, for debugging.
+    ///
+    /// Each line is a node kind or a field of the parent node. Unlike the [`Display`]
+    /// implementation, which prints WGSL source, this shows the structure of the tree,
+    /// e.g. how operators are nested. Spans and absent values are omitted. The node kinds
+    /// and field names are those of the JSON schema of `to_json`, but the output is not
+    /// stable.
+    pub fn debug_tree(&self) -> String {
+        to_tree(self, "translation_unit")
+    }
+}
+
+macro_rules! impl_debug_tree {
+    ($($ty:ident => $name:literal),* $(,)?) => {
+        $(
+            impl $ty {
+                /// Print the syntax tree as an indented tree of nodes, for debugging.
+                /// See [`TranslationUnit::debug_tree`].
+                pub fn debug_tree(&self) -> String {
+                    to_tree(self, $name)
+                }
+            }
+        )*
+    };
+}
+
+impl_debug_tree! {
+    GlobalDeclaration => "global_declaration",
+    Statement => "statement",
+    Expression => "expression",
+    TypeExpression => "type",
+}
+
+impl DebugTree for TranslationUnit {
+    fn fields(&self, tree: &mut Tree) {
+        #[cfg(feature = "imports")]
+        {
+            tree.opt("base", self.base.as_ref());
+            tree.list("imports", &self.imports);
+            tree.list("exports", &self.exports);
+        }
+        tree.list("directives", &self.global_directives);
+        tree.list("declarations", &self.global_declarations);
+    }
+}
+
+#[cfg(feature = "imports")]
+impl DebugTree for ImportStatement {
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.field("type_only", &number(self.type_only));
+        tree.field("path", &self.path);
+        tree.field("content", &self.content);
+    }
+}
+
+#[cfg(feature = "imports")]
+impl DebugTree for ExportStatement {
+    fn fields(&self, tree: &mut Tree) {
+        tree.list("items", &self.items);
+    }
+}
+
+#[cfg(feature = "imports")]
+impl DebugTree for ImportItem {
+    fn fields(&self, tree: &mut Tree) {
+        tree.field("name", &self.ident);
+        tree.opt("rename", self.rename.as_ref());
+    }
+}
+
+#[cfg(feature = "imports")]
+impl DebugTree for ModulePath {
+    fn fields(&self, tree: &mut Tree) {
+        let (origin, depth) = match self.origin {
+            PathOrigin::Absolute => ("absolute", 0),
+            PathOrigin::Relative(n) => ("relative", n),
+            PathOrigin::Package => ("package", 0),
+        };
+        tree.field("origin", &string(origin));
+        tree.field("depth", &number(depth));
+        tree.list("components", &self.components);
+    }
+}
+
+#[cfg(feature = "imports")]
+impl DebugTree for ImportContent {
+    fn kind(&self) -> Option<&'static str> {
+        match self {
+            ImportContent::Item(_) => Some("item"),
+            ImportContent::Collection(_) => Some("collection"),
+        }
+    }
+    fn fields(&self, tree: &mut Tree) {
+        match self {
+            ImportContent::Item(item) => item.fields(tree),
+            ImportContent::Collection(coll) => tree.list("imports", coll),
+        }
+    }
+}
+
+#[cfg(feature = "imports")]
+impl DebugTree for Import {
+    fn fields(&self, tree: &mut Tree) {
+        tree.list("path", &self.path);
+        tree.field("content", &self.content);
+    }
+}
+
+impl DebugTree for GlobalDirective {
+    fn kind(&self) -> Option<&'static str> {
+        match self {
+            GlobalDirective::Diagnostic(_) => Some("diagnostic"),
+            GlobalDirective::Enable(_) => Some("enable"),
+            GlobalDirective::Requires(_) => Some("requires"),
+        }
+    }
+    fn fields(&self, tree: &mut Tree) {
+        match self {
+            GlobalDirective::Diagnostic(d) => {
+                attrs!(tree, d);
+                tree.field("severity", &string(&d.severity));
+                tree.field("rule", &d.rule_name);
+            }
+            GlobalDirective::Enable(d) => {
+                attrs!(tree, d);
+                tree.list("extensions", &d.extensions);
+            }
+            GlobalDirective::Requires(d) => {
+                attrs!(tree, d);
+                tree.list("extensions", &d.extensions);
+            }
+        }
+    }
+}
+
+impl DebugTree for GlobalDeclaration {
+    fn kind(&self) -> Option<&'static str> {
+        match self {
+            GlobalDeclaration::Void => Some("void"),
+            GlobalDeclaration::Declaration(decl) => decl.kind(),
+            GlobalDeclaration::TypeAlias(_) => Some("type_alias"),
+            GlobalDeclaration::Struct(_) => Some("struct"),
+            GlobalDeclaration::Function(decl) => decl.kind(),
+            GlobalDeclaration::ConstAssert(decl) => decl.kind(),
+        }
+    }
+    fn fields(&self, tree: &mut Tree) {
+        match self {
+            GlobalDeclaration::Void => {}
+            GlobalDeclaration::Declaration(decl) => decl.fields(tree),
+            GlobalDeclaration::TypeAlias(decl) => {
+                attrs!(tree, decl);
+                tree.field("name", &decl.ident);
+                tree.field("type", &decl.ty);
+            }
+            GlobalDeclaration::Struct(decl) => {
+                attrs!(tree, decl);
+                tree.field("name", &decl.ident);
+                tree.list("members", &decl.members);
+            }
+            GlobalDeclaration::Function(decl) => decl.fields(tree),
+            GlobalDeclaration::ConstAssert(decl) => decl.fields(tree),
+        }
+    }
+}
+
+impl DebugTree for Declaration {
+    fn kind(&self) -> Option<&'static str> {
+        Some("declaration")
+    }
+    fn fields(&self, tree: &mut Tree) {
+        let (kind, addr_space) = match self.kind {
+            DeclarationKind::Const => ("const", None),
+            DeclarationKind::Override => ("override", None),
+            DeclarationKind::Let => ("let", None),
+            DeclarationKind::Var(addr_space) => ("var", addr_space),
+        };
+        tree.list("attributes", &self.attributes);
+        tree.field("declaration_kind", &string(kind));
+        match addr_space {
+            Some(AddressSpace::Storage(access_mode)) => {
+                tree.field("address_space", &string("storage"));
+                tree.opt("access_mode", access_mode.map(string).as_ref());
+            }
+            Some(addr_space) => tree.field("address_space", &string(addr_space)),
+            None => {}
+        }
+        tree.field("name", &self.ident);
+        tree.opt("type", self.ty.as_ref());
+        tree.opt("initializer", self.initializer.as_ref());
+    }
+}
+
+impl DebugTree for StructMember {
+    fn fields(&self, tree: &mut Tree) {
+        tree.list("attributes", &self.attributes);
+        tree.field("name", &self.ident);
+        tree.field("type", &self.ty);
+    }
+}
+
+impl DebugTree for Function {
+    fn kind(&self) -> Option<&'static str> {
+        Some("function")
+    }
+    fn fields(&self, tree: &mut Tree) {
+        tree.list("attributes", &self.attributes);
+        tree.field("name", &self.ident);
+        tree.list("parameters", &self.parameters);
+        tree.list("return_attributes", &self.return_attributes);
+        tree.opt("return_type", self.return_type.as_ref());
+        tree.field("body", &self.body);
+    }
+}
+
+impl DebugTree for FormalParameter {
+    fn fields(&self, tree: &mut Tree) {
+        tree.list("attributes", &self.attributes);
+        tree.field("name", &self.ident);
+        tree.field("type", &self.ty);
+    }
+}
+
+impl DebugTree for ConstAssert {
+    fn kind(&self) -> Option<&'static str> {
+        Some("const_assert")
+    }
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.field("expression", &self.expression);
+    }
+}
+
+/// An attribute argument: an expression or a name.
+enum AttrArg<'a> {
+    Expr(&'a ExpressionNode),
+    Name(Scalar),
+}
+
+impl DebugTree for AttrArg<'_> {
+    fn kind(&self) -> Option<&'static str> {
+        match self {
+            AttrArg::Expr(e) => e.kind(),
+            AttrArg::Name(_) => None,
+        }
+    }
+    fn scalar(&self) -> Option<String> {
+        match self {
+            AttrArg::Expr(_) => None,
+            AttrArg::Name(name) => name.scalar(),
+        }
+    }
+    fn fields(&self, tree: &mut Tree) {
+        if let AttrArg::Expr(e) = self {
+            e.fields(tree)
+        }
+    }
+}
+
+impl DebugTree for Attribute {
+    fn fields(&self, tree: &mut Tree) {
+        use AttrArg::{Expr, Name};
+        let (name, args) = match self {
+            Attribute::Align(e) => ("align", Some(vec![Expr(e)])),
+            Attribute::Binding(e) => ("binding", Some(vec![Expr(e)])),
+            Attribute::BlendSrc(e) => ("blend_src", Some(vec![Expr(e)])),
+            Attribute::Builtin(b) => ("builtin", Some(vec![Name(string(b))])),
+            Attribute::Const => ("const", None),
+            Attribute::Diagnostic(d) => (
+                "diagnostic",
+                Some(vec![Name(string(&d.severity)), Name(string(&d.rule))]),
+            ),
+            Attribute::Group(e) => ("group", Some(vec![Expr(e)])),
+            Attribute::Id(e) => ("id", Some(vec![Expr(e)])),
+            Attribute::Interpolate(i) => (
+                "interpolate",
+                Some(
+                    std::iter::once(Name(string(i.ty)))
+                        .chain(i.sampling.map(|s| Name(string(s))))
+                        .collect(),
+                ),
+            ),
+            Attribute::Invariant => ("invariant", None),
+            Attribute::Location(e) => ("location", Some(vec![Expr(e)])),
+            Attribute::MustUse => ("must_use", None),
+            Attribute::Size(e) => ("size", Some(vec![Expr(e)])),
+            Attribute::WorkgroupSize(w) => (
+                "workgroup_size",
+                Some(
+                    std::iter::once(&w.x)
+                        .chain(&w.y)
+                        .chain(&w.z)
+                        .map(Expr)
+                        .collect(),
+                ),
+            ),
+            Attribute::Vertex => ("vertex", None),
+            Attribute::Fragment => ("fragment", None),
+            Attribute::Compute => ("compute", None),
+            #[cfg(feature = "condcomp")]
+            Attribute::If(e) => ("if", Some(vec![Expr(e)])),
+            #[cfg(feature = "condcomp")]
+            Attribute::Elif(e) => ("elif", Some(vec![Expr(e)])),
+            #[cfg(feature = "condcomp")]
+            Attribute::Else => ("else", None),
+            #[cfg(feature = "generics")]
+            Attribute::Type(t) => {
+                tree.field("name", &string("type"));
+                tree.list("arguments", [&t.ident]);
+                tree.list("variants", &t.variants);
+                return;
+            }
+            #[cfg(feature = "imports")]
+            Attribute::Deprecated(message) => (
+                "deprecated",
+                message.as_ref().map(|message| vec![Name(string(message))]),
+            ),
+            Attribute::Custom(c) => {
+                tree.field("name", &c.name);
+                tree.list("arguments", c.arguments.iter().flatten());
+                return;
+            }
+        };
+        tree.field("name", &string(name));
+        tree.list("arguments", args.into_iter().flatten());
+    }
+}
+
+impl DebugTree for TypeExpression {
+    fn fields(&self, tree: &mut Tree) {
+        #[cfg(feature = "imports")]
+        tree.opt("path", self.path.as_ref());
+        tree.field("name", &self.ident);
+        tree.list("template_args", self.template_args.iter().flatten());
+    }
+}
+
+impl DebugTree for TemplateArg {
+    fn kind(&self) -> Option<&'static str> {
+        self.expression.kind()
+    }
+    fn fields(&self, tree: &mut Tree) {
+        self.expression.fields(tree)
+    }
+}
+
+impl DebugTree for Expression {
+    fn kind(&self) -> Option<&'static str> {
+        Some(match self {
+            Expression::Literal(_) => "literal",
+            Expression::Parenthesized(_) => "parenthesized",
+            Expression::NamedComponent(_) => "named_component",
+            Expression::Indexing(_) => "indexing",
+            Expression::Unary(_) => "unary",
+            Expression::Binary(_) => "binary",
+            Expression::FunctionCall(_) => "function_call",
+            Expression::TypeOrIdentifier(_) => "type_or_identifier",
+        })
+    }
+    fn fields(&self, tree: &mut Tree) {
+        match self {
+            Expression::Literal(lit) => {
+                let (ty, value) = match lit {
+                    LiteralExpression::Bool(b) => ("bool", number(b)),
+                    LiteralExpression::AbstractInt(n) => ("abstract_int", number(n)),
+                    LiteralExpression::AbstractFloat(n) => ("abstract_float", number(n)),
+                    LiteralExpression::I32(n) => ("i32", number(n)),
+                    LiteralExpression::U32(n) => ("u32", number(n)),
+                    LiteralExpression::F32(n) => ("f32", number(n)),
+                    LiteralExpression::F16(n) => ("f16", number(n)),
+                };
+                tree.field("type", &string(ty));
+                tree.field("value", &value);
+            }
+            Expression::Parenthesized(e) => tree.field("expression", &e.expression),
+            Expression::NamedComponent(e) => {
+                tree.field("base", &e.base);
+                tree.field("component", &e.component);
+            }
+            Expression::Indexing(e) => {
+                tree.field("base", &e.base);
+                tree.field("index", &e.index);
+            }
+            Expression::Unary(e) => {
+                tree.field("operator", &string(e.operator));
+                tree.field("operand", &e.operand);
+            }
+            Expression::Binary(e) => {
+                tree.field("operator", &string(e.operator));
+                tree.field("left", &e.left);
+                tree.field("right", &e.right);
+            }
+            Expression::FunctionCall(e) => call_fields(e, tree),
+            Expression::TypeOrIdentifier(ty) => tree.field("type", ty),
+        }
+    }
+}
+
+fn call_fields(call: &FunctionCall, tree: &mut Tree) {
+    tree.field("callee", &call.ty);
+    tree.list("arguments", &call.arguments);
+}
+
+impl DebugTree for Statement {
+    fn kind(&self) -> Option<&'static str> {
+        Some(match self {
+            Statement::Void => "void",
+            Statement::Compound(_) => "compound",
+            Statement::Assignment(_) => "assignment",
+            Statement::Increment(_) => "increment",
+            Statement::Decrement(_) => "decrement",
+            Statement::If(_) => "if",
+            Statement::Switch(_) => "switch",
+            Statement::Loop(_) => "loop",
+            Statement::For(_) => "for",
+            Statement::While(_) => "while",
+            Statement::Break(_) => "break",
+            Statement::Continue(_) => "continue",
+            Statement::Return(_) => "return",
+            Statement::Discard(_) => "discard",
+            Statement::FunctionCall(_) => "function_call",
+            Statement::ConstAssert(_) => "const_assert",
+            Statement::Declaration(_) => "declaration",
+        })
+    }
+    fn fields(&self, tree: &mut Tree) {
+        match self {
+            Statement::Void => {}
+            Statement::Compound(s) => s.fields(tree),
+            Statement::Assignment(s) => {
+                attrs!(tree, s);
+                tree.field("operator", &string(&s.operator));
+                tree.field("lhs", &s.lhs);
+                tree.field("rhs", &s.rhs);
+            }
+            Statement::Increment(s) => {
+                attrs!(tree, s);
+                tree.field("expression", &s.expression);
+            }
+            Statement::Decrement(s) => {
+                attrs!(tree, s);
+                tree.field("expression", &s.expression);
+            }
+            Statement::If(s) => {
+                tree.list("attributes", &s.attributes);
+                tree.field("condition", &s.if_clause.expression);
+                tree.field("body", &s.if_clause.body);
+                tree.list("else_ifs", &s.else_if_clauses);
+                tree.opt("else", s.else_clause.as_ref());
+            }
+            Statement::Switch(s) => {
+                tree.list("attributes", &s.attributes);
+                tree.field("expression", &s.expression);
+                tree.list("body_attributes", &s.body_attributes);
+                tree.list("clauses", &s.clauses);
+            }
+            Statement::Loop(s) => {
+                tree.list("attributes", &s.attributes);
+                tree.field("body", &s.body);
+                tree.opt("continuing", s.continuing.as_ref());
+            }
+            Statement::For(s) => {
+                tree.list("attributes", &s.attributes);
+                tree.opt("initializer", s.initializer.as_ref());
+                tree.opt("condition", s.condition.as_ref());
+                tree.opt("update", s.update.as_ref());
+                tree.field("body", &s.body);
+            }
+            Statement::While(s) => {
+                tree.list("attributes", &s.attributes);
+                tree.field("condition", &s.condition);
+                tree.field("body", &s.body);
+            }
+            Statement::Break(s) => attrs!(tree, s),
+            Statement::Continue(s) => attrs!(tree, s),
+            Statement::Return(s) => {
+                attrs!(tree, s);
+                tree.opt("expression", s.expression.as_ref());
+            }
+            Statement::Discard(s) => attrs!(tree, s),
+            Statement::FunctionCall(s) => {
+                attrs!(tree, s);
+                call_fields(&s.call, tree);
+            }
+            Statement::ConstAssert(s) => s.fields(tree),
+            Statement::Declaration(s) => s.fields(tree),
+        }
+    }
+}
+
+impl DebugTree for CompoundStatement {
+    fn kind(&self) -> Option<&'static str> {
+        Some("compound")
+    }
+    fn fields(&self, tree: &mut Tree) {
+        tree.list("attributes", &self.attributes);
+        #[cfg(feature = "imports")]
+        tree.list("imports", &self.imports);
+        tree.list("statements", &self.statements);
+    }
+}
+
+impl DebugTree for ElseIfClause {
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.field("condition", &self.expression);
+        tree.field("body", &self.body);
+    }
+}
+
+impl DebugTree for ElseClause {
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.field("body", &self.body);
+    }
+}
+
+impl DebugTree for SwitchClause {
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.list("selectors", &self.case_selectors);
+        tree.field("body", &self.body);
+    }
+}
+
+impl DebugTree for CaseSelector {
+    fn kind(&self) -> Option<&'static str> {
+        match self {
+            CaseSelector::Default => None,
+            CaseSelector::Expression(e) => e.kind(),
+        }
+    }
+    fn scalar(&self) -> Option<String> {
+        match self {
+            CaseSelector::Default => string("default").scalar(),
+            CaseSelector::Expression(_) => None,
+        }
+    }
+    fn fields(&self, tree: &mut Tree) {
+        if let CaseSelector::Expression(e) = self {
+            e.fields(tree)
+        }
+    }
+}
+
+impl DebugTree for ContinuingStatement {
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.field("body", &self.body);
+        tree.opt("break_if", self.break_if.as_ref());
+    }
+}
+
+impl DebugTree for BreakIfStatement {
+    fn fields(&self, tree: &mut Tree) {
+        attrs!(tree, self);
+        tree.field("expression", &self.expression);
+    }
+}
+
+#[test]
+fn test_debug_tree() {
+    let wgsl = crate::parse_str("const x = -a[1] * (b + 2.0);").unwrap();
+    let GlobalDeclaration::Declaration(decl) = &wgsl.global_declarations[0] else {
+        panic!("expected a declaration")
+    };
+    let expr = decl.initializer.as_ref().unwrap();
+    let expected = r#"binary
+  operator: "*"
+  left: unary
+    operator: "-"
+    operand: indexing
+      base: type_or_identifier
+        type:
+          name: "a"
+      index: literal
+        type: "abstract_int"
+        value: 1
+  right: parenthesized
+    expression: binary
+      operator: "+"
+      left: type_or_identifier
+        type:
+          name: "b"
+      right: literal
+        type: "abstract_float"
+        value: 2.0
+"#;
+    assert_eq!(expr.debug_tree(), expected);
+}
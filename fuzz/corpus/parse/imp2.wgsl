@@ -0,0 +1,6 @@
+@if(false && hello)
+import imp3/f3;
+
+fn f2() -> vec4f {
+    return vec4f(1.0, 0.0, 0.0, 0.0);
+}
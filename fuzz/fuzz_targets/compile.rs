@@ -0,0 +1,34 @@
+//! Compilation must never panic or overflow the stack, whatever the input. The input
+//! is the root module, it can import a fixed set of modules.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wesl::{ResolveLimits, VirtualResolver, Wesl};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut resolver = VirtualResolver::new();
+    resolver.add_module("main", source.to_string().into());
+    resolver.add_module(
+        "util",
+        "import super::consts::N;
+        struct Data { x: array<u32, N> }
+        fn helper(d: Data) -> u32 { return d.x[0]; }"
+            .into(),
+    );
+    resolver.add_module("consts", "const N = 4u;".into());
+    let mut compiler = Wesl::new("").set_custom_resolver(resolver);
+    compiler.set_options(wesl::CompileOptions {
+        limits: ResolveLimits {
+            max_depth: Some(8),
+            max_total_modules: Some(16),
+        },
+        ..Default::default()
+    });
+    if let Ok(res) = compiler.compile("main") {
+        let _ = res.to_string();
+    }
+});
@@ -0,0 +1,21 @@
+//! The parser must never panic or overflow the stack, whatever the input.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = wgsl_parse::recognize_str(source);
+    // long flat expressions like `1+1+...` are deep trees, the limit is off by default.
+    let limits = wgsl_parse::ParseLimits {
+        max_expression_operators: Some(512),
+        ..Default::default()
+    };
+    if let Ok(wgsl) = wgsl_parse::parse_str_with_limits(source, limits) {
+        // display and drop are recursive too.
+        let _ = wgsl.to_string();
+    }
+});
@@ -12,8 +12,8 @@ use std::{
 use wesl::{
     eval::{ty_eval_ty, Eval, EvalAttrs, HostShareable, Instance, RefInstance, Ty},
     syntax::{self, AccessMode, AddressSpace},
-    CompileOptions, CompileResult, Diagnostic, FileResolver, ManglerKind, PkgBuilder, Router,
-    SyntaxUtil, VirtualResolver, Wesl,
+    BindingAllocator, CompileOptions, CompileResult, Diagnostic, FileResolver, ManglerKind,
+    PkgBuilder, Router, SyntaxUtil, VirtualResolver, Wesl,
 };
 use wgsl_parse::syntax::TranslationUnit;
 
@@ -99,6 +99,30 @@ struct CompOptsArgs {
     /// used.
     #[arg(long)]
     eager: bool,
+    /// Error if the output has no entry point function
+    #[arg(long)]
+    require_entry_point: bool,
+    /// Also mangle the root module declarations, except entry points and bindings
+    #[arg(long)]
+    mangle_entry_locals: bool,
+    /// Replace type aliases with the aliased types
+    #[arg(long)]
+    inline_type_aliases: bool,
+    /// Assign bindings to resources declared without `@group`/`@binding`, in this group
+    #[arg(long)]
+    auto_bindings: Option<u32>,
+    /// Rewrite `while` loops to `loop` statements with a leading break-if
+    #[arg(long)]
+    lower_while_loops: bool,
+    /// Emit the root module as written, with the imported declarations appended
+    #[arg(long)]
+    preserve_entry_verbatim: bool,
+    /// Merge identical module-scope const arrays and structs into one declaration
+    #[arg(long)]
+    dedup_consts: bool,
+    /// Check that every struct has a well-defined memory layout
+    #[arg(long)]
+    validate_layouts: bool,
     /// Disable performing validation checks with naga
     #[cfg(feature = "naga")]
     #[arg(long)]
@@ -138,6 +162,15 @@ impl From<&CompOptsArgs> for CompileOptions {
                 opts.keep.clone()
             },
             features,
+            require_entry_point: opts.require_entry_point,
+            mangle_entry_locals: opts.mangle_entry_locals,
+            inline_type_aliases: opts.inline_type_aliases,
+            auto_bindings: opts.auto_bindings.map(BindingAllocator::new),
+            lower_while_loops: opts.lower_while_loops,
+            preserve_entry_verbatim: opts.preserve_entry_verbatim,
+            dedup_consts: opts.dedup_consts,
+            validate_layouts: opts.validate_layouts,
+            ..Default::default()
         }
     }
 }
@@ -146,6 +179,9 @@ impl From<&CompOptsArgs> for CompileOptions {
 struct CompileArgs {
     #[command(flatten)]
     options: CompOptsArgs,
+    /// Output the syntax tree as JSON instead of WGSL code
+    #[arg(long)]
+    json: bool,
     /// WESL file entry point
     file: Option<PathBuf>,
 }
@@ -364,7 +400,7 @@ fn run_compile(
         .use_sourcemap(!options.no_sourcemap)
         .set_mangler(options.mangler.into());
 
-    match file_or_source {
+    let res = match file_or_source {
         FileOrSource::File(path) => {
             let base = options
                 .base
@@ -374,8 +410,7 @@ fn run_compile(
             let name = path.file_name().ok_or(CliError::FileNotFound)?;
             let resolver = FileResolver::new(base);
 
-            let res = compiler.set_custom_resolver(resolver).compile(name)?;
-            Ok(res)
+            compiler.set_custom_resolver(resolver).compile(name)?
         }
         FileOrSource::Source(source) => {
             let base = std::env::current_dir().unwrap();
@@ -386,10 +421,14 @@ fn run_compile(
             router.mount_resolver(name, resolver);
             router.mount_fallback_resolver(FileResolver::new(base));
 
-            let res = compiler.set_custom_resolver(router).compile(name)?;
-            Ok(res)
+            compiler.set_custom_resolver(router).compile(name)?
         }
+    };
+
+    for warning in &res.warnings {
+        eprintln!("{}", warning.display_warning());
     }
+    Ok(res)
 }
 
 fn parse_binding(
@@ -544,27 +583,21 @@ fn run(cli: Cli) -> Result<(), CliError> {
         Command::Compile(args) => {
             let comp = file_or_source(args.file)
                 .map(|input| run_compile(&args.options, input))
-                .unwrap_or_else(|| {
-                    Ok(CompileResult {
-                        syntax: TranslationUnit::default(),
-                        sourcemap: None,
-                    })
-                })?;
+                .unwrap_or_else(|| Ok(CompileResult::default()))?;
             #[cfg(feature = "naga")]
             if !args.options.no_naga {
                 naga_validate(&comp.to_string())?;
             }
-            println!("{}", comp);
+            if args.json {
+                println!("{}", comp.syntax.to_json());
+            } else {
+                println!("{}", comp);
+            }
         }
         Command::Eval(args) => {
             let comp = file_or_source(args.file)
                 .map(|input| run_compile(&args.options, input))
-                .unwrap_or_else(|| {
-                    Ok(CompileResult {
-                        syntax: TranslationUnit::default(),
-                        sourcemap: None,
-                    })
-                })?;
+                .unwrap_or_else(|| Ok(CompileResult::default()))?;
             let mut eval = comp.eval(&args.expr)?;
             if args.binary {
                 let buf = eval
@@ -579,12 +612,7 @@ fn run(cli: Cli) -> Result<(), CliError> {
         Command::Exec(args) => {
             let comp = file_or_source(args.file)
                 .map(|input| run_compile(&args.options, input))
-                .unwrap_or_else(|| {
-                    Ok(CompileResult {
-                        syntax: TranslationUnit::default(),
-                        sourcemap: None,
-                    })
-                })?;
+                .unwrap_or_else(|| Ok(CompileResult::default()))?;
 
             let resources = args
                 .resources